@@ -1,15 +1,20 @@
 use eframe::egui;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use crate::config::Config;
 use crate::parser::{self, Presentation};
 use crate::render;
+use crate::render::aspect::Aspect;
 use crate::render::image_cache::ImageCache;
+use crate::render::links::{self, LinkArea};
 use crate::render::transition::{
     ActiveTransition, TransitionDirection, TransitionKind, ease_in_out,
 };
-use crate::theme::Theme;
+use crate::search;
+use crate::theme::{self, Theme, ThemeFonts};
+use crate::watcher::{FileWatcher, POLL_INTERVAL};
 
 const OVERVIEW_TRANSITION_DURATION: f32 = 0.4;
 const DRAW_FADE_DURATION: f32 = 8.0;
@@ -57,14 +62,22 @@ enum ActiveDraw {
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum AppMode {
     Presentation,
-    Grid { selected: usize },
-    OverviewTransition { selected: usize, entering: bool },
+    /// Full-text search: the slide stays visible with matches marked
+    Search,
+    Grid {
+        selected: usize,
+    },
+    OverviewTransition {
+        selected: usize,
+        entering: bool,
+    },
 }
 
 struct PresentationApp {
     presentation: Presentation,
-    #[allow(dead_code)]
     file_path: PathBuf,
+    /// Watches the presentation file for live reload
+    watcher: FileWatcher,
     current_slide: usize,
     mode: AppMode,
     theme: Theme,
@@ -73,12 +86,18 @@ struct PresentationApp {
     image_cache: ImageCache,
     show_hud: bool,
     toast: Option<Toast>,
+    /// Slide number or heading being typed to jump to, while the box is open
+    jump: Option<JumpBox>,
+    /// Query and results of the `/` search
+    slide_search: SlideSearch,
     last_ctrl_c: Option<Instant>,
     last_esc: Option<Instant>,
     reveal_steps: Vec<usize>,
     max_steps: Vec<usize>,
     scroll_offsets: Vec<f32>,
     scroll_targets: Vec<f32>,
+    /// Scroll to the highlighted code lines on the next frame, after a step
+    follow_code: bool,
     frame_count: u32,
     fps: f32,
     fps_update: Instant,
@@ -88,6 +107,8 @@ struct PresentationApp {
     active_draw: ActiveDraw,
     /// Cached slide rect from last frame, used for mouse coordinate conversion
     last_slide_rect: egui::Rect,
+    /// Links drawn on the current slide last frame, in screen coordinates
+    links: Vec<LinkArea>,
     /// Which grid cell the mouse is hovering over
     hover_slide: Option<usize>,
     /// Whether to show hover effect (false when keyboard took over)
@@ -98,6 +119,14 @@ struct PresentationApp {
     grid_scroll_offset: f32,
     /// Target scroll position in grid
     grid_scroll_target: f32,
+    /// Whether the presenter view window is open
+    show_presenter: bool,
+    /// Start of the presenter view's elapsed timer
+    timer_start: Instant,
+    /// Slide aspect ratio; slides are letterboxed to it
+    aspect: Aspect,
+    /// Fonts currently installed in the egui context, `None` until the first frame
+    installed_fonts: Option<ThemeFonts>,
 }
 
 struct Toast {
@@ -131,12 +160,30 @@ impl Toast {
     }
 }
 
+/// The jump box: digits or `:` open it, Enter goes to the selected match.
+#[derive(Debug, Default)]
+struct JumpBox {
+    query: String,
+    /// Index into the current matches, moved with Up/Down
+    selected: usize,
+}
+
+/// State of the `/` search: what was typed and the slides it found.
+#[derive(Debug, Default)]
+struct SlideSearch {
+    query: String,
+    hits: Vec<search::Hit>,
+    /// Index into `hits` of the result being shown
+    selected: usize,
+}
+
 impl PresentationApp {
     fn new(file: PathBuf, presentation: Presentation, windowed: bool) -> Self {
         let _ = windowed; // used at window creation time
 
-        let theme_name = presentation.meta.theme.as_deref().unwrap_or("light");
-        let theme = Theme::from_name(theme_name);
+        let aspect = Aspect::resolve(&presentation.meta, &Config::load_or_default());
+
+        let theme = Theme::from_meta(&presentation.meta);
 
         let transition_name = presentation.meta.transition.as_deref().unwrap_or("slide");
         let default_transition = TransitionKind::from_name(transition_name);
@@ -157,10 +204,13 @@ impl PresentationApp {
         let scroll_offsets = vec![0.0; slide_count];
         let scroll_targets = vec![0.0; slide_count];
 
+        let watcher = FileWatcher::new(watched_files(&file, &presentation));
+
         let now = Instant::now();
         Self {
             presentation,
             file_path: file,
+            watcher,
             current_slide: 0,
             mode: AppMode::Presentation,
             theme,
@@ -169,12 +219,15 @@ impl PresentationApp {
             image_cache,
             show_hud: false,
             toast: None,
+            jump: None,
+            slide_search: SlideSearch::default(),
             last_ctrl_c: None,
             last_esc: None,
             reveal_steps,
             max_steps,
             scroll_offsets,
             scroll_targets,
+            follow_code: false,
             frame_count: 0,
             fps: 0.0,
             fps_update: now,
@@ -183,11 +236,16 @@ impl PresentationApp {
             arrows: Vec::new(),
             active_draw: ActiveDraw::None,
             last_slide_rect: egui::Rect::ZERO,
+            links: Vec::new(),
             hover_slide: None,
             use_hover: false,
             last_hover_pos: None,
             grid_scroll_offset: 0.0,
             grid_scroll_target: 0.0,
+            show_presenter: false,
+            timer_start: now,
+            aspect,
+            installed_fonts: None,
         }
     }
 
@@ -215,6 +273,7 @@ impl PresentationApp {
         // If we have reveal steps remaining, reveal next item
         if self.reveal_steps[idx] < self.max_steps[idx] {
             self.reveal_steps[idx] += 1;
+            self.follow_code = true;
             return;
         }
 
@@ -228,7 +287,7 @@ impl PresentationApp {
         self.transition = Some(ActiveTransition::new(
             idx,
             idx + 1,
-            self.default_transition,
+            self.transition_to(idx + 1),
             TransitionDirection::Forward,
         ));
     }
@@ -243,6 +302,7 @@ impl PresentationApp {
         // If we've revealed items, un-reveal
         if self.reveal_steps[idx] > 0 {
             self.reveal_steps[idx] -= 1;
+            self.follow_code = true;
             return;
         }
 
@@ -260,11 +320,40 @@ impl PresentationApp {
         self.transition = Some(ActiveTransition::new(
             idx,
             prev,
-            self.default_transition,
+            self.transition_to(prev),
             TransitionDirection::Backward,
         ));
     }
 
+    /// Transition used when moving to `index`: the slide's own `@transition`,
+    /// otherwise the presentation default (which `T` can cycle).
+    fn transition_to(&self, index: usize) -> TransitionKind {
+        let settings = &self.presentation.slides[index].settings;
+        if settings.transition != self.presentation.meta.transition {
+            let name = settings.transition.as_deref().unwrap_or("slide");
+            TransitionKind::from_name(name)
+        } else {
+            self.default_transition
+        }
+    }
+
+    /// Theme for the slide at `index`, honoring slide-level overrides.
+    fn slide_theme(&self, index: usize) -> Cow<'_, Theme> {
+        render::slide_theme(
+            &self.presentation.slides[index],
+            &self.presentation.meta,
+            &self.theme,
+        )
+    }
+
+    /// Jump to the slide with this `@id`, heading slug or `slide-N` anchor.
+    fn jump_to_id(&mut self, anchor: &str) {
+        match self.presentation.find_anchor(anchor) {
+            Some(index) => self.jump_to_slide(index),
+            None => self.toast = Some(Toast::new(format!("No slide matches {anchor}"))),
+        }
+    }
+
     fn jump_to_slide(&mut self, index: usize) {
         if index < self.slide_count() && self.transition.is_none() {
             let cur = self.current_slide;
@@ -274,6 +363,93 @@ impl PresentationApp {
         }
     }
 
+    /// Re-parse the presentation file after it changed on disk, keeping the
+    /// presenter on the same slide (or the nearest match).
+    fn reload(&mut self) {
+        let presentation = match parser::load(&self.file_path) {
+            Ok(p) => p,
+            Err(e) => {
+                self.toast = Some(Toast::new(format!("Reload failed: {e}")));
+                return;
+            }
+        };
+        // Whether the presenter switched between light and dark, checked
+        // before the themes reload
+        let toggled = presentation.meta.theme == self.presentation.meta.theme
+            && self.theme.name != Theme::from_meta(&self.presentation.meta).name;
+        let base_path = self.file_path.parent().unwrap_or(Path::new("."));
+        theme::load_user_themes(base_path);
+        render::syntax::load_custom_themes(base_path);
+        // Always rebuilt, since the theme's file may be what changed
+        let theme = Theme::from_meta(&presentation.meta);
+        self.theme = if toggled { theme.toggled() } else { theme };
+        if presentation.meta.transition != self.presentation.meta.transition {
+            let transition_name = presentation.meta.transition.as_deref().unwrap_or("slide");
+            self.default_transition = TransitionKind::from_name(transition_name);
+        }
+        if presentation.meta.aspect != self.presentation.meta.aspect {
+            self.aspect = Aspect::resolve(&presentation.meta, &Config::load_or_default());
+        }
+
+        let warnings: Vec<String> = theme::check_themes(&presentation)
+            .into_iter()
+            .chain(render::syntax::check_code_themes(&presentation))
+            .chain(parser::include::check_includes(&presentation))
+            .collect();
+        self.watcher
+            .set_paths(watched_files(&self.file_path, &presentation));
+
+        let old_index = self.current_slide;
+        let new_index = match_slide(&self.presentation, old_index, &presentation);
+        let old_reveal = self.reveal_steps.get(old_index).copied().unwrap_or(0);
+        let old_scroll = self.scroll_targets.get(old_index).copied().unwrap_or(0.0);
+
+        let slide_count = presentation.slides.len();
+        self.max_steps = presentation
+            .slides
+            .iter()
+            .map(|s| parser::compute_max_steps(&s.blocks))
+            .collect();
+        self.reveal_steps = vec![0; slide_count];
+        self.reveal_steps[new_index] = old_reveal.min(self.max_steps[new_index]);
+        self.scroll_offsets = vec![0.0; slide_count];
+        self.scroll_targets = vec![0.0; slide_count];
+        self.scroll_offsets[new_index] = old_scroll;
+        self.scroll_targets[new_index] = old_scroll;
+
+        self.presentation = presentation;
+        self.current_slide = new_index;
+        self.transition = None;
+        self.pen_strokes.retain(|s| s.slide_index < slide_count);
+        self.arrows.retain(|a| a.slide_index < slide_count);
+
+        match self.mode {
+            AppMode::Grid { selected } => {
+                self.mode = AppMode::Grid {
+                    selected: selected.min(slide_count - 1),
+                };
+            }
+            AppMode::OverviewTransition { selected, entering } => {
+                self.mode = AppMode::OverviewTransition {
+                    selected: selected.min(slide_count - 1),
+                    entering,
+                };
+            }
+            AppMode::Search => {
+                self.slide_search.hits =
+                    search::full_text(&self.presentation, &self.slide_search.query);
+                self.slide_search.selected = 0;
+            }
+            AppMode::Presentation => {}
+        }
+
+        let message = warnings
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "Reloaded".to_string());
+        self.toast = Some(Toast::new(message));
+    }
+
     fn toggle_theme(&mut self) {
         self.theme = self.theme.toggled();
         self.toast = Some(Toast::new(format!("Theme: {}", self.theme.name)));
@@ -283,13 +459,15 @@ impl PresentationApp {
         self.default_transition = match self.default_transition {
             TransitionKind::SlideHorizontal => TransitionKind::Fade,
             TransitionKind::Fade => TransitionKind::Spatial,
-            TransitionKind::Spatial => TransitionKind::None,
+            TransitionKind::Spatial => TransitionKind::Morph,
+            TransitionKind::Morph => TransitionKind::None,
             TransitionKind::None => TransitionKind::SlideHorizontal,
         };
         let name = match self.default_transition {
             TransitionKind::SlideHorizontal => "Slide",
             TransitionKind::Fade => "Fade",
             TransitionKind::Spatial => "Spatial",
+            TransitionKind::Morph => "Morph",
             TransitionKind::None => "None",
         };
         self.toast = Some(Toast::new(format!("Transition: {name}")));
@@ -308,10 +486,19 @@ impl PresentationApp {
     fn draw_slide(&self, ui: &egui::Ui, index: usize, rect: egui::Rect, opacity: f32, scale: f32) {
         if index < self.presentation.slides.len() {
             let reveal = self.reveal_steps.get(index).copied().unwrap_or(0);
+            let slide = &self.presentation.slides[index];
+            let theme = self.slide_theme(index);
+            if let Some(ref background) = slide.settings.background {
+                render::background::draw(ui, background, &theme, rect, opacity, &self.image_cache);
+            } else if let Cow::Owned(ref theme) = theme {
+                // Slides with their own theme paint their own background
+                let bg = Theme::with_opacity(theme.background, opacity);
+                ui.painter().rect_filled(rect, 0.0, bg);
+            }
             render::render_slide(
                 ui,
-                &self.presentation.slides[index],
-                &self.theme,
+                slide,
+                &theme,
                 rect,
                 opacity,
                 &self.image_cache,
@@ -351,7 +538,7 @@ impl PresentationApp {
         let grid_height = rect.bottom() - grid_top - padding;
 
         let cell_width = (grid_width - gap * (cols as f32 - 1.0)) / cols as f32;
-        let natural_height = cell_width * 9.0 / 16.0;
+        let natural_height = cell_width / self.aspect.ratio();
         let total_natural = rows as f32 * natural_height + (rows as f32 - 1.0) * gap;
 
         // If natural layout fits in the viewport, clamp to viewport; otherwise use natural size
@@ -380,7 +567,7 @@ impl PresentationApp {
         let gap = 12.0 * scale;
         let grid_width = rect.width() - padding * 2.0;
         let cell_width = (grid_width - gap * (cols as f32 - 1.0)) / cols as f32;
-        let cell_height = cell_width * 9.0 / 16.0;
+        let cell_height = cell_width / self.aspect.ratio();
 
         rows as f32 * cell_height + (rows as f32 - 1.0) * gap
     }
@@ -415,121 +602,30 @@ impl PresentationApp {
         egui::pos2(local.x + rect.left(), local.y + rect.top() - scroll)
     }
 
-    fn handle_mouse_input(&mut self, ctx: &egui::Context) {
-        let (primary_pressed, primary_down, secondary_pressed, secondary_down, pointer_pos) = ctx
-            .input(|i| {
-                let pp = i.pointer.button_pressed(egui::PointerButton::Primary);
-                let pd = i.pointer.button_down(egui::PointerButton::Primary);
-                let sp = i.pointer.button_pressed(egui::PointerButton::Secondary);
-                let sd = i.pointer.button_down(egui::PointerButton::Secondary);
-                let pos = i.pointer.hover_pos();
-                (pp, pd, sp, sd, pos)
-            });
-
-        let Some(pos) = pointer_pos else { return };
-        let local = self.screen_to_local(pos);
-
-        // Left button press → start PenPending
-        if primary_pressed {
-            self.active_draw = ActiveDraw::PenPending {
-                origin: local,
-                points: vec![local],
-            };
-            return;
-        }
-
-        // Right button press → start ArrowPending
-        if secondary_pressed {
-            self.active_draw = ActiveDraw::ArrowPending {
-                origin: local,
-                current: local,
-            };
-            return;
-        }
-
-        // Left button held
-        if primary_down {
-            match &mut self.active_draw {
-                ActiveDraw::PenPending { origin, points } => {
-                    points.push(local);
-                    if origin.distance(local) > DRAG_THRESHOLD {
-                        let pts = std::mem::take(points);
-                        self.active_draw = ActiveDraw::PenDrawing { points: pts };
-                    }
-                }
-                ActiveDraw::PenDrawing { points } => {
-                    points.push(local);
-                }
-                _ => {}
-            }
-            ctx.request_repaint();
-            return;
-        }
-
-        // Right button held
-        if secondary_down {
-            match &mut self.active_draw {
-                ActiveDraw::ArrowPending { origin, current } => {
-                    *current = local;
-                    if origin.distance(local) > DRAG_THRESHOLD {
-                        let from = *origin;
-                        self.active_draw = ActiveDraw::ArrowDrawing {
-                            from,
-                            current: local,
-                        };
-                    }
-                }
-                ActiveDraw::ArrowDrawing { current, .. } => {
-                    *current = local;
-                }
-                _ => {}
-            }
-            ctx.request_repaint();
-            return;
-        }
-
-        // Button released — commit or navigate
-        match std::mem::replace(&mut self.active_draw, ActiveDraw::None) {
-            ActiveDraw::PenPending { .. } => {
-                self.navigate_forward();
-            }
-            ActiveDraw::PenDrawing { points } => {
-                if points.len() >= 2 {
-                    self.pen_strokes.push(PenStroke {
-                        points,
-                        start: Instant::now(),
-                        slide_index: self.current_slide,
-                    });
-                }
-            }
-            ActiveDraw::ArrowPending { .. } => {
-                self.navigate_backward();
-            }
-            ActiveDraw::ArrowDrawing { from, current } => {
-                self.arrows.push(ArrowAnnotation {
-                    from,
-                    to: current,
-                    start: Instant::now(),
-                    slide_index: self.current_slide,
-                });
-            }
-            ActiveDraw::None => {}
-        }
-    }
-}
-
-impl eframe::App for PresentationApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.update_fps();
-
-        let mode = self.mode;
-
+    /// Handle keyboard input for the focused window. Returns viewport
+    /// commands to send once the input lock has been released.
+    fn handle_keyboard(
+        &mut self,
+        ctx: &egui::Context,
+        mode: AppMode,
+    ) -> Vec<egui::ViewportCommand> {
         // Collect viewport commands to send AFTER the input closure
         // (sending inside ctx.input() causes RwLock deadlock)
         let mut viewport_cmds: Vec<egui::ViewportCommand> = Vec::new();
 
         // Handle keyboard input
         ctx.input(|i| {
+            // The jump box takes every key while it's open
+            if self.jump.is_some() {
+                self.handle_jump_input(i);
+                return;
+            }
+            // So does the search box
+            if matches!(mode, AppMode::Search) {
+                self.handle_search_input(i);
+                return;
+            }
+
             // Quit: Q from any mode
             if i.key_pressed(egui::Key::Q) {
                 viewport_cmds.push(egui::ViewportCommand::Close);
@@ -595,6 +691,12 @@ impl eframe::App for PresentationApp {
                 return;
             }
 
+            // Presenter view: S (from any mode)
+            if i.key_pressed(egui::Key::S) {
+                self.show_presenter = !self.show_presenter;
+                return;
+            }
+
             match mode {
                 AppMode::Presentation => {
                     // Forward: Right, N, Space
@@ -635,6 +737,23 @@ impl eframe::App for PresentationApp {
                     if i.key_pressed(egui::Key::End) {
                         self.jump_to_slide(self.slide_count().saturating_sub(1));
                     }
+                    // Digits or `:` open the jump box, `/` opens search
+                    for event in &i.events {
+                        if let egui::Event::Text(text) = event {
+                            if text.chars().all(|c| c.is_ascii_digit()) {
+                                self.jump = Some(JumpBox {
+                                    query: text.clone(),
+                                    selected: 0,
+                                });
+                            } else if text == ":" {
+                                self.jump = Some(JumpBox::default());
+                            } else if text == "/" && self.transition.is_none() {
+                                self.slide_search = SlideSearch::default();
+                                self.mode = AppMode::Search;
+                                self.show_hud = false;
+                            }
+                        }
+                    }
                     // G: animate into grid overview
                     if i.key_pressed(egui::Key::G) && self.transition.is_none() {
                         self.mode = AppMode::OverviewTransition {
@@ -691,14 +810,238 @@ impl eframe::App for PresentationApp {
                 AppMode::OverviewTransition { .. } => {
                     // Block input during overview animation
                 }
+                AppMode::Search => {}
             }
         });
 
+        viewport_cmds
+    }
+
+    /// Typing, Up/Down, Enter and Esc while the jump box is open.
+    fn handle_jump_input(&mut self, i: &egui::InputState) {
+        let Some(jump) = self.jump.as_mut() else {
+            return;
+        };
+        for event in &i.events {
+            if let egui::Event::Text(text) = event {
+                jump.query.extend(text.chars().filter(|c| !c.is_control()));
+                jump.selected = 0;
+            }
+        }
+        if i.key_pressed(egui::Key::Backspace) {
+            jump.query.pop();
+            jump.selected = 0;
+        }
+        let matches = search::slide_matches(&self.presentation, &jump.query);
+        if i.key_pressed(egui::Key::ArrowDown) {
+            jump.selected = (jump.selected + 1).min(matches.len().saturating_sub(1));
+        }
+        if i.key_pressed(egui::Key::ArrowUp) {
+            jump.selected = jump.selected.saturating_sub(1);
+        }
+        if i.key_pressed(egui::Key::Escape) {
+            self.jump = None;
+        } else if i.key_pressed(egui::Key::Enter) {
+            let query = jump.query.clone();
+            match matches.get(jump.selected) {
+                Some(&index) => self.jump_to_slide(index),
+                None if !query.trim().is_empty() => {
+                    self.toast = Some(Toast::new(format!("No slide matches {query}")));
+                }
+                None => {}
+            }
+            self.jump = None;
+        }
+    }
+
+    /// Typing, Enter/Shift+Enter (or Down/Up) between results, and Esc to
+    /// leave search on the slide being shown.
+    fn handle_search_input(&mut self, i: &egui::InputState) {
+        let search = &mut self.slide_search;
+        let mut changed = false;
+        for event in &i.events {
+            if let egui::Event::Text(text) = event {
+                search
+                    .query
+                    .extend(text.chars().filter(|c| !c.is_control()));
+                changed = true;
+            }
+        }
+        if i.key_pressed(egui::Key::Backspace) {
+            changed |= search.query.pop().is_some();
+        }
+        if i.key_pressed(egui::Key::Escape) {
+            self.mode = AppMode::Presentation;
+            return;
+        }
+
+        let count = search.hits.len();
+        if changed {
+            search.hits = search::full_text(&self.presentation, &search.query);
+            search.selected = 0;
+        } else if count > 0 {
+            let back = (i.key_pressed(egui::Key::Enter) && i.modifiers.shift)
+                || i.key_pressed(egui::Key::ArrowUp);
+            let forward = (i.key_pressed(egui::Key::Enter) && !i.modifiers.shift)
+                || i.key_pressed(egui::Key::ArrowDown);
+            if forward {
+                search.selected = (search.selected + 1) % count;
+            } else if back {
+                search.selected = (search.selected + count - 1) % count;
+            } else {
+                return;
+            }
+        } else {
+            return;
+        }
+
+        if let Some(slide) = search.hits.get(search.selected).map(|hit| hit.slide) {
+            self.jump_to_slide(slide);
+            // Reveal everything so matches in later steps are visible
+            self.reveal_steps[slide] = self.max_steps[slide];
+        }
+    }
+
+    fn handle_mouse_input(&mut self, ctx: &egui::Context) {
+        let (primary_pressed, primary_down, secondary_pressed, secondary_down, pointer_pos) = ctx
+            .input(|i| {
+                let pp = i.pointer.button_pressed(egui::PointerButton::Primary);
+                let pd = i.pointer.button_down(egui::PointerButton::Primary);
+                let sp = i.pointer.button_pressed(egui::PointerButton::Secondary);
+                let sd = i.pointer.button_down(egui::PointerButton::Secondary);
+                let pos = i.pointer.hover_pos();
+                (pp, pd, sp, sd, pos)
+            });
+        let command = ctx.input(|i| i.modifiers.command);
+
+        let Some(pos) = pointer_pos else { return };
+        let local = self.screen_to_local(pos);
+
+        // Links need Ctrl/Cmd, so a plain click on one still advances
+        let link = links::at(&self.links, pos).cloned();
+        if link.is_some() && command {
+            ctx.set_cursor_icon(egui::CursorIcon::PointingHand);
+        }
+
+        // Left button press → start PenPending
+        if primary_pressed {
+            self.active_draw = ActiveDraw::PenPending {
+                origin: local,
+                points: vec![local],
+            };
+            return;
+        }
+
+        // Right button press → start ArrowPending
+        if secondary_pressed {
+            self.active_draw = ActiveDraw::ArrowPending {
+                origin: local,
+                current: local,
+            };
+            return;
+        }
+
+        // Left button held
+        if primary_down {
+            match &mut self.active_draw {
+                ActiveDraw::PenPending { origin, points } => {
+                    points.push(local);
+                    if origin.distance(local) > DRAG_THRESHOLD {
+                        let pts = std::mem::take(points);
+                        self.active_draw = ActiveDraw::PenDrawing { points: pts };
+                    }
+                }
+                ActiveDraw::PenDrawing { points } => {
+                    points.push(local);
+                }
+                _ => {}
+            }
+            ctx.request_repaint();
+            return;
+        }
+
+        // Right button held
+        if secondary_down {
+            match &mut self.active_draw {
+                ActiveDraw::ArrowPending { origin, current } => {
+                    *current = local;
+                    if origin.distance(local) > DRAG_THRESHOLD {
+                        let from = *origin;
+                        self.active_draw = ActiveDraw::ArrowDrawing {
+                            from,
+                            current: local,
+                        };
+                    }
+                }
+                ActiveDraw::ArrowDrawing { current, .. } => {
+                    *current = local;
+                }
+                _ => {}
+            }
+            ctx.request_repaint();
+            return;
+        }
+
+        // Button released — commit, follow a link or navigate
+        match std::mem::replace(&mut self.active_draw, ActiveDraw::None) {
+            ActiveDraw::PenPending { .. } => match link {
+                Some(link) if command && link.is_internal() => self.jump_to_id(&link.url),
+                Some(link) if command => {
+                    ctx.open_url(egui::OpenUrl::new_tab(&link.url));
+                }
+                _ => self.navigate_forward(),
+            },
+            ActiveDraw::PenDrawing { points } => {
+                if points.len() >= 2 {
+                    self.pen_strokes.push(PenStroke {
+                        points,
+                        start: Instant::now(),
+                        slide_index: self.current_slide,
+                    });
+                }
+            }
+            ActiveDraw::ArrowPending { .. } => {
+                self.navigate_backward();
+            }
+            ActiveDraw::ArrowDrawing { from, current } => {
+                self.arrows.push(ArrowAnnotation {
+                    from,
+                    to: current,
+                    start: Instant::now(),
+                    slide_index: self.current_slide,
+                });
+            }
+            ActiveDraw::None => {}
+        }
+    }
+}
+
+impl eframe::App for PresentationApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.update_fps();
+
+        // Live reload: pick up edits to the markdown file
+        if self.watcher.poll() {
+            self.reload();
+        }
+        ctx.request_repaint_after(POLL_INTERVAL);
+
+        let mode = self.mode;
+
+        let viewport_cmds = self.handle_keyboard(ctx, mode);
+
         // Send collected viewport commands outside the input closure
         for cmd in viewport_cmds {
             ctx.send_viewport_cmd(cmd);
         }
 
+        // Swap fonts when the theme changed (reload or toggle)
+        if self.installed_fonts.as_ref() != Some(&self.theme.fonts) {
+            theme::install_fonts(ctx, &self.theme);
+            self.installed_fonts = Some(self.theme.fonts.clone());
+            ctx.request_repaint();
+        }
+
         // Mouse input handling (presentation mode only, outside ctx.input closure)
         if matches!(mode, AppMode::Presentation) && self.transition.is_none() {
             self.handle_mouse_input(ctx);
@@ -752,9 +1095,32 @@ impl eframe::App for PresentationApp {
 
                 let scale = Self::compute_scale(rect);
 
+                // Mark search matches on the slides drawn this frame
+                let query = matches!(self.mode, AppMode::Search)
+                    .then_some(self.slide_search.query.as_str());
+                render::matches::set_query(ctx, query);
+
                 match self.mode {
-                    AppMode::Presentation => {
-                        self.draw_presentation_with_scroll(ui, ctx, rect, scale);
+                    AppMode::Presentation | AppMode::Search => {
+                        // Letterbox the slide to the presentation's aspect ratio
+                        let slide_rect = self.aspect.fit(rect);
+                        if slide_rect != rect {
+                            ui.painter().rect_filled(rect, 0.0, egui::Color32::BLACK);
+                            ui.painter().rect_filled(slide_rect, 0.0, bg);
+                        }
+                        let mut slide_ui = ui.new_child(
+                            egui::UiBuilder::new()
+                                .max_rect(slide_rect)
+                                .id_salt("letterbox"),
+                        );
+                        slide_ui.shrink_clip_rect(slide_rect);
+                        let slide_scale = self.aspect.scale(slide_rect);
+                        self.draw_presentation_with_scroll(
+                            &mut slide_ui,
+                            ctx,
+                            slide_rect,
+                            slide_scale,
+                        );
                     }
                     AppMode::Grid { selected } => {
                         self.draw_grid(ui, ctx, rect, selected, scale);
@@ -795,11 +1161,34 @@ impl eframe::App for PresentationApp {
                     }
                 }
 
+                // Jump box (presentation mode only)
+                if let Some(jump) = &self.jump {
+                    if matches!(self.mode, AppMode::Presentation) {
+                        draw_jump_box(ui, &self.theme, rect, scale, &self.presentation, jump);
+                    }
+                }
+
+                // Search box and results
+                if matches!(self.mode, AppMode::Search) {
+                    draw_search(
+                        ui,
+                        &self.theme,
+                        rect,
+                        scale,
+                        &self.presentation,
+                        &self.slide_search,
+                    );
+                }
+
                 // HUD overlay (presentation mode only)
                 if self.show_hud && matches!(self.mode, AppMode::Presentation) {
                     draw_hud(ui, &self.theme, rect, scale);
                 }
             });
+
+        if self.show_presenter {
+            self.show_presenter_window(ctx);
+        }
     }
 }
 
@@ -813,18 +1202,25 @@ impl PresentationApp {
     ) {
         // Cache slide rect for mouse coordinate conversion
         self.last_slide_rect = rect;
+        // Drop links and code focus registered by other views; collect this
+        // slide's below
+        links::take(ctx);
+        render::text::take_code_focus(ctx);
+        let follow_code = std::mem::take(&mut self.follow_code);
 
         // During transitions, just render normally (no scroll)
         if self.transition.is_some() {
             self.draw_presentation(ui, ctx, rect, scale);
             self.draw_annotations(ui, scale);
+            self.links.clear();
             return;
         }
 
         let idx = self.current_slide;
         let slide = &self.presentation.slides[idx];
+        let theme = self.slide_theme(idx).into_owned();
         let (content_height, available_height) =
-            render::measure_slide_content_height(ui, slide, &self.theme, rect, scale);
+            render::measure_slide_content_height(ui, slide, &theme, rect, scale);
         let overflow = content_height - available_height;
 
         if overflow <= 0.0 {
@@ -832,6 +1228,7 @@ impl PresentationApp {
             self.scroll_offsets[idx] = 0.0;
             self.scroll_targets[idx] = 0.0;
             self.draw_presentation(ui, ctx, rect, scale);
+            self.links = links::take(ctx);
             self.draw_annotations(ui, scale);
             return;
         }
@@ -856,30 +1253,40 @@ impl PresentationApp {
         let scrolled_rect = rect.translate(egui::vec2(0.0, -scroll_offset));
         let reveal = self.reveal_steps.get(idx).copied().unwrap_or(0);
         let child_ui = ui.new_child(egui::UiBuilder::new().max_rect(rect).id_salt("scroll_clip"));
+        render::background::draw_slide(&child_ui, slide, &theme, rect, 1.0, &self.image_cache);
         render::render_slide(
             &child_ui,
             slide,
-            &self.theme,
+            &theme,
             scrolled_rect,
             1.0,
             &self.image_cache,
             reveal,
             scale,
         );
+        self.links = links::take(ctx);
+
+        // Center the highlighted code lines after stepping through them
+        if let Some(focus) = render::text::take_code_focus(ctx).filter(|_| follow_code) {
+            let top = focus.top() - scrolled_rect.top();
+            let target = top + focus.height() / 2.0 - rect.height() / 2.0;
+            self.scroll_targets[idx] = target.clamp(0.0, overflow);
+            ctx.request_repaint();
+        }
 
         // Draw fade-out gradient at bottom
         let fade_h = 80.0 * scale;
         if scroll_offset < overflow - 0.5 {
-            draw_fade_gradient(ui, rect, fade_h, &self.theme, false);
+            draw_fade_gradient(ui, rect, fade_h, &theme, false);
         }
         // Draw fade-in gradient at top when scrolled
         if scroll_offset > 0.5 {
-            draw_fade_gradient(ui, rect, fade_h, &self.theme, true);
+            draw_fade_gradient(ui, rect, fade_h, &theme, true);
         }
 
         // Draw scroll indicators
-        let indicator_color = Theme::with_opacity(self.theme.foreground, 0.35);
-        let indicator_font = egui::FontId::proportional(self.theme.body_size * 0.4 * scale);
+        let indicator_color = Theme::with_opacity(theme.foreground, 0.35);
+        let indicator_font = egui::FontId::proportional(theme.body_size * 0.4 * scale);
         if scroll_offset < overflow - 0.5 {
             let galley = ui.painter().layout_no_wrap(
                 "\u{25BC}".to_string(),
@@ -955,6 +1362,16 @@ impl PresentationApp {
                     self.draw_slide(ui, from, from_rect, 1.0, scale);
                     self.draw_slide(ui, to, to_rect, 1.0, scale);
                 }
+                TransitionKind::Morph => {
+                    // Slides fade while their code blocks are captured and morphed
+                    render::morph::set_capturing(ctx, true);
+                    self.draw_slide(ui, from, rect, 1.0 - progress, scale);
+                    let from_code = render::morph::take(ctx);
+                    self.draw_slide(ui, to, rect, progress, scale);
+                    let to_code = render::morph::take(ctx);
+                    render::morph::set_capturing(ctx, false);
+                    render::morph::draw(ui, (from, to), &from_code, &to_code, progress);
+                }
                 TransitionKind::None => {
                     self.draw_slide(ui, to, rect, 1.0, scale);
                 }
@@ -968,24 +1385,17 @@ impl PresentationApp {
     }
 
     fn draw_presentation_chrome(&self, ui: &egui::Ui, rect: egui::Rect, scale: f32) {
+        let slide = &self.presentation.slides[self.current_slide];
+        let theme = self.slide_theme(self.current_slide);
+
         // Footer
-        if let Some(ref footer) = self.presentation.meta.footer {
-            let footer_color = Theme::with_opacity(self.theme.foreground, 0.4);
-            let galley = ui.painter().layout_no_wrap(
-                footer.clone(),
-                egui::FontId::proportional(14.0 * scale),
-                footer_color,
-            );
-            let pos = egui::pos2(
-                rect.center().x - galley.rect.width() / 2.0,
-                rect.bottom() - 30.0 * scale,
-            );
-            ui.painter().galley(pos, galley, footer_color);
+        if let Some(ref footer) = slide.settings.footer {
+            render::draw_footer(ui, footer, &theme, rect, scale);
         }
 
         // Slide counter
         let counter_text = format!("{} / {}", self.current_slide + 1, self.slide_count());
-        let counter_color = Theme::with_opacity(self.theme.foreground, 0.3);
+        let counter_color = Theme::with_opacity(theme.foreground, 0.3);
         let counter_galley = ui.painter().layout_no_wrap(
             counter_text,
             egui::FontId::monospace(14.0 * scale),
@@ -1000,7 +1410,7 @@ impl PresentationApp {
 
         // FPS overlay
         let fps_text = format!("{:.0} fps", self.fps);
-        let fps_color = Theme::with_opacity(self.theme.foreground, 0.3);
+        let fps_color = Theme::with_opacity(theme.foreground, 0.3);
         let fps_galley =
             ui.painter()
                 .layout_no_wrap(fps_text, egui::FontId::monospace(14.0 * scale), fps_color);
@@ -1137,7 +1547,7 @@ impl PresentationApp {
                 continue;
             }
 
-            let cell_scale = (cell_rect.width() / 1920.0).min(cell_rect.height() / 1080.0);
+            let cell_scale = self.aspect.scale(cell_rect);
 
             // Fill cell with theme background
             grid_child
@@ -1260,8 +1670,8 @@ impl PresentationApp {
             selected
         };
         let hero_cell_rect = self.grid_cell_rect(hero_index, rect, scale, 0.0);
-        let hero_rect = lerp_rect(rect, hero_cell_rect, grid_amount);
-        let hero_scale = (hero_rect.width() / 1920.0).min(hero_rect.height() / 1080.0);
+        let hero_rect = lerp_rect(self.aspect.fit(rect), hero_cell_rect, grid_amount);
+        let hero_scale = self.aspect.scale(hero_rect);
 
         let count = self.slide_count();
 
@@ -1271,7 +1681,7 @@ impl PresentationApp {
                 continue;
             }
             let cell_rect = self.grid_cell_rect(i, rect, scale, 0.0);
-            let cell_scale = (cell_rect.width() / 1920.0).min(cell_rect.height() / 1080.0);
+            let cell_scale = self.aspect.scale(cell_rect);
 
             ui.painter()
                 .rect_filled(cell_rect, 4.0 * scale, self.theme.background);
@@ -1351,7 +1761,7 @@ impl PresentationApp {
 
     /// Pen color: cyan/blue tones
     fn pen_color(&self, opacity: f32) -> egui::Color32 {
-        if self.theme.name == "dark" {
+        if self.theme.is_dark() {
             egui::Color32::from_rgba_unmultiplied(80, 200, 255, (opacity * 230.0) as u8)
         } else {
             egui::Color32::from_rgba_unmultiplied(30, 80, 200, (opacity * 230.0) as u8)
@@ -1360,7 +1770,7 @@ impl PresentationApp {
 
     /// Pen outline color: darker cyan/blue
     fn pen_outline_color(&self, opacity: f32) -> egui::Color32 {
-        if self.theme.name == "dark" {
+        if self.theme.is_dark() {
             egui::Color32::from_rgba_unmultiplied(30, 130, 180, (opacity * 140.0) as u8)
         } else {
             egui::Color32::from_rgba_unmultiplied(15, 40, 130, (opacity * 140.0) as u8)
@@ -1369,7 +1779,7 @@ impl PresentationApp {
 
     /// Arrow color: yellow-orange / red tones
     fn arrow_color(&self, opacity: f32) -> egui::Color32 {
-        if self.theme.name == "dark" {
+        if self.theme.is_dark() {
             egui::Color32::from_rgba_unmultiplied(255, 200, 50, (opacity * 230.0) as u8)
         } else {
             egui::Color32::from_rgba_unmultiplied(220, 40, 40, (opacity * 230.0) as u8)
@@ -1378,7 +1788,7 @@ impl PresentationApp {
 
     /// Arrow outline color: darker orange / red
     fn arrow_outline_color(&self, opacity: f32) -> egui::Color32 {
-        if self.theme.name == "dark" {
+        if self.theme.is_dark() {
             egui::Color32::from_rgba_unmultiplied(200, 140, 0, (opacity * 140.0) as u8)
         } else {
             egui::Color32::from_rgba_unmultiplied(150, 20, 20, (opacity * 140.0) as u8)
@@ -1533,6 +1943,304 @@ impl PresentationApp {
     }
 }
 
+impl PresentationApp {
+    /// Show the presenter view in a second native window. It shares all
+    /// navigation state with the main window, so keys work in either one.
+    fn show_presenter_window(&mut self, ctx: &egui::Context) {
+        let title = format!("{} — Presenter", self.display_title());
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("mdeck_presenter"),
+            egui::ViewportBuilder::default()
+                .with_title(title)
+                .with_inner_size([1280.0, 800.0]),
+            |ctx, class| {
+                if class == egui::ViewportClass::Embedded {
+                    // The backend can't open another window: show it inline
+                    let mut open = true;
+                    egui::Window::new("Presenter")
+                        .open(&mut open)
+                        .default_size([960.0, 600.0])
+                        .show(ctx, |ui| {
+                            let (rect, _) =
+                                ui.allocate_exact_size(ui.available_size(), egui::Sense::hover());
+                            self.draw_presenter_view(ui, rect);
+                        });
+                    self.show_presenter = open;
+                    return;
+                }
+
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_presenter = false;
+                    return;
+                }
+
+                // R: restart the elapsed timer (presenter window only)
+                if ctx.input(|i| i.key_pressed(egui::Key::R)) {
+                    self.timer_start = Instant::now();
+                }
+
+                let viewport_cmds = self.handle_keyboard(ctx, self.mode);
+                for cmd in viewport_cmds {
+                    match cmd {
+                        // Quitting from the presenter view closes the app
+                        egui::ViewportCommand::Close => {
+                            ctx.send_viewport_cmd_to(egui::ViewportId::ROOT, cmd);
+                        }
+                        _ => ctx.send_viewport_cmd(cmd),
+                    }
+                }
+                if ctx.input(|i| !i.events.is_empty()) {
+                    ctx.request_repaint_of(egui::ViewportId::ROOT);
+                }
+
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::new().inner_margin(0.0))
+                    .show(ctx, |ui| {
+                        let rect = ui.max_rect();
+                        self.draw_presenter_view(ui, rect);
+                    });
+
+                // Keep the clock ticking
+                ctx.request_repaint_after(std::time::Duration::from_secs(1));
+            },
+        );
+    }
+
+    /// Draw the presenter view: current slide, next preview, notes, timer
+    /// and clock.
+    fn draw_presenter_view(&self, ui: &mut egui::Ui, rect: egui::Rect) {
+        let theme = &self.theme;
+        ui.painter().rect_filled(rect, 0.0, theme.code_background);
+
+        let scale = (rect.width() / 1280.0).min(rect.height() / 800.0);
+        let padding = 24.0 * scale;
+        let gap = 24.0 * scale;
+        let label_h = 28.0 * scale;
+        let bar_h = 56.0 * scale;
+        let label_font = egui::FontId::proportional(15.0 * scale);
+        let label_color = Theme::with_opacity(theme.foreground, 0.5);
+
+        let content = egui::Rect::from_min_max(
+            rect.min + egui::vec2(padding, padding),
+            egui::pos2(rect.right() - padding, rect.bottom() - padding - bar_h),
+        );
+        let left_w = (content.width() - gap) * 0.62;
+        let right_w = content.width() - gap - left_w;
+
+        let idx = self.current_slide;
+        let reveal = self.reveal_steps.get(idx).copied().unwrap_or(0);
+        let max = self.max_steps.get(idx).copied().unwrap_or(0);
+
+        // Current slide
+        ui.painter().text(
+            content.min,
+            egui::Align2::LEFT_TOP,
+            "CURRENT",
+            label_font.clone(),
+            label_color,
+        );
+        let ratio = self.aspect.ratio();
+        let current_w = left_w.min((content.height() - label_h) * ratio);
+        let current_rect = egui::Rect::from_min_size(
+            content.min + egui::vec2(0.0, label_h),
+            egui::vec2(current_w, current_w / ratio),
+        );
+        self.draw_presenter_slide(ui, current_rect, idx, reveal);
+
+        // Next: the next reveal step on this slide, or the next slide
+        let right_x = content.left() + left_w + gap;
+        let next = if reveal < max {
+            Some(("NEXT STEP", idx, reveal + 1))
+        } else if idx + 1 < self.slide_count() {
+            Some(("NEXT SLIDE", idx + 1, 0))
+        } else {
+            None
+        };
+        let next_label = next.map_or("END OF PRESENTATION", |(label, _, _)| label);
+        ui.painter().text(
+            egui::pos2(right_x, content.top()),
+            egui::Align2::LEFT_TOP,
+            next_label,
+            label_font.clone(),
+            label_color,
+        );
+        let next_rect = egui::Rect::from_min_size(
+            egui::pos2(right_x, content.top() + label_h),
+            egui::vec2(right_w, right_w / ratio),
+        );
+        match next {
+            Some((_, index, step)) => self.draw_presenter_slide(ui, next_rect, index, step),
+            None => {
+                ui.painter()
+                    .rect_filled(next_rect, 4.0 * scale, theme.background);
+            }
+        }
+
+        // Notes
+        let notes_top = next_rect.bottom() + gap;
+        ui.painter().text(
+            egui::pos2(right_x, notes_top),
+            egui::Align2::LEFT_TOP,
+            "NOTES",
+            label_font.clone(),
+            label_color,
+        );
+        let notes_rect = egui::Rect::from_min_max(
+            egui::pos2(right_x, notes_top + label_h),
+            egui::pos2(content.right(), content.bottom()),
+        );
+        let mut notes_ui = ui.new_child(
+            egui::UiBuilder::new()
+                .max_rect(notes_rect)
+                .id_salt("presenter_notes"),
+        );
+        notes_ui.shrink_clip_rect(notes_rect);
+        match self
+            .presentation
+            .slides
+            .get(idx)
+            .and_then(|s| s.notes.as_deref())
+        {
+            Some(notes) => {
+                let font_size = 20.0 * scale;
+                let mut y = notes_rect.top();
+                for para in notes.split("\n\n") {
+                    let text = para.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+                    let inlines = parser::inline::parse(&text);
+                    y += render::text::draw_inlines(
+                        &notes_ui,
+                        &inlines,
+                        egui::pos2(notes_rect.left(), y),
+                        font_size,
+                        theme.foreground,
+                        notes_rect.width(),
+                    );
+                    y += font_size * 0.6;
+                }
+            }
+            None => {
+                notes_ui.painter().text(
+                    notes_rect.min,
+                    egui::Align2::LEFT_TOP,
+                    "No notes for this slide",
+                    egui::FontId::proportional(18.0 * scale),
+                    Theme::with_opacity(theme.foreground, 0.35),
+                );
+            }
+        }
+
+        // Bottom bar: elapsed timer, position, clock
+        let bar_y = rect.bottom() - padding - bar_h / 2.0;
+        let bar_font = egui::FontId::monospace(28.0 * scale);
+        let elapsed = self.timer_start.elapsed().as_secs();
+        ui.painter().text(
+            egui::pos2(content.left(), bar_y),
+            egui::Align2::LEFT_CENTER,
+            format!(
+                "{:02}:{:02}:{:02}",
+                elapsed / 3600,
+                elapsed / 60 % 60,
+                elapsed % 60
+            ),
+            bar_font.clone(),
+            theme.foreground,
+        );
+
+        let mut position = format!("Slide {} / {}", idx + 1, self.slide_count());
+        if max > 0 {
+            position.push_str(&format!("  ·  Step {reveal} / {max}"));
+        }
+        ui.painter().text(
+            egui::pos2(rect.center().x, bar_y),
+            egui::Align2::CENTER_CENTER,
+            position,
+            egui::FontId::proportional(20.0 * scale),
+            Theme::with_opacity(theme.foreground, 0.7),
+        );
+
+        ui.painter().text(
+            egui::pos2(content.right(), bar_y),
+            egui::Align2::RIGHT_CENTER,
+            chrono::Local::now().format("%H:%M").to_string(),
+            bar_font,
+            theme.foreground,
+        );
+    }
+
+    /// Draw a slide preview at a given reveal step for the presenter view.
+    fn draw_presenter_slide(
+        &self,
+        ui: &mut egui::Ui,
+        rect: egui::Rect,
+        index: usize,
+        reveal: usize,
+    ) {
+        let Some(slide) = self.presentation.slides.get(index) else {
+            return;
+        };
+        let theme = self.slide_theme(index);
+        ui.painter().rect_filled(rect, 4.0, theme.background);
+
+        let mut child = ui.new_child(
+            egui::UiBuilder::new()
+                .max_rect(rect)
+                .id_salt(("presenter_slide", index)),
+        );
+        child.shrink_clip_rect(rect);
+        if let Some(ref background) = slide.settings.background {
+            render::background::draw(&child, background, &theme, rect, 1.0, &self.image_cache);
+        }
+        render::render_slide(
+            &child,
+            slide,
+            &theme,
+            rect,
+            1.0,
+            &self.image_cache,
+            reveal,
+            self.aspect.scale(rect),
+        );
+
+        ui.painter().rect_stroke(
+            rect,
+            4.0,
+            egui::Stroke::new(1.0, Theme::with_opacity(self.theme.foreground, 0.2)),
+            egui::StrokeKind::Outside,
+        );
+    }
+}
+
+/// The deck file, every file its code blocks include and the theme files,
+/// for live reload.
+fn watched_files(file: &Path, presentation: &Presentation) -> Vec<PathBuf> {
+    let base_path = file.parent().unwrap_or(Path::new("."));
+    std::iter::once(file.to_path_buf())
+        .chain(parser::include::included_files(presentation))
+        .chain(theme::theme_files(base_path))
+        .collect()
+}
+
+/// Find the slide in `new` that best corresponds to `old_index` in `old`.
+/// Prefers the slide with the same title closest to the old position,
+/// falling back to the same index clamped to the new slide count.
+fn match_slide(old: &Presentation, old_index: usize, new: &Presentation) -> usize {
+    let last = new.slides.len().saturating_sub(1);
+    let title = old.slides.get(old_index).and_then(parser::Slide::title);
+    if let Some(title) = title {
+        let best = new
+            .slides
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.title().as_deref() == Some(title.as_str()))
+            .min_by_key(|(i, _)| i.abs_diff(old_index))
+            .map(|(i, _)| i);
+        if let Some(i) = best {
+            return i;
+        }
+    }
+    old_index.min(last)
+}
+
 fn lerp_rect(a: egui::Rect, b: egui::Rect, t: f32) -> egui::Rect {
     egui::Rect::from_min_max(
         egui::pos2(
@@ -1583,6 +2291,258 @@ fn draw_fade_gradient(ui: &egui::Ui, rect: egui::Rect, fade_h: f32, theme: &Them
     ui.painter().add(egui::Shape::mesh(mesh));
 }
 
+/// The jump box at the top of the screen: what was typed, then the matching
+/// slides with their numbers and titles, the selected one highlighted.
+fn draw_jump_box(
+    ui: &egui::Ui,
+    theme: &Theme,
+    rect: egui::Rect,
+    scale: f32,
+    presentation: &Presentation,
+    jump: &JumpBox,
+) {
+    let matches = search::slide_matches(presentation, &jump.query);
+    let text_color = Theme::with_opacity(theme.foreground, 0.9);
+    let dim_color = Theme::with_opacity(theme.foreground, 0.5);
+    let key_color = Theme::with_opacity(theme.accent, 0.9);
+
+    let padding = 16.0 * scale;
+    let line_height = 30.0 * scale;
+    let width = 420.0 * scale;
+    let rows = matches.len().max(1);
+    let height = padding * 2.0 + line_height * (rows + 1) as f32 + 8.0 * scale;
+    let box_rect = egui::Rect::from_min_size(
+        egui::pos2(rect.center().x - width / 2.0, rect.top() + 60.0 * scale),
+        egui::vec2(width, height),
+    );
+    let painter = ui.painter();
+    painter.rect_filled(
+        box_rect,
+        12.0 * scale,
+        Theme::with_opacity(theme.code_background, 0.95),
+    );
+
+    let (prompt, prompt_color) = if jump.query.is_empty() {
+        ("Slide number or heading".to_string(), dim_color)
+    } else {
+        (format!("{}\u{258f}", jump.query), text_color)
+    };
+    let galley =
+        painter.layout_no_wrap(prompt, egui::FontId::monospace(18.0 * scale), prompt_color);
+    let mut y = box_rect.top() + padding;
+    painter.galley(
+        egui::pos2(box_rect.left() + padding, y),
+        galley,
+        prompt_color,
+    );
+    y += line_height + 8.0 * scale;
+
+    if matches.is_empty() {
+        let message = if jump.query.trim().is_empty() {
+            "Type a number, or letters from a heading"
+        } else {
+            "No matching slide"
+        };
+        let galley = painter.layout_no_wrap(
+            message.to_string(),
+            egui::FontId::proportional(16.0 * scale),
+            dim_color,
+        );
+        painter.galley(egui::pos2(box_rect.left() + padding, y), galley, dim_color);
+        return;
+    }
+
+    let title_width = width - padding * 2.0 - 56.0 * scale;
+    for (row, &index) in matches.iter().enumerate() {
+        let row_rect = egui::Rect::from_min_size(
+            egui::pos2(box_rect.left() + padding / 2.0, y - 4.0 * scale),
+            egui::vec2(width - padding, line_height),
+        );
+        if row == jump.selected {
+            painter.rect_filled(
+                row_rect,
+                6.0 * scale,
+                Theme::with_opacity(theme.accent, 0.2),
+            );
+        }
+        let number = painter.layout_no_wrap(
+            format!("{}", index + 1),
+            egui::FontId::monospace(16.0 * scale),
+            key_color,
+        );
+        painter.galley(egui::pos2(box_rect.left() + padding, y), number, key_color);
+
+        let slide = &presentation.slides[index];
+        let title = slide
+            .title()
+            .or_else(|| slide.id().map(str::to_string))
+            .unwrap_or_else(|| "Untitled slide".to_string());
+        let mut job = egui::text::LayoutJob::simple_singleline(
+            title,
+            egui::FontId::proportional(16.0 * scale),
+            text_color,
+        );
+        job.wrap = egui::text::TextWrapping::truncate_at_width(title_width);
+        let galley = painter.layout_job(job);
+        painter.galley(
+            egui::pos2(box_rect.left() + padding + 56.0 * scale, y),
+            galley,
+            text_color,
+        );
+        y += line_height;
+    }
+}
+
+/// The search box along the bottom of the screen: the query, then a window
+/// of results around the selected one, each with its slide number, title and
+/// the text around the match.
+fn draw_search(
+    ui: &egui::Ui,
+    theme: &Theme,
+    rect: egui::Rect,
+    scale: f32,
+    presentation: &Presentation,
+    state: &SlideSearch,
+) {
+    const VISIBLE: usize = 5;
+    let text_color = Theme::with_opacity(theme.foreground, 0.9);
+    let dim_color = Theme::with_opacity(theme.foreground, 0.5);
+    let key_color = Theme::with_opacity(theme.accent, 0.9);
+
+    let padding = 16.0 * scale;
+    let prompt_height = 30.0 * scale;
+    let row_height = 52.0 * scale;
+    let width = (640.0 * scale).min(rect.width() - 40.0 * scale);
+    let rows = state.hits.len().clamp(1, VISIBLE);
+    let height = padding * 2.0 + prompt_height + 8.0 * scale + row_height * rows as f32;
+    let box_rect = egui::Rect::from_min_size(
+        egui::pos2(
+            rect.center().x - width / 2.0,
+            rect.bottom() - 40.0 * scale - height,
+        ),
+        egui::vec2(width, height),
+    );
+    let painter = ui.painter();
+    painter.rect_filled(
+        box_rect,
+        12.0 * scale,
+        Theme::with_opacity(theme.code_background, 0.95),
+    );
+
+    let left = box_rect.left() + padding;
+    let right = box_rect.right() - padding;
+    let mut y = box_rect.top() + padding;
+    let prompt = painter.layout_no_wrap(
+        format!("/{}\u{258f}", state.query),
+        egui::FontId::monospace(18.0 * scale),
+        text_color,
+    );
+    painter.galley(egui::pos2(left, y), prompt, text_color);
+    if !state.hits.is_empty() {
+        let count = painter.layout_no_wrap(
+            format!("{} of {}", state.selected + 1, state.hits.len()),
+            egui::FontId::proportional(15.0 * scale),
+            dim_color,
+        );
+        painter.galley(
+            egui::pos2(right - count.rect.width(), y + 2.0 * scale),
+            count,
+            dim_color,
+        );
+    }
+    y += prompt_height + 8.0 * scale;
+
+    if state.hits.is_empty() {
+        let message = if state.query.trim().is_empty() {
+            "Search headings, text, code and notes"
+        } else {
+            "No matches"
+        };
+        let galley = painter.layout_no_wrap(
+            message.to_string(),
+            egui::FontId::proportional(16.0 * scale),
+            dim_color,
+        );
+        painter.galley(egui::pos2(left, y), galley, dim_color);
+        return;
+    }
+
+    let first = state
+        .selected
+        .saturating_sub(VISIBLE / 2)
+        .min(state.hits.len().saturating_sub(VISIBLE));
+    let number_width = 48.0 * scale;
+    for (row, hit) in state.hits.iter().enumerate().skip(first).take(VISIBLE) {
+        let row_rect = egui::Rect::from_min_size(
+            egui::pos2(box_rect.left() + padding / 2.0, y - 4.0 * scale),
+            egui::vec2(width - padding, row_height - 4.0 * scale),
+        );
+        if row == state.selected {
+            painter.rect_filled(
+                row_rect,
+                6.0 * scale,
+                Theme::with_opacity(theme.accent, 0.2),
+            );
+        }
+
+        let number = painter.layout_no_wrap(
+            format!("{}", hit.slide + 1),
+            egui::FontId::monospace(16.0 * scale),
+            key_color,
+        );
+        painter.galley(egui::pos2(left, y), number, key_color);
+
+        let field = painter.layout_no_wrap(
+            hit.field.label().to_string(),
+            egui::FontId::proportional(13.0 * scale),
+            dim_color,
+        );
+        let field_width = field.rect.width();
+        painter.galley(
+            egui::pos2(right - field_width, y + 2.0 * scale),
+            field,
+            dim_color,
+        );
+
+        let text_left = left + number_width;
+        let text_width = right - text_left - field_width - 12.0 * scale;
+        let title = presentation.slides[hit.slide]
+            .title()
+            .unwrap_or_else(|| format!("Slide {}", hit.slide + 1));
+        let mut job = egui::text::LayoutJob::simple_singleline(
+            title,
+            egui::FontId::proportional(16.0 * scale),
+            text_color,
+        );
+        job.wrap = egui::text::TextWrapping::truncate_at_width(text_width);
+        painter.galley(
+            egui::pos2(text_left, y),
+            painter.layout_job(job),
+            text_color,
+        );
+
+        let font = egui::FontId::proportional(14.0 * scale);
+        let plain = egui::TextFormat::simple(font.clone(), dim_color);
+        let marked = egui::TextFormat {
+            background: render::matches::MARK_COLOR,
+            color: text_color,
+            ..egui::TextFormat::simple(font, text_color)
+        };
+        let mut job = egui::text::LayoutJob::default();
+        let (snippet, matched) = (&hit.snippet, hit.matched.clone());
+        job.append(&snippet[..matched.start], 0.0, plain.clone());
+        job.append(&snippet[matched.clone()], 0.0, marked);
+        job.append(&snippet[matched.end..], 0.0, plain);
+        job.wrap = egui::text::TextWrapping::truncate_at_width(right - text_left);
+        painter.galley(
+            egui::pos2(text_left, y + 24.0 * scale),
+            painter.layout_job(job),
+            dim_color,
+        );
+        y += row_height;
+    }
+}
+
 fn draw_hud(ui: &egui::Ui, theme: &Theme, rect: egui::Rect, scale: f32) {
     let shortcuts = [
         ("Space / N / \u{2192}", "Next slide / reveal"),
@@ -1590,6 +2550,7 @@ fn draw_hud(ui: &egui::Ui, theme: &Theme, rect: egui::Rect, scale: f32) {
         ("\u{2191} / \u{2193} / Wheel", "Scroll slide content"),
         ("Left click", "Next slide"),
         ("Right click", "Previous slide"),
+        ("Ctrl/Cmd click", "Open link"),
         ("Left drag", "Freehand pen (blue)"),
         ("Right drag", "Draw arrow (orange)"),
         ("Esc", "Clear drawings / \u{00d7}2 exit"),
@@ -1597,10 +2558,14 @@ fn draw_hud(ui: &egui::Ui, theme: &Theme, rect: egui::Rect, scale: f32) {
         ("T", "Cycle transition"),
         ("D", "Toggle theme"),
         ("F", "Toggle fullscreen"),
+        ("S", "Presenter view"),
         ("H", "Toggle this HUD"),
         ("Q", "Quit"),
         ("Home", "First slide"),
         ("End", "Last slide"),
+        ("0-9 + Enter", "Go to slide number"),
+        (":", "Find slide by heading"),
+        ("/", "Search all slide text"),
     ];
 
     let bg = Theme::with_opacity(theme.code_background, 0.9);
@@ -1657,15 +2622,27 @@ fn draw_hud(ui: &egui::Ui, theme: &Theme, rect: egui::Rect, scale: f32) {
 pub fn run(
     file: PathBuf,
     windowed: bool,
-    start_slide: Option<usize>,
+    start_slide: Option<String>,
     start_overview: bool,
 ) -> anyhow::Result<()> {
-    let content = std::fs::read_to_string(&file)?;
-    let base_path = file.parent().unwrap_or(std::path::Path::new("."));
-    let presentation = parser::parse(&content, base_path);
+    let presentation = parser::load(&file)?;
+    let start_slide = start_slide
+        .map(|target| {
+            presentation
+                .resolve_slide(&target)
+                .ok_or_else(|| anyhow::anyhow!("No slide matches --slide {target}"))
+        })
+        .transpose()?;
 
-    if presentation.slides.is_empty() {
-        anyhow::bail!("No slides found in {}", file.display());
+    let base_path = file.parent().unwrap_or(std::path::Path::new("."));
+    theme::load_user_themes(base_path);
+    render::syntax::load_custom_themes(base_path);
+    let warnings = theme::check_themes(&presentation)
+        .into_iter()
+        .chain(render::syntax::check_code_themes(&presentation))
+        .chain(parser::include::check_includes(&presentation));
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
     }
 
     let title = presentation.meta.title.clone().unwrap_or_else(|| {
@@ -1686,10 +2663,10 @@ pub fn run(
 
     let (initial_slide, initial_overview) = if start_overview {
         // --overview flag: start in grid at current slide
-        (start_slide.map(|s| s.saturating_sub(1)).unwrap_or(0), true)
-    } else if let Some(s) = start_slide {
-        // --slide N flag: start on that slide (1-indexed)
-        (s.saturating_sub(1), false)
+        (start_slide.unwrap_or(0), true)
+    } else if let Some(index) = start_slide {
+        // --slide flag: start on that slide
+        (index, false)
     } else {
         // Fall back to config
         match config_start {
@@ -1738,3 +2715,44 @@ pub fn run(
     )
     .map_err(|e| anyhow::anyhow!("{e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn match_slide_follows_title_when_slides_inserted() {
+        let old = parser::parse("# A\n\nx\n\n# B\n\ny\n\n# C\n\nz", Path::new("."));
+        let new = parser::parse(
+            "# A\n\nx\n\n# New\n\nw\n\n# B\n\ny\n\n# C\n\nz",
+            Path::new("."),
+        );
+        assert_eq!(match_slide(&old, 1, &new), 2);
+    }
+
+    #[test]
+    fn match_slide_clamps_when_slides_removed() {
+        let old = parser::parse("# A\n\nx\n\n# B\n\ny\n\n# C\n\nz", Path::new("."));
+        let new = parser::parse("# A\n\nx", Path::new("."));
+        assert_eq!(match_slide(&old, 2, &new), 0);
+    }
+
+    #[test]
+    fn watched_files_include_theme_files() {
+        let dir = std::env::temp_dir().join(format!("mdeck-watch-themes-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("themes")).unwrap();
+        std::fs::write(dir.join("themes").join("ocean.yaml"), "base: dark\n").unwrap();
+        std::fs::write(dir.join("themes").join("notes.txt"), "").unwrap();
+
+        let deck = dir.join("deck.md");
+        let presentation = parser::parse("# One", &dir);
+        let files = watched_files(&deck, &presentation);
+        assert_eq!(files[0], deck);
+        assert!(files.contains(&dir.join("themes").join("ocean.yaml")));
+        assert!(files.contains(&dir.join("themes")));
+        assert!(!files.contains(&dir.join("themes").join("notes.txt")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -24,9 +24,9 @@ pub struct Cli {
     #[arg(long, global = false)]
     pub windowed: bool,
 
-    /// Start on a specific slide (1-indexed)
-    #[arg(long, global = false)]
-    pub slide: Option<usize>,
+    /// Start on a specific slide: a number (1-indexed) or a slide id
+    #[arg(long, global = false, value_name = "SLIDE")]
+    pub slide: Option<String>,
 
     /// Start in grid overview mode
     #[arg(long, global = false)]
@@ -66,22 +66,42 @@ pub enum Commands {
         shell: Shell,
     },
 
-    /// Export slides as PNG images
+    /// Export slides as PNG images or a PDF document
     Export {
         /// Markdown file to export
         file: PathBuf,
 
-        /// Output directory for PNG files
+        /// Output directory for exported files
         #[arg(short, long, default_value = "export")]
         output_dir: PathBuf,
 
+        /// Output format
+        #[arg(long, value_enum, default_value = "png")]
+        format: ExportFormat,
+
         /// Export width in pixels
         #[arg(long, default_value = "1920")]
         width: u32,
 
-        /// Export height in pixels
-        #[arg(long, default_value = "1080")]
-        height: u32,
+        /// Export height in pixels (defaults to the presentation's aspect ratio)
+        #[arg(long)]
+        height: Option<u32>,
+
+        /// Export every incremental-reveal step as its own frame
+        /// (slide-03-step-0.png, ... or one PDF page per step)
+        #[arg(long)]
+        steps: bool,
+
+        /// Render offscreen on the CPU instead of opening a window
+        /// (automatic when no display is available)
+        #[arg(long)]
+        headless: bool,
+    },
+
+    /// List and inspect presentation themes
+    Theme {
+        #[command(subcommand)]
+        command: ThemeCommands,
     },
 
     /// Print the mdeck markdown format specification
@@ -122,6 +142,24 @@ pub enum ConfigCommands {
     },
 }
 
+#[derive(Subcommand)]
+pub enum ThemeCommands {
+    /// List built-in themes and YAML themes from themes/ folders
+    List {
+        /// Presentation file or directory whose themes/ folder to include
+        /// (defaults to the current directory)
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One PNG image per slide
+    Png,
+    /// A single multi-page PDF
+    Pdf,
+}
+
 #[derive(Clone, ValueEnum)]
 pub enum Shell {
     Bash,
@@ -142,9 +180,15 @@ impl Cli {
             Some(Commands::Export {
                 file,
                 output_dir,
+                format,
                 width,
                 height,
-            }) => crate::commands::export::run(file, output_dir, width, height),
+                steps,
+                headless,
+            }) => crate::commands::export::run(
+                file, output_dir, format, width, height, steps, headless,
+            ),
+            Some(Commands::Theme { command }) => crate::commands::theme::run(command),
             Some(Commands::Spec { short }) => {
                 crate::commands::spec::run(short);
                 Ok(())
@@ -0,0 +1,519 @@
+mod pdf;
+mod raster;
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+
+use crate::cli::ExportFormat;
+use crate::config::Config;
+use crate::parser::{self, Presentation, PresentationMeta};
+use crate::render;
+use crate::render::aspect::Aspect;
+use crate::render::image_cache::ImageCache;
+use crate::render::links;
+use crate::theme::{self, Theme};
+
+use pdf::{LinkTarget, PageLink};
+
+/// A single exported image: a slide at a given reveal step.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    slide: usize,
+    step: usize,
+}
+
+impl Frame {
+    /// PNG file name, e.g. `slide-03.png` or `slide-03-step-1.png`.
+    fn file_name(&self, steps: bool) -> String {
+        if steps {
+            format!("slide-{:02}-step-{}.png", self.slide + 1, self.step)
+        } else {
+            format!("slide-{:02}.png", self.slide + 1)
+        }
+    }
+}
+
+/// List the frames to export. Without `steps`, each slide is exported once,
+/// fully revealed; with it, every reveal step gets its own frame.
+fn frames(presentation: &Presentation, steps: bool) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    for (slide, s) in presentation.slides.iter().enumerate() {
+        let max = parser::compute_max_steps(&s.blocks);
+        let first = if steps { 0 } else { max };
+        frames.extend((first..=max).map(|step| Frame { slide, step }));
+    }
+    frames
+}
+
+/// Where rendered frames go: PNG files, or pages collected for a PDF.
+struct Output {
+    dir: PathBuf,
+    format: ExportFormat,
+    /// Whether every reveal step is exported as its own frame
+    steps: bool,
+    total: usize,
+    pages: Vec<pdf::Page>,
+}
+
+impl Output {
+    fn add(&mut self, index: usize, frame: Frame, image: egui::ColorImage, links: Vec<PageLink>) {
+        match self.format {
+            ExportFormat::Png => {
+                let filename = frame.file_name(self.steps);
+                let path = self.dir.join(&filename);
+                save_color_image(&image, &path);
+                eprintln!("  Saved {filename}");
+            }
+            ExportFormat::Pdf => {
+                self.pages.push(pdf::Page {
+                    image,
+                    slide: frame.slide,
+                    links,
+                });
+                eprintln!("  Rendered page {} of {}", index + 1, self.total);
+            }
+        }
+    }
+
+    /// Write the PDF once all pages have been rendered.
+    fn finish(&self, file: &Path, meta: &PresentationMeta) -> anyhow::Result<()> {
+        if self.format == ExportFormat::Pdf {
+            let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+            let path = self.dir.join(format!("{stem}.pdf"));
+            std::fs::write(&path, pdf::write(&self.pages, meta))
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))?;
+            eprintln!("  Saved {} ({} pages)", path.display(), self.pages.len());
+        }
+        Ok(())
+    }
+}
+
+/// Paint one export frame: background, slide content and footer. If the
+/// image size doesn't match the slide aspect ratio, the slide is letterboxed.
+/// Returns the links on the frame.
+fn draw_frame(
+    ui: &egui::Ui,
+    presentation: &Presentation,
+    theme: &Theme,
+    aspect: Aspect,
+    image_cache: &ImageCache,
+    frame: Frame,
+) -> Vec<PageLink> {
+    let full = ui.max_rect();
+    let rect = aspect.fit(full);
+    ui.painter().rect_filled(full, 0.0, egui::Color32::BLACK);
+    links::take(ui.ctx());
+
+    let Some(slide) = presentation.slides.get(frame.slide) else {
+        return Vec::new();
+    };
+    let theme = render::slide_theme(slide, &presentation.meta, theme);
+    render::background::draw_slide(ui, slide, &theme, rect, 1.0, image_cache);
+
+    let scale = aspect.scale(rect);
+    render::render_slide(ui, slide, &theme, rect, 1.0, image_cache, frame.step, scale);
+    if let Some(ref footer) = slide.settings.footer {
+        render::draw_footer(ui, footer, &theme, rect, scale);
+    }
+
+    // Links to slides that don't exist are left out
+    links::take(ui.ctx())
+        .into_iter()
+        .filter_map(|link| {
+            let target = if link.is_internal() {
+                LinkTarget::Slide(presentation.find_anchor(&link.url)?)
+            } else {
+                LinkTarget::Uri(link.url)
+            };
+            let to_page = |p: egui::Pos2| ((p - full.min) / full.size()).to_pos2();
+            Some(PageLink {
+                rect: egui::Rect::from_min_max(to_page(link.rect.min), to_page(link.rect.max)),
+                target,
+            })
+        })
+        .collect()
+}
+
+struct ExportApp {
+    presentation: Presentation,
+    theme: Theme,
+    aspect: Aspect,
+    image_cache: ImageCache,
+    frames: Vec<Frame>,
+    output: Arc<Mutex<Output>>,
+    current_frame: usize,
+    /// Links drawn on the current frame
+    links: Vec<PageLink>,
+    screenshot_requested: bool,
+    done: bool,
+}
+
+impl ExportApp {
+    fn new(
+        presentation: Presentation,
+        aspect: Aspect,
+        base_path: &Path,
+        frames: Vec<Frame>,
+        output: Arc<Mutex<Output>>,
+    ) -> Self {
+        let theme = Theme::from_meta(&presentation.meta);
+        let image_cache = ImageCache::new(base_path.to_path_buf());
+
+        Self {
+            presentation,
+            theme,
+            aspect,
+            image_cache,
+            frames,
+            output,
+            current_frame: 0,
+            links: Vec::new(),
+            screenshot_requested: false,
+            done: false,
+        }
+    }
+}
+
+impl eframe::App for ExportApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.done {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            return;
+        }
+
+        // Check for screenshot result from previous frame
+        let mut screenshot = None;
+        ctx.input(|i| {
+            for event in &i.events {
+                if let egui::Event::Screenshot { image, .. } = event {
+                    screenshot = Some(image.clone());
+                }
+            }
+        });
+
+        if let Some(image) = screenshot {
+            let frame = self.frames[self.current_frame];
+            let links = std::mem::take(&mut self.links);
+            self.output
+                .lock()
+                .unwrap()
+                .add(self.current_frame, frame, (*image).clone(), links);
+            self.screenshot_requested = false;
+            self.current_frame += 1;
+            if self.current_frame >= self.frames.len() {
+                self.done = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                return;
+            }
+        }
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::new().inner_margin(0.0))
+            .show(ctx, |ui| {
+                let frame = self.frames[self.current_frame];
+                self.links = draw_frame(
+                    ui,
+                    &self.presentation,
+                    &self.theme,
+                    self.aspect,
+                    &self.image_cache,
+                    frame,
+                );
+            });
+
+        // Request screenshot after rendering (will arrive next frame)
+        if !self.screenshot_requested {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+            self.screenshot_requested = true;
+        }
+
+        ctx.request_repaint();
+    }
+}
+
+/// Render frames offscreen: run egui without a window, tessellate its output
+/// and rasterize it on the CPU. Needs no display server or GPU.
+fn render_headless(
+    presentation: &Presentation,
+    aspect: Aspect,
+    base_path: &Path,
+    size: [usize; 2],
+    frames: &[Frame],
+    mut on_frame: impl FnMut(usize, Frame, egui::ColorImage, Vec<PageLink>),
+) {
+    let theme = Theme::from_meta(&presentation.meta);
+    let image_cache = ImageCache::new(base_path.to_path_buf());
+    let ctx = egui::Context::default();
+    theme::install_fonts(&ctx, &theme);
+    let mut renderer = raster::SoftwareRenderer::default();
+    let screen =
+        egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(size[0] as f32, size[1] as f32));
+
+    for (index, &frame) in frames.iter().enumerate() {
+        let mut input = egui::RawInput {
+            screen_rect: Some(screen),
+            ..Default::default()
+        };
+        input
+            .viewports
+            .entry(egui::ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(1.0);
+
+        let mut links = Vec::new();
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::new().inner_margin(0.0))
+                .show(ctx, |ui| {
+                    links = draw_frame(ui, presentation, &theme, aspect, &image_cache, frame);
+                });
+        });
+
+        renderer.update_textures(&output.textures_delta);
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let image = renderer.render(&primitives, size, output.pixels_per_point, theme.background);
+        renderer.free_textures(&output.textures_delta);
+
+        on_frame(index, frame, image, links);
+    }
+}
+
+/// Whether a window can be opened. On Linux this needs an X11 or Wayland
+/// display; other platforms always have one.
+fn has_display() -> bool {
+    if !cfg!(target_os = "linux") {
+        return true;
+    }
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+fn save_color_image(image: &egui::ColorImage, path: &Path) {
+    let width = image.width() as u32;
+    let height = image.height() as u32;
+    let pixels: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|c| [c.r(), c.g(), c.b(), c.a()])
+        .collect();
+
+    image::save_buffer(path, &pixels, width, height, image::ColorType::Rgba8)
+        .unwrap_or_else(|e| eprintln!("Failed to save {}: {e}", path.display()));
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    file: PathBuf,
+    output_dir: PathBuf,
+    format: ExportFormat,
+    width: u32,
+    height: Option<u32>,
+    steps: bool,
+    headless: bool,
+) -> anyhow::Result<()> {
+    let presentation = parser::load(&file)?;
+    let base_path = file
+        .parent()
+        .unwrap_or(std::path::Path::new("."))
+        .to_path_buf();
+
+    theme::load_user_themes(&base_path);
+    render::syntax::load_custom_themes(&base_path);
+    let warnings = theme::check_themes(&presentation)
+        .into_iter()
+        .chain(render::syntax::check_code_themes(&presentation))
+        .chain(parser::include::check_includes(&presentation));
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+    }
+
+    // Default the height from the presentation's aspect ratio
+    let aspect = Aspect::resolve(&presentation.meta, &Config::load_or_default());
+    let height = height.unwrap_or_else(|| width * aspect.height / aspect.width);
+
+    std::fs::create_dir_all(&output_dir)?;
+
+    let frames = frames(&presentation, steps);
+    let slide_count = presentation.slides.len();
+    eprintln!(
+        "Exporting {} slides ({} frames) to {} ({}x{})",
+        slide_count,
+        frames.len(),
+        output_dir.display(),
+        width,
+        height,
+    );
+
+    let meta = presentation.meta.clone();
+    let output = Output {
+        dir: output_dir,
+        format,
+        steps,
+        total: frames.len(),
+        pages: Vec::new(),
+    };
+
+    let headless = headless || !has_display();
+    let output = if headless {
+        let mut output = output;
+        render_headless(
+            &presentation,
+            aspect,
+            &base_path,
+            [width as usize, height as usize],
+            &frames,
+            |index, frame, image, links| output.add(index, frame, image, links),
+        );
+        output
+    } else {
+        run_windowed(
+            presentation,
+            aspect,
+            base_path,
+            width,
+            height,
+            frames,
+            output,
+        )?
+    };
+
+    output.finish(&file, &meta)?;
+
+    eprintln!("Export complete.");
+    Ok(())
+}
+
+/// Render frames in a borderless window, capturing each with a screenshot.
+fn run_windowed(
+    presentation: Presentation,
+    aspect: Aspect,
+    base_path: PathBuf,
+    width: u32,
+    height: u32,
+    frames: Vec<Frame>,
+    output: Output,
+) -> anyhow::Result<Output> {
+    let title = presentation
+        .meta
+        .title
+        .clone()
+        .unwrap_or_else(|| "mdeck export".to_string());
+
+    let viewport = egui::ViewportBuilder::default()
+        .with_inner_size([width as f32, height as f32])
+        .with_title(&title)
+        .with_decorations(false);
+
+    let options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
+
+    let output = Arc::new(Mutex::new(output));
+    let output_clone = Arc::clone(&output);
+    eframe::run_native(
+        &title,
+        options,
+        Box::new(move |cc| {
+            let app = ExportApp::new(presentation, aspect, &base_path, frames, output_clone);
+            theme::install_fonts(&cc.egui_ctx, &app.theme);
+            Ok(Box::new(app))
+        }),
+    )
+    .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let output = Arc::try_unwrap(output)
+        .map_err(|_| anyhow::anyhow!("Export window did not shut down"))?
+        .into_inner()
+        .unwrap();
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_cover_every_reveal_step() {
+        let content = "# One\n\n+ a\n+ b\n\n---\n\n# Two";
+        let pres = parser::parse(content, Path::new("."));
+
+        let slides: Vec<(usize, usize)> = frames(&pres, false)
+            .iter()
+            .map(|f| (f.slide, f.step))
+            .collect();
+        assert_eq!(slides, vec![(0, 2), (1, 0)]);
+
+        let steps: Vec<(usize, usize)> = frames(&pres, true)
+            .iter()
+            .map(|f| (f.slide, f.step))
+            .collect();
+        assert_eq!(steps, vec![(0, 0), (0, 1), (0, 2), (1, 0)]);
+    }
+
+    #[test]
+    fn step_frames_get_numbered_file_names() {
+        let frame = Frame { slide: 2, step: 1 };
+        assert_eq!(frame.file_name(false), "slide-03.png");
+        assert_eq!(frame.file_name(true), "slide-03-step-1.png");
+    }
+
+    #[test]
+    fn headless_render_draws_slide_content() {
+        let content = "---\n@theme: dark\n---\n\n# Hello\n\nSome text";
+        let pres = parser::parse(content, Path::new("."));
+        let theme = Theme::dark();
+        let frames = frames(&pres, false);
+
+        let mut images = Vec::new();
+        render_headless(
+            &pres,
+            Aspect::default(),
+            Path::new("."),
+            [192, 108],
+            &frames,
+            |_, _, image, _| images.push(image),
+        );
+
+        assert_eq!(images.len(), 1);
+        let image = &images[0];
+        assert_eq!(image.size, [192, 108]);
+        assert_eq!(image.pixels[0], theme.background);
+        assert!(
+            image.pixels.iter().any(|&p| p != theme.background),
+            "expected text to be drawn"
+        );
+    }
+
+    #[test]
+    fn headless_render_collects_links() {
+        let content = "# One\n\nSee [docs](https://example.com) or [two](#slide-2) and [gone](#nope)\n\n---\n\n# Two";
+        let pres = parser::parse(content, Path::new("."));
+        let frames = frames(&pres, false);
+
+        let mut pages = Vec::new();
+        render_headless(
+            &pres,
+            Aspect::default(),
+            Path::new("."),
+            [960, 540],
+            &frames,
+            |_, _, _, links| pages.push(links),
+        );
+
+        let targets: Vec<&LinkTarget> = pages[0].iter().map(|l| &l.target).collect();
+        assert_eq!(
+            targets,
+            [
+                &LinkTarget::Uri("https://example.com".to_string()),
+                &LinkTarget::Slide(1)
+            ]
+        );
+        let rect = pages[0][0].rect;
+        assert!(rect.min.x > 0.0 && rect.max.x < 1.0 && rect.min.y > 0.0 && rect.max.y < 1.0);
+        assert!(pages[1].is_empty());
+    }
+}
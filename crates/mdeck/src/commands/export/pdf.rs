@@ -0,0 +1,221 @@
+use eframe::egui;
+use pdf_writer::types::{ActionType, AnnotationType};
+use pdf_writer::{Content, Date, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::parser::PresentationMeta;
+
+/// Resolution assumed when converting exported pixels to PDF points.
+const PIXELS_PER_INCH: f32 = 96.0;
+
+/// A rendered frame and the links on it.
+pub struct Page {
+    pub image: egui::ColorImage,
+    /// The slide the frame shows; internal links go to its first page.
+    pub slide: usize,
+    pub links: Vec<PageLink>,
+}
+
+/// A clickable area, in fractions of the page size from the top left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageLink {
+    pub rect: egui::Rect,
+    pub target: LinkTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    Uri(String),
+    Slide(usize),
+}
+
+/// Build a PDF with one full-bleed page per rendered frame, with link
+/// annotations over the links drawn on it. Title, author and date from the
+/// frontmatter are written into the document info dictionary.
+pub fn write(pages: &[Page], meta: &PresentationMeta) -> Vec<u8> {
+    let mut pdf = Pdf::new();
+    let catalog_id = Ref::new(1);
+    let tree_id = Ref::new(2);
+    let info_id = Ref::new(3);
+
+    // Each page needs three objects: the page, its image and its content stream
+    let page_refs: Vec<(Ref, Ref, Ref)> = (0..pages.len() as i32)
+        .map(|i| {
+            let base = 4 + i * 3;
+            (Ref::new(base), Ref::new(base + 1), Ref::new(base + 2))
+        })
+        .collect();
+
+    pdf.catalog(catalog_id).pages(tree_id);
+    pdf.pages(tree_id)
+        .kids(page_refs.iter().map(|(page, _, _)| *page))
+        .count(pages.len() as i32);
+
+    let image_name = Name(b"Im0");
+    for (page, &(page_id, image_id, content_id)) in pages.iter().zip(&page_refs) {
+        let image = &page.image;
+        let [width_px, height_px] = image.size;
+        let width = width_px as f32 * 72.0 / PIXELS_PER_INCH;
+        let height = height_px as f32 * 72.0 / PIXELS_PER_INCH;
+
+        let rgb: Vec<u8> = image
+            .pixels
+            .iter()
+            .flat_map(|c| [c.r(), c.g(), c.b()])
+            .collect();
+        let data = miniz_oxide::deflate::compress_to_vec_zlib(&rgb, 6);
+
+        let mut xobject = pdf.image_xobject(image_id, &data);
+        xobject.filter(Filter::FlateDecode);
+        xobject.width(width_px as i32);
+        xobject.height(height_px as i32);
+        xobject.color_space_name(Name(b"DeviceRGB"));
+        xobject.bits_per_component(8);
+        xobject.finish();
+
+        let mut content = Content::new();
+        content.save_state();
+        content.transform([width, 0.0, 0.0, height, 0.0, 0.0]);
+        content.x_object(image_name);
+        content.restore_state();
+        pdf.stream(content_id, &content.finish());
+
+        let links = &page.links;
+        let mut page = pdf.page(page_id);
+        page.parent(tree_id)
+            .media_box(Rect::new(0.0, 0.0, width, height))
+            .contents(content_id);
+        page.resources().x_objects().pair(image_name, image_id);
+        if !links.is_empty() {
+            let mut annotations = page.annotations();
+            for link in links {
+                // PDF coordinates run up from the bottom left
+                let r = link.rect;
+                let mut annotation = annotations.push();
+                annotation
+                    .subtype(AnnotationType::Link)
+                    .rect(Rect::new(
+                        r.min.x * width,
+                        height - r.max.y * height,
+                        r.max.x * width,
+                        height - r.min.y * height,
+                    ))
+                    .border(0.0, 0.0, 0.0, None);
+                let mut action = annotation.action();
+                match &link.target {
+                    LinkTarget::Uri(uri) => {
+                        action.action_type(ActionType::Uri).uri(Str(uri.as_bytes()));
+                    }
+                    LinkTarget::Slide(slide) => {
+                        let target = pages.iter().position(|p| p.slide == *slide);
+                        if let Some(&(target_id, _, _)) = target.and_then(|i| page_refs.get(i)) {
+                            action
+                                .action_type(ActionType::GoTo)
+                                .destination()
+                                .page(target_id)
+                                .fit();
+                        }
+                    }
+                }
+            }
+        }
+        page.finish();
+    }
+
+    let mut info = pdf.document_info(info_id);
+    if let Some(ref title) = meta.title {
+        info.title(TextStr(title));
+    }
+    if let Some(ref author) = meta.author {
+        info.author(TextStr(author));
+    }
+    if let Some(date) = meta.date.as_deref().and_then(parse_date) {
+        info.creation_date(date);
+    }
+    info.creator(TextStr("mdeck"));
+    info.finish();
+
+    pdf.finish()
+}
+
+/// Parse a frontmatter date such as `2026-03-14` or `March 14, 2026`.
+fn parse_date(value: &str) -> Option<Date> {
+    use chrono::Datelike;
+
+    const FORMATS: [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%B %d, %Y", "%d %B %Y", "%b %d, %Y"];
+    let value = value.trim();
+    let date = FORMATS
+        .iter()
+        .find_map(|f| chrono::NaiveDate::parse_from_str(value, f).ok())?;
+    Some(
+        Date::new(date.year() as u16)
+            .month(date.month() as u8)
+            .day(date.day() as u8),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_page_per_frame_with_metadata() {
+        let frame = egui::ColorImage::new([4, 3], vec![egui::Color32::RED; 12]);
+        let page = |slide| Page {
+            image: frame.clone(),
+            slide,
+            links: Vec::new(),
+        };
+        let meta = PresentationMeta {
+            title: Some("Quarterly Review".to_string()),
+            author: Some("Ada".to_string()),
+            date: Some("2026-03-14".to_string()),
+            ..Default::default()
+        };
+        let bytes = write(&[page(0), page(1)], &meta);
+        let text = String::from_utf8_lossy(&bytes);
+
+        assert!(text.starts_with("%PDF-"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("/Title (Quarterly Review)"));
+        assert!(text.contains("/Author (Ada)"));
+        assert!(text.contains("/CreationDate (D:20260314)"));
+    }
+
+    #[test]
+    fn writes_link_annotations() {
+        let link = |target| PageLink {
+            rect: egui::Rect::from_min_max(egui::pos2(0.25, 0.5), egui::pos2(0.75, 1.0)),
+            target,
+        };
+        let pages = [
+            Page {
+                image: egui::ColorImage::new([96, 96], vec![egui::Color32::RED; 96 * 96]),
+                slide: 0,
+                links: vec![
+                    link(LinkTarget::Uri("https://example.com".to_string())),
+                    link(LinkTarget::Slide(1)),
+                ],
+            },
+            Page {
+                image: egui::ColorImage::new([96, 96], vec![egui::Color32::RED; 96 * 96]),
+                slide: 1,
+                links: Vec::new(),
+            },
+        ];
+        let bytes = write(&pages, &PresentationMeta::default());
+        let text = String::from_utf8_lossy(&bytes);
+
+        assert!(text.contains("/Subtype /Link"));
+        assert!(text.contains("/Rect [18 0 54 36]"));
+        assert!(text.contains("/URI (https://example.com)"));
+        assert!(text.contains("/S /GoTo"));
+        assert!(text.contains("/D [7 0 R /Fit]"));
+    }
+
+    #[test]
+    fn parses_common_date_formats() {
+        assert!(parse_date("2026-03-14").is_some());
+        assert!(parse_date("March 14, 2026").is_some());
+        assert!(parse_date("sometime soon").is_none());
+    }
+}
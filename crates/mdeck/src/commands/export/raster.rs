@@ -0,0 +1,414 @@
+//! A small CPU rasterizer for tessellated egui output, used to export slides
+//! without a window, display server or GPU.
+//!
+//! It mirrors what egui's GPU backends do: vertex colors and textures are
+//! premultiplied sRGBA, multiplied together and alpha-blended in gamma space.
+//! Edge anti-aliasing comes from egui's own feathering, so every pixel is
+//! sampled once at its center.
+
+use std::collections::HashMap;
+
+use eframe::egui::{
+    self, ClippedPrimitive, Color32, ImageData, Mesh, TextureFilter, TextureId, TextureOptions,
+    TextureWrapMode, TexturesDelta, epaint,
+};
+
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+    options: TextureOptions,
+}
+
+#[derive(Default)]
+pub struct SoftwareRenderer {
+    textures: HashMap<TextureId, Texture>,
+}
+
+impl SoftwareRenderer {
+    /// Upload new and updated textures. Call before [`Self::render`].
+    pub fn update_textures(&mut self, delta: &TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            self.set_texture(*id, image_delta);
+        }
+    }
+
+    /// Drop textures egui no longer uses. Call after [`Self::render`].
+    pub fn free_textures(&mut self, delta: &TexturesDelta) {
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    fn set_texture(&mut self, id: TextureId, delta: &epaint::ImageDelta) {
+        let ImageData::Color(image) = &delta.image;
+        match delta.pos {
+            None => {
+                self.textures.insert(
+                    id,
+                    Texture {
+                        size: image.size,
+                        pixels: image.pixels.clone(),
+                        options: delta.options,
+                    },
+                );
+            }
+            Some([x0, y0]) => {
+                // Partial update, e.g. new glyphs added to the font atlas
+                let Some(texture) = self.textures.get_mut(&id) else {
+                    return;
+                };
+                let [w, h] = image.size;
+                for y in 0..h.min(texture.size[1].saturating_sub(y0)) {
+                    let cols = w.min(texture.size[0].saturating_sub(x0));
+                    let dst = (y0 + y) * texture.size[0] + x0;
+                    texture.pixels[dst..dst + cols]
+                        .copy_from_slice(&image.pixels[y * w..y * w + cols]);
+                }
+                texture.options = delta.options;
+            }
+        }
+    }
+
+    /// Paint tessellated primitives onto a new `size` image (in physical
+    /// pixels) filled with `background`.
+    pub fn render(
+        &self,
+        primitives: &[ClippedPrimitive],
+        size: [usize; 2],
+        pixels_per_point: f32,
+        background: Color32,
+    ) -> egui::ColorImage {
+        let mut target = Target {
+            width: size[0],
+            height: size[1],
+            pixels: vec![background; size[0] * size[1]],
+        };
+
+        for primitive in primitives {
+            let epaint::Primitive::Mesh(mesh) = &primitive.primitive else {
+                // Paint callbacks need a GPU; slides never use them
+                continue;
+            };
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                continue;
+            };
+
+            // Round the clip rect to whole pixels, like a GPU scissor rect
+            let clip = primitive.clip_rect;
+            let clip = ClipRect {
+                min_x: (clip.min.x * pixels_per_point).round().max(0.0) as usize,
+                min_y: (clip.min.y * pixels_per_point).round().max(0.0) as usize,
+                max_x: ((clip.max.x * pixels_per_point).round().max(0.0) as usize)
+                    .min(target.width),
+                max_y: ((clip.max.y * pixels_per_point).round().max(0.0) as usize)
+                    .min(target.height),
+            };
+            if clip.min_x >= clip.max_x || clip.min_y >= clip.max_y {
+                continue;
+            }
+
+            draw_mesh(&mut target, mesh, texture, &clip, pixels_per_point);
+        }
+
+        egui::ColorImage::new(size, target.pixels)
+    }
+}
+
+struct Target {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color32>,
+}
+
+struct ClipRect {
+    min_x: usize,
+    min_y: usize,
+    max_x: usize,
+    max_y: usize,
+}
+
+/// Vertex attributes interpolated across a triangle.
+#[derive(Clone, Copy)]
+struct Vertex {
+    x: f32,
+    y: f32,
+    u: f32,
+    v: f32,
+    color: [f32; 4],
+}
+
+fn draw_mesh(
+    target: &mut Target,
+    mesh: &Mesh,
+    texture: &Texture,
+    clip: &ClipRect,
+    pixels_per_point: f32,
+) {
+    let vertex = |i: u32| {
+        let v = &mesh.vertices[i as usize];
+        Vertex {
+            x: v.pos.x * pixels_per_point,
+            y: v.pos.y * pixels_per_point,
+            u: v.uv.x,
+            v: v.uv.y,
+            color: v.color.to_array().map(f32::from),
+        }
+    };
+
+    for tri in mesh.indices.chunks_exact(3) {
+        draw_triangle(
+            target,
+            [vertex(tri[0]), vertex(tri[1]), vertex(tri[2])],
+            texture,
+            clip,
+        );
+    }
+}
+
+fn edge(a: &Vertex, b: &Vertex, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/// Top-left fill rule: pixels exactly on an edge shared by two triangles
+/// belong to exactly one of them.
+fn owns_edge(a: &Vertex, b: &Vertex) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+fn draw_triangle(target: &mut Target, v: [Vertex; 3], texture: &Texture, clip: &ClipRect) {
+    let [v0, mut v1, mut v2] = v;
+    let mut area = edge(&v0, &v1, v2.x, v2.y);
+    if area == 0.0 {
+        return;
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut v1, &mut v2);
+        area = -area;
+    }
+
+    let min_x = (v0.x.min(v1.x).min(v2.x).floor().max(0.0) as usize).max(clip.min_x);
+    let min_y = (v0.y.min(v1.y).min(v2.y).floor().max(0.0) as usize).max(clip.min_y);
+    let max_x = (v0.x.max(v1.x).max(v2.x).ceil().max(0.0) as usize).min(clip.max_x);
+    let max_y = (v0.y.max(v1.y).max(v2.y).ceil().max(0.0) as usize).min(clip.max_y);
+    if min_x >= max_x || min_y >= max_y {
+        return;
+    }
+
+    let filter = pick_filter(&v0, &v1, &v2, area, texture);
+    let owns = [
+        owns_edge(&v1, &v2),
+        owns_edge(&v2, &v0),
+        owns_edge(&v0, &v1),
+    ];
+    let inside = |w: f32, owned: bool| w > 0.0 || (w == 0.0 && owned);
+
+    for py in min_y..max_y {
+        let y = py as f32 + 0.5;
+        for px in min_x..max_x {
+            let x = px as f32 + 0.5;
+            let w0 = edge(&v1, &v2, x, y);
+            let w1 = edge(&v2, &v0, x, y);
+            let w2 = edge(&v0, &v1, x, y);
+            if !(inside(w0, owns[0]) && inside(w1, owns[1]) && inside(w2, owns[2])) {
+                continue;
+            }
+
+            let (b0, b1, b2) = (w0 / area, w1 / area, w2 / area);
+            let u = v0.u * b0 + v1.u * b1 + v2.u * b2;
+            let v = v0.v * b0 + v1.v * b1 + v2.v * b2;
+            let texel = sample(texture, u, v, filter);
+
+            let mut src = [0.0; 4];
+            for (c, out) in src.iter_mut().enumerate() {
+                let color = v0.color[c] * b0 + v1.color[c] * b1 + v2.color[c] * b2;
+                *out = color * texel[c] / 255.0;
+            }
+            if src[3] <= 0.0 && src[..3].iter().all(|&c| c <= 0.0) {
+                continue;
+            }
+
+            // Premultiplied "over" blending
+            let dst = &mut target.pixels[py * target.width + px];
+            let inv = 1.0 - (src[3] / 255.0).clamp(0.0, 1.0);
+            let d = dst.to_array();
+            let blend = |s: f32, d: u8| (s + f32::from(d) * inv).round().clamp(0.0, 255.0) as u8;
+            *dst = Color32::from_rgba_premultiplied(
+                blend(src[0], d[0]),
+                blend(src[1], d[1]),
+                blend(src[2], d[2]),
+                blend(src[3], d[3]),
+            );
+        }
+    }
+}
+
+/// Choose the magnification or minification filter by comparing the
+/// triangle's area on screen with its area in texels.
+fn pick_filter(
+    v0: &Vertex,
+    v1: &Vertex,
+    v2: &Vertex,
+    area: f32,
+    texture: &Texture,
+) -> TextureFilter {
+    let texel_area = ((v1.u - v0.u) * (v2.v - v0.v) - (v2.u - v0.u) * (v1.v - v0.v)).abs()
+        * texture.size[0] as f32
+        * texture.size[1] as f32;
+    if texel_area > area {
+        texture.options.minification
+    } else {
+        texture.options.magnification
+    }
+}
+
+fn sample(texture: &Texture, u: f32, v: f32, filter: TextureFilter) -> [f32; 4] {
+    let [w, h] = texture.size;
+    if w == 0 || h == 0 {
+        return [255.0; 4];
+    }
+    let wrap = texture.options.wrap_mode;
+    let fetch = |x: i64, y: i64| {
+        let x = wrap_coord(x, w, wrap);
+        let y = wrap_coord(y, h, wrap);
+        texture.pixels[y * w + x].to_array().map(f32::from)
+    };
+
+    match filter {
+        TextureFilter::Nearest => {
+            fetch((u * w as f32).floor() as i64, (v * h as f32).floor() as i64)
+        }
+        TextureFilter::Linear => {
+            let x = u * w as f32 - 0.5;
+            let y = v * h as f32 - 0.5;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            let (x0, y0) = (x0 as i64, y0 as i64);
+            let (a, b) = (fetch(x0, y0), fetch(x0 + 1, y0));
+            let (c, d) = (fetch(x0, y0 + 1), fetch(x0 + 1, y0 + 1));
+            let mut out = [0.0; 4];
+            for i in 0..4 {
+                let top = a[i] + (b[i] - a[i]) * fx;
+                let bottom = c[i] + (d[i] - c[i]) * fx;
+                out[i] = top + (bottom - top) * fy;
+            }
+            out
+        }
+    }
+}
+
+fn wrap_coord(coord: i64, size: usize, mode: TextureWrapMode) -> usize {
+    let size = size as i64;
+    let wrapped = match mode {
+        TextureWrapMode::ClampToEdge => coord.clamp(0, size - 1),
+        TextureWrapMode::Repeat => coord.rem_euclid(size),
+        TextureWrapMode::MirroredRepeat => {
+            let period = coord.rem_euclid(size * 2);
+            if period < size {
+                period
+            } else {
+                size * 2 - 1 - period
+            }
+        }
+    };
+    wrapped as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn white_texture(renderer: &mut SoftwareRenderer) {
+        let mut delta = TexturesDelta::default();
+        delta.set.push((
+            TextureId::Managed(0),
+            epaint::ImageDelta::full(
+                egui::ColorImage::new([1, 1], vec![Color32::WHITE]),
+                TextureOptions::LINEAR,
+            ),
+        ));
+        renderer.update_textures(&delta);
+    }
+
+    fn rect_primitive(rect: egui::Rect, color: Color32) -> ClippedPrimitive {
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(rect, color);
+        ClippedPrimitive {
+            clip_rect: egui::Rect::EVERYTHING,
+            primitive: epaint::Primitive::Mesh(mesh),
+        }
+    }
+
+    #[test]
+    fn fills_rect_without_gaps_or_double_blending() {
+        let mut renderer = SoftwareRenderer::default();
+        white_texture(&mut renderer);
+
+        // Half-transparent red over black: the quad's diagonal must not be
+        // blended twice
+        let half_red = Color32::from_rgba_premultiplied(128, 0, 0, 128);
+        let rect = egui::Rect::from_min_max(egui::pos2(2.0, 2.0), egui::pos2(6.0, 6.0));
+        let image = renderer.render(
+            &[rect_primitive(rect, half_red)],
+            [8, 8],
+            1.0,
+            Color32::BLACK,
+        );
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if (2..6).contains(&x) && (2..6).contains(&y) {
+                    Color32::from_rgba_premultiplied(128, 0, 0, 255)
+                } else {
+                    Color32::BLACK
+                };
+                assert_eq!(image.pixels[y * 8 + x], expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn respects_clip_rect_and_scale() {
+        let mut renderer = SoftwareRenderer::default();
+        white_texture(&mut renderer);
+
+        let mut primitive = rect_primitive(
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(4.0, 4.0)),
+            Color32::WHITE,
+        );
+        primitive.clip_rect = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 4.0));
+        let image = renderer.render(&[primitive], [8, 8], 2.0, Color32::BLACK);
+
+        assert_eq!(image.pixels[0], Color32::WHITE);
+        assert_eq!(image.pixels[1], Color32::WHITE);
+        assert_eq!(image.pixels[2], Color32::BLACK);
+        assert_eq!(image.pixels[7 * 8], Color32::WHITE);
+    }
+
+    #[test]
+    fn applies_partial_texture_updates() {
+        let mut renderer = SoftwareRenderer::default();
+        let id = TextureId::Managed(1);
+        let mut delta = TexturesDelta::default();
+        delta.set.push((
+            id,
+            epaint::ImageDelta::full(
+                egui::ColorImage::new([2, 2], vec![Color32::BLACK; 4]),
+                TextureOptions::NEAREST,
+            ),
+        ));
+        delta.set.push((
+            id,
+            epaint::ImageDelta::partial(
+                [1, 1],
+                egui::ColorImage::new([1, 1], vec![Color32::WHITE]),
+                TextureOptions::NEAREST,
+            ),
+        ));
+        renderer.update_textures(&delta);
+
+        let texture = &renderer.textures[&id];
+        assert_eq!(texture.pixels[3], Color32::WHITE);
+        assert_eq!(texture.pixels[0], Color32::BLACK);
+    }
+}
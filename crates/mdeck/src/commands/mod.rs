@@ -3,3 +3,4 @@ pub mod completion;
 pub mod config;
 pub mod export;
 pub mod spec;
+pub mod theme;
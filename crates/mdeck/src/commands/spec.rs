@@ -20,10 +20,16 @@ SLIDE SEPARATION
 
 FRONTMATTER (YAML at top of file)
   title, author, date     Standard metadata
-  @theme: dark|light      Global theme
+  @theme: dark|light|name Global theme (custom: themes/name.yaml)
   @transition: slide|fade|spatial|none
   @aspect: 16:9|4:3|16:10
   @footer: "text"         Footer on every slide
+  @code-theme: name       Syntax theme (syntect name or themes/*.tmTheme)
+
+SLIDE DIRECTIVES (at the top of a slide, override frontmatter)
+  @theme, @transition, @footer, @code-theme
+  @background: #1E1E1E | linear-gradient(135deg, #000, #333)
+               | path.jpg [cover|contain] [dim|dim=0.6]
 
 LAYOUTS (auto-inferred, override with @layout: name)
   title        H1 + optional subtitle
@@ -51,6 +57,7 @@ KEYBOARD SHORTCUTS
   Enter/E        Back to present. T           Cycle transition
   D              Toggle theme     F           Toggle fullscreen
   H              Show/hide HUD    Esc x2      Exit
+  S              Presenter view   R           Reset timer (presenter)
   Ctrl+C x2      Exit             Q           Quit
 
 MOUSE CONTROLS
@@ -59,6 +66,10 @@ MOUSE CONTROLS
   Scroll wheel   Scroll content
   Drawings fade out after 8 seconds
 
+SPEAKER NOTES (shown only in the presenter view)
+  ???                     Everything after this line is notes
+  <!-- notes: text -->    Notes comment, may span several lines
+
 COLUMN SEPARATOR
   +++   Separates left and right columns in two-column layout
 "#
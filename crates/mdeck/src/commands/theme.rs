@@ -0,0 +1,46 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::cli::ThemeCommands;
+use crate::theme;
+
+pub fn run(cmd: ThemeCommands) -> Result<()> {
+    match cmd {
+        ThemeCommands::List { path } => list(path),
+    }
+}
+
+fn list(path: Option<PathBuf>) -> Result<()> {
+    let base_path = match path {
+        Some(ref p) if p.is_file() => p.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        Some(p) => p,
+        None => PathBuf::from("."),
+    };
+    theme::load_user_themes(&base_path);
+
+    println!("{}", "Themes:".bold());
+    for (name, path) in theme::available() {
+        let source = match path {
+            Some(path) => path.display().to_string(),
+            None => "built-in".to_string(),
+        };
+        println!("  {:<16} {}", name.cyan(), source.dimmed());
+    }
+
+    println!();
+    println!(
+        "Select with {} in the frontmatter or at the top of a slide.",
+        "@theme: <name>".bold()
+    );
+    println!(
+        "Add themes as YAML files in {} next to the deck or in {}.",
+        "themes/".bold(),
+        crate::config::Config::themes_dir()
+            .map(|d| d.display().to_string())
+            .unwrap_or_else(|| "~/.config/mdeck/themes".to_string())
+            .bold()
+    );
+    Ok(())
+}
@@ -116,6 +116,11 @@ impl Config {
             .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))
     }
 
+    /// Directory for user themes, `~/.config/mdeck/themes`.
+    pub fn themes_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join(APP_DIR).join("themes"))
+    }
+
     pub fn load() -> Result<Self> {
         let path = Self::path()?;
         Self::load_from(&path)
@@ -149,11 +154,28 @@ impl Config {
     }
 
     pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        self.set_with_themes(key, value, Self::themes_dir())
+    }
+
+    /// [`Config::set`], checking `defaults.theme` against the built-in themes
+    /// and those in `themes_dir`.
+    fn set_with_themes(
+        &mut self,
+        key: &str,
+        value: &str,
+        themes_dir: Option<PathBuf>,
+    ) -> Result<()> {
         match key {
             "defaults.theme" => {
-                match value {
-                    "light" | "dark" => {}
-                    _ => anyhow::bail!("Invalid theme: {value}. Must be 'light' or 'dark'."),
+                // Only config-dir themes: a deck's own themes/ isn't
+                // there for the other decks this default applies to
+                let themes = crate::theme::read_themes(themes_dir.as_slice());
+                if !crate::theme::BUILT_IN.contains(&value) && !themes.contains_key(value) {
+                    let known: Vec<&str> = crate::theme::BUILT_IN
+                        .into_iter()
+                        .chain(themes.keys().map(String::as_str))
+                        .collect();
+                    anyhow::bail!("Invalid theme: {value}. Available: {}.", known.join(", "));
                 }
                 self.defaults
                     .get_or_insert_with(DefaultsConfig::default)
@@ -198,3 +220,37 @@ impl Config {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_theme_must_be_built_in_or_in_the_themes_dir() {
+        let dir = std::env::temp_dir().join(format!("mdeck-config-themes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ocean.yaml"), "base: dark\n").unwrap();
+
+        let mut config = Config::default();
+        config
+            .set_with_themes("defaults.theme", "ocean", Some(dir.clone()))
+            .unwrap();
+        config
+            .set_with_themes("defaults.theme", "dark", Some(dir.clone()))
+            .unwrap();
+        let error = config
+            .set_with_themes("defaults.theme", "sepia", Some(dir.clone()))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Invalid theme: sepia. Available: light, dark, ocean."
+        );
+        assert_eq!(
+            config.defaults.and_then(|d| d.theme).as_deref(),
+            Some("dark")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
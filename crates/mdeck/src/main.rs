@@ -5,7 +5,9 @@ mod commands;
 mod config;
 mod parser;
 mod render;
+mod search;
 mod theme;
+mod watcher;
 
 use clap::{CommandFactory, Parser};
 use colored::Colorize;
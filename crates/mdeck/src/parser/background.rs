@@ -0,0 +1,176 @@
+/// A slide background from the `@background` directive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Background {
+    pub fill: BackgroundFill,
+    /// Opacity of a theme-colored overlay drawn on top (0.0 = none), so text
+    /// stays readable over busy images.
+    pub dim: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackgroundFill {
+    /// Solid RGBA color.
+    Color([u8; 4]),
+    /// Linear gradient. The angle follows CSS: 0 points up, 90 points right.
+    Gradient { angle: f32, stops: Vec<[u8; 4]> },
+    /// Image path, resolved relative to the deck.
+    Image { path: String, fit: BackgroundFit },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BackgroundFit {
+    /// Fill the slide, cropping the image.
+    #[default]
+    Cover,
+    /// Show the whole image, letterboxed on the theme background.
+    Contain,
+}
+
+/// Default overlay opacity for a bare `dim` option.
+const DEFAULT_DIM: f32 = 0.5;
+
+/// Parse an `@background` value:
+///
+/// - `#1E1E1E` or `#1E1E1E80` — solid color
+/// - `linear-gradient(135deg, #0F2027, #2C5364)` — gradient (angle optional)
+/// - `images/hero.jpg contain dim=0.6` — image, with `cover` (default) or
+///   `contain` and an optional `dim` / `dim=<0..1>` overlay
+///
+/// Options may follow colors and gradients too. Returns `None` for an empty value.
+pub fn parse(value: &str) -> Option<Background> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    // Peel recognized options off the end, so image paths may contain spaces
+    let mut fit = None;
+    let mut dim = 0.0;
+    let mut rest = value;
+    while let Some((head, last)) = rest.rsplit_once(char::is_whitespace) {
+        match last {
+            "cover" => fit = Some(BackgroundFit::Cover),
+            "contain" => fit = Some(BackgroundFit::Contain),
+            "dim" => dim = DEFAULT_DIM,
+            _ => match last
+                .strip_prefix("dim=")
+                .and_then(|v| v.parse::<f32>().ok())
+            {
+                Some(v) => dim = v.clamp(0.0, 1.0),
+                None => break,
+            },
+        }
+        rest = head.trim_end();
+    }
+
+    let fill = if let Some(color) = parse_hex_color(rest) {
+        BackgroundFill::Color(color)
+    } else if let Some(gradient) = parse_gradient(rest) {
+        gradient
+    } else {
+        BackgroundFill::Image {
+            path: rest.to_string(),
+            fit: fit.unwrap_or_default(),
+        }
+    };
+
+    Some(Background { fill, dim })
+}
+
+/// Parse `#RGB`, `#RRGGBB` or `#RRGGBBAA` into RGBA.
+pub fn parse_hex_color(value: &str) -> Option<[u8; 4]> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => {
+            let mut rgb = [0u8; 3];
+            for (c, out) in hex.chars().zip(&mut rgb) {
+                let v = c.to_digit(16)? as u8;
+                *out = v * 17;
+            }
+            Some([rgb[0], rgb[1], rgb[2], 255])
+        }
+        6 => Some([byte(0)?, byte(2)?, byte(4)?, 255]),
+        8 => Some([byte(0)?, byte(2)?, byte(4)?, byte(6)?]),
+        _ => None,
+    }
+}
+
+/// Parse `linear-gradient([<angle>deg,] <color>, <color>, ...)`.
+fn parse_gradient(value: &str) -> Option<BackgroundFill> {
+    let inner = value.strip_prefix("linear-gradient(")?.strip_suffix(')')?;
+    let mut parts = inner.split(',').map(str::trim).peekable();
+
+    // Default direction is top to bottom, as in CSS
+    let mut angle = 180.0;
+    if let Some(deg) = parts.peek().and_then(|p| p.strip_suffix("deg")) {
+        angle = deg.trim().parse().ok()?;
+        parts.next();
+    }
+
+    let stops: Vec<[u8; 4]> = parts.map(parse_hex_color).collect::<Option<_>>()?;
+    if stops.len() < 2 {
+        return None;
+    }
+    Some(BackgroundFill::Gradient { angle, stops })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        let bg = parse("#1E1E1E").unwrap();
+        assert_eq!(bg.fill, BackgroundFill::Color([0x1E, 0x1E, 0x1E, 255]));
+        assert_eq!(bg.dim, 0.0);
+        assert_eq!(parse_hex_color("#fff"), Some([255, 255, 255, 255]));
+        assert_eq!(parse_hex_color("#00000080"), Some([0, 0, 0, 0x80]));
+        assert_eq!(parse_hex_color("#12345"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn parses_gradients() {
+        let bg = parse("linear-gradient(135deg, #0F2027, #2C5364)").unwrap();
+        assert_eq!(
+            bg.fill,
+            BackgroundFill::Gradient {
+                angle: 135.0,
+                stops: vec![[0x0F, 0x20, 0x27, 255], [0x2C, 0x53, 0x64, 255]],
+            }
+        );
+
+        let bg = parse("linear-gradient(#000, #fff, #000)").unwrap();
+        assert!(matches!(
+            bg.fill,
+            BackgroundFill::Gradient { angle: 180.0, ref stops } if stops.len() == 3
+        ));
+    }
+
+    #[test]
+    fn parses_images_with_options() {
+        let bg = parse("images/hero shot.jpg contain dim=0.6").unwrap();
+        assert_eq!(
+            bg.fill,
+            BackgroundFill::Image {
+                path: "images/hero shot.jpg".to_string(),
+                fit: BackgroundFit::Contain,
+            }
+        );
+        assert_eq!(bg.dim, 0.6);
+
+        let bg = parse("photo.png dim").unwrap();
+        assert_eq!(
+            bg.fill,
+            BackgroundFill::Image {
+                path: "photo.png".to_string(),
+                fit: BackgroundFit::Cover,
+            }
+        );
+        assert_eq!(bg.dim, DEFAULT_DIM);
+    }
+}
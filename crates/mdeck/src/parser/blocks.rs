@@ -1,4 +1,8 @@
-use super::{Block, Directive, ImageDirectives, Inline, ListItem, ListMarker};
+use super::diagram::{self, DiagramSyntax};
+use super::{
+    Alignment, Block, CodeOptions, Directive, ImageDirectives, Inline, ListItem, ListMarker,
+    include, mermaid,
+};
 
 /// Extract @ directives from the beginning of a slide's raw text.
 /// Returns (directives, remaining content).
@@ -25,6 +29,97 @@ pub fn extract_directives(raw: &str) -> (Vec<Directive>, String) {
     (directives, remaining_lines.join("\n"))
 }
 
+/// Extract speaker notes from a slide's content.
+/// Notes are everything after a `???` line, plus the body of any
+/// `<!-- notes: ... -->` comment. Fenced code blocks are left untouched.
+/// Returns (remaining content, notes).
+pub fn extract_notes(content: &str) -> (String, Option<String>) {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut remaining_lines = Vec::new();
+    let mut notes: Vec<String> = Vec::new();
+    let mut fence: Option<String> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if let Some(open) = &fence {
+            // Only a line of fence characters closes, as in parse_code_block
+            let fence_char = open.chars().next().unwrap_or('`');
+            if trimmed.len() >= open.len() && trimmed.chars().all(|c| c == fence_char) {
+                fence = None;
+            }
+            remaining_lines.push(line);
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let fence_char = trimmed.chars().next().unwrap_or('`');
+            fence = Some(trimmed.chars().take_while(|&c| c == fence_char).collect());
+            remaining_lines.push(line);
+            i += 1;
+            continue;
+        }
+
+        // Trailing notes section: everything after ???
+        if trimmed == "???" {
+            notes.push(lines[i + 1..].join("\n"));
+            break;
+        }
+
+        // Notes comment: <!-- notes: ... --> (may span several lines)
+        if let Some(after_open) = trimmed.strip_prefix("<!--") {
+            let mut body = after_open.to_string();
+            let mut end = i;
+            while !body.contains("-->") && end + 1 < lines.len() {
+                end += 1;
+                body.push('\n');
+                body.push_str(lines[end]);
+            }
+            // An unclosed comment stays on the slide rather than hiding the rest
+            if body.contains("-->")
+                && let Some(text) = parse_notes_comment(&body)
+            {
+                notes.push(text);
+                i = end + 1;
+                continue;
+            }
+        }
+
+        remaining_lines.push(line);
+        i += 1;
+    }
+
+    let notes = notes
+        .iter()
+        .map(|n| n.trim())
+        .filter(|n| !n.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let notes = if notes.is_empty() { None } else { Some(notes) };
+    (remaining_lines.join("\n"), notes)
+}
+
+/// Parse the body of an HTML comment (text after `<!--`) as a notes comment.
+/// Returns `None` for ordinary comments.
+fn parse_notes_comment(body: &str) -> Option<String> {
+    let inner = body.split("-->").next()?.trim_start();
+    let keyword = inner.get(..5)?;
+    if !keyword.eq_ignore_ascii_case("notes") {
+        return None;
+    }
+    let rest = &inner[5..];
+    let rest = if let Some(r) = rest.strip_prefix(':') {
+        r
+    } else if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        rest
+    } else {
+        return None;
+    };
+    Some(rest.trim().to_string())
+}
+
 fn parse_directive_line(line: &str) -> Option<Directive> {
     if !line.starts_with('@') {
         return None;
@@ -92,6 +187,22 @@ pub fn parse(content: &str) -> Vec<Block> {
             continue;
         }
 
+        // Display math: $$ ... $$. Without a closing $$ the line is plain text.
+        if trimmed.starts_with("$$") {
+            match parse_math_block(&lines, i) {
+                Some((block, end)) => {
+                    blocks.push(block);
+                    i = end;
+                }
+                None => {
+                    let inlines = super::inline::parse(trimmed);
+                    blocks.push(Block::Paragraph { inlines });
+                    i += 1;
+                }
+            }
+            continue;
+        }
+
         // Image: ![alt](path)
         if trimmed.starts_with("![") {
             if let Some(img) = parse_image(trimmed) {
@@ -189,9 +300,11 @@ fn parse_code_block(lines: &[&str], start: usize, fence_char: char) -> (Block, u
     let fence_prefix: String = opening.chars().take_while(|&c| c == fence_char).collect();
     let fence_len = fence_prefix.len();
 
-    // Parse language and highlight spec from opening line
+    // Parse language, highlight spec and include options from opening line
     let after_fence = &opening[fence_len..];
-    let (language, highlight_lines, is_diagram) = parse_code_info(after_fence.trim());
+    let (info, include) = include::parse_options(after_fence.trim());
+    let (info, options) = parse_code_options(&info);
+    let (language, highlight_steps, syntax) = parse_code_info(&info);
 
     let mut code_lines = Vec::new();
     let mut i = start + 1;
@@ -215,28 +328,123 @@ fn parse_code_block(lines: &[&str], start: usize, fence_char: char) -> (Block, u
 
     let code = code_lines.join("\n");
 
-    if is_diagram {
-        (Block::Diagram { content: code }, i)
+    // Included files are always shown as code, highlighted by their
+    // extension unless a language is given
+    if let Some(include) = include {
+        let language = language.or_else(|| {
+            std::path::Path::new(&include.file)
+                .extension()
+                .map(|ext| ext.to_string_lossy().into_owned())
+        });
+        return (
+            Block::CodeBlock {
+                language,
+                code,
+                highlight_steps,
+                include: Some(include),
+                options,
+            },
+            i,
+        );
+    }
+
+    if language.as_deref() == Some("math") {
+        return (Block::Math { tex: code }, i);
+    }
+
+    // Mermaid types that can't be drawn stay as code
+    let syntax = syntax.filter(|&s| s == DiagramSyntax::Native || mermaid::parse(&code).is_some());
+    if let Some(syntax) = syntax {
+        let direction = diagram::Direction::from_info(after_fence.trim());
+        (
+            Block::Diagram {
+                content: code,
+                direction,
+                syntax,
+            },
+            i,
+        )
     } else {
         (
             Block::CodeBlock {
                 language,
                 code,
-                highlight_lines,
+                highlight_steps,
+                include: None,
+                options,
             },
             i,
         )
     }
 }
 
-fn parse_code_info(info: &str) -> (Option<String>, Vec<usize>, bool) {
+/// `$$ tex $$` on one line, or `$$` ... `$$` around several. `None` if no
+/// closing `$$` follows before a blank line or a code fence.
+fn parse_math_block(lines: &[&str], start: usize) -> Option<(Block, usize)> {
+    let first = &lines[start].trim()[2..];
+    let (tex, end) = match first.find("$$") {
+        Some(close) => (first[..close].to_string(), start + 1),
+        None => {
+            // Look for the closing $$ before taking any lines
+            let close = lines[start + 1..]
+                .iter()
+                .map(|line| line.trim())
+                .take_while(|line| {
+                    !line.is_empty() && !line.starts_with("```") && !line.starts_with("~~~")
+                })
+                .position(|line| line.contains("$$"))?
+                + start
+                + 1;
+            let last = lines[close].trim();
+            let mut tex = vec![first];
+            tex.extend(lines[start + 1..close].iter().map(|line| line.trim()));
+            tex.push(&last[..last.find("$$")?]);
+            (tex.join("\n"), close + 1)
+        }
+    };
+    Some((
+        Block::Math {
+            tex: tex.trim().to_string(),
+        },
+        end,
+    ))
+}
+
+/// Take `numbers`, `numbers=N` and `diff` out of a code fence's info
+/// string, returning the rest of it. A leading `diff` is the language of a
+/// plain diff, so it comes out too.
+fn parse_code_options(info: &str) -> (String, CodeOptions) {
+    if info.starts_with("@diagram") {
+        return (info.to_string(), CodeOptions::default());
+    }
+    let mut options = CodeOptions::default();
+    let mut rest = Vec::new();
+    for word in info.split_whitespace() {
+        if word == "diff" {
+            options.diff = true;
+        } else if word == "numbers" {
+            options.line_numbers = Some(1);
+        } else if let Some(start) = word
+            .strip_prefix("numbers=")
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            options.line_numbers = Some(start);
+            options.explicit_start = true;
+        } else {
+            rest.push(word);
+        }
+    }
+    (rest.join(" "), options)
+}
+
+fn parse_code_info(info: &str) -> (Option<String>, Vec<Vec<usize>>, Option<DiagramSyntax>) {
     if info.is_empty() {
-        return (None, vec![], false);
+        return (None, vec![], None);
     }
 
     // Check for @diagram
     if info.starts_with("@diagram") {
-        return (None, vec![], true);
+        return (None, vec![], Some(DiagramSyntax::Native));
     }
 
     // Parse language and optional highlight spec
@@ -259,10 +467,18 @@ fn parse_code_info(info: &str) -> (Option<String>, Vec<usize>, bool) {
         Some(lang_part.to_string())
     };
 
-    (language, highlight_part, false)
+    let syntax = (lang_part == "mermaid").then_some(DiagramSyntax::Mermaid);
+    (language, highlight_part, syntax)
 }
 
-fn parse_highlight_spec(spec: &str) -> Vec<usize> {
+/// `{2,4-6}` highlights lines 2 and 4 to 6; `{1-3|5|7-9}` highlights 1 to 3,
+/// then moves to 5 and then 7 to 9 as the slide is stepped through. An empty
+/// stage (`{|1-3}`) highlights nothing.
+fn parse_highlight_spec(spec: &str) -> Vec<Vec<usize>> {
+    spec.split('|').map(parse_highlight_stage).collect()
+}
+
+fn parse_highlight_stage(spec: &str) -> Vec<usize> {
     let mut lines = Vec::new();
     for part in spec.split(',') {
         let part = part.trim();
@@ -378,18 +594,34 @@ fn parse_table(lines: &[&str], start: usize) -> (Option<Block>, usize) {
         return (None, i);
     }
 
-    // First line = headers
+    // First line = headers, second = alignments, the rest = data rows
     let headers = parse_table_row(table_lines[0]);
+    let alignments = parse_table_alignments(table_lines[1]);
 
-    // Second line = separator (skip)
-    // Remaining lines = data rows
+    let mut step = 0;
+    let mut row_steps = Vec::new();
     let rows: Vec<Vec<Vec<Inline>>> = table_lines
         .iter()
         .skip(2)
-        .map(|line| parse_table_row(line))
+        .map(|line| {
+            let (next_step, line) = strip_row_marker(line);
+            if next_step {
+                step += 1;
+            }
+            row_steps.push(step);
+            parse_table_row(&line)
+        })
         .collect();
 
-    (Some(Block::Table { headers, rows }), i)
+    (
+        Some(Block::Table {
+            headers,
+            rows,
+            alignments,
+            row_steps,
+        }),
+        i,
+    )
 }
 
 fn parse_table_row(line: &str) -> Vec<Vec<Inline>> {
@@ -400,6 +632,36 @@ fn parse_table_row(line: &str) -> Vec<Vec<Inline>> {
         .collect()
 }
 
+fn parse_table_alignments(line: &str) -> Vec<Alignment> {
+    line.trim()
+        .trim_matches('|')
+        .split('|')
+        .map(|cell| {
+            let cell = cell.trim();
+            match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) if cell.len() > 1 => Alignment::Center,
+                (false, true) => Alignment::Right,
+                _ => Alignment::Left,
+            }
+        })
+        .collect()
+}
+
+/// Take a leading `+` off a row's first cell, as in `| + Parsing | Done |`,
+/// reporting whether there was one.
+fn strip_row_marker(line: &str) -> (bool, String) {
+    let body = line.trim().strip_prefix('|').unwrap_or(line.trim());
+    let first = body.trim_start();
+    match first.strip_prefix('+') {
+        Some(rest)
+            if rest.starts_with(char::is_whitespace) || rest.trim_start().starts_with('|') =>
+        {
+            (true, format!("| {rest}"))
+        }
+        _ => (false, line.to_string()),
+    }
+}
+
 fn is_list_start(line: &str) -> bool {
     if line.len() < 2 {
         return false;
@@ -590,6 +852,7 @@ fn parse_paragraph(lines: &[&str], start: usize) -> (Block, usize) {
             || trimmed.starts_with('#')
             || trimmed.starts_with("```")
             || trimmed.starts_with("~~~")
+            || trimmed.starts_with("$$")
             || trimmed.starts_with("![")
             || trimmed.starts_with("> ")
             || trimmed == ">"
@@ -629,6 +892,44 @@ mod tests {
         assert!(content.contains("# Title"));
     }
 
+    #[test]
+    fn test_extract_notes_trailing() {
+        let (content, notes) =
+            extract_notes("# Title\n\nBody\n\n???\nRemember the demo.\n\nSmile.");
+        assert_eq!(content, "# Title\n\nBody\n");
+        assert_eq!(notes.as_deref(), Some("Remember the demo.\n\nSmile."));
+    }
+
+    #[test]
+    fn test_extract_notes_comment() {
+        let (content, notes) = extract_notes(
+            "# Title\n<!-- notes: Short one -->\n<!--\nnotes\nLine one\nLine two\n-->\nBody",
+        );
+        assert_eq!(content, "# Title\nBody");
+        assert_eq!(notes.as_deref(), Some("Short one\n\nLine one\nLine two"));
+    }
+
+    #[test]
+    fn test_extract_notes_ignores_code_and_plain_comments() {
+        let raw = "```\n???\n```\n<!-- not notes -->";
+        let (content, notes) = extract_notes(raw);
+        assert_eq!(content, raw);
+        assert!(notes.is_none());
+    }
+
+    #[test]
+    fn test_extract_notes_unclosed_comment_and_fences() {
+        let raw = "<!-- notes: never closed\n\n- Point";
+        let (content, notes) = extract_notes(raw);
+        assert_eq!(content, raw);
+        assert!(notes.is_none());
+
+        let raw = "```\n```rust\n<!-- notes: inside -->\n```\n<!-- notes: outside -->";
+        let (content, notes) = extract_notes(raw);
+        assert_eq!(content, "```\n```rust\n<!-- notes: inside -->\n```");
+        assert_eq!(notes.as_deref(), Some("outside"));
+    }
+
     #[test]
     fn test_parse_heading() {
         let blocks = parse("# Title");
@@ -655,6 +956,84 @@ mod tests {
         assert!(matches!(&blocks[0], Block::Diagram { .. }));
     }
 
+    #[test]
+    fn test_parse_diagram_direction() {
+        let blocks = parse("```@diagram direction: TB\n- A -> B\n```");
+        assert!(matches!(
+            &blocks[0],
+            Block::Diagram {
+                direction: diagram::Direction::TopBottom,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_mermaid_block() {
+        let blocks = parse("```mermaid\nflowchart LR\n  A --> B\n```");
+        assert!(matches!(
+            &blocks[0],
+            Block::Diagram {
+                syntax: DiagramSyntax::Mermaid,
+                ..
+            }
+        ));
+
+        // Diagram types without a native renderer stay as code
+        let blocks = parse("```mermaid\npie\n  \"A\" : 1\n```");
+        if let Block::CodeBlock { language, .. } = &blocks[0] {
+            assert_eq!(language.as_deref(), Some("mermaid"));
+        } else {
+            panic!("Expected CodeBlock");
+        }
+    }
+
+    #[test]
+    fn test_parse_math_block() {
+        let blocks = parse("Before\n$$\n\\frac{a}{b}\n= c\n$$\nAfter");
+        assert_eq!(blocks.len(), 3);
+        if let Block::Math { tex } = &blocks[1] {
+            assert_eq!(tex, "\\frac{a}{b}\n= c");
+        } else {
+            panic!("Expected Math");
+        }
+
+        let blocks = parse("$$ x^2 $$\n\n```math\ny\n```");
+        assert!(matches!(&blocks[0], Block::Math { tex } if tex == "x^2"));
+        assert!(matches!(&blocks[1], Block::Math { tex } if tex == "y"));
+    }
+
+    #[test]
+    fn test_unclosed_math_is_text() {
+        let blocks = parse("$$ x^2\n\n- item\n\n```\ncode\n```");
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(&blocks[0], Block::Paragraph { .. }));
+        assert!(matches!(&blocks[1], Block::List { .. }));
+        assert!(matches!(&blocks[2], Block::CodeBlock { .. }));
+
+        let blocks = parse("Costs\n$$$ budget\nMore text");
+        assert_eq!(blocks.len(), 3);
+        assert!(blocks.iter().all(|b| matches!(b, Block::Paragraph { .. })));
+
+        // A $$ further down, in code, doesn't close it
+        let blocks = parse("$$ x^2\nSome prose\n```sh\necho $$\n```");
+        assert_eq!(blocks.len(), 3);
+        assert!(matches!(&blocks[0], Block::Paragraph { .. }));
+        assert!(matches!(&blocks[1], Block::Paragraph { .. }));
+        match &blocks[2] {
+            Block::CodeBlock { code, .. } => assert_eq!(code, "echo $$"),
+            other => panic!("Expected CodeBlock, got {other:?}"),
+        }
+
+        // Multi-line math still closes on a later line
+        let blocks = parse("$$\nx^2\n+ y^2\n$$");
+        assert_eq!(blocks.len(), 1);
+        match &blocks[0] {
+            Block::Math { tex } => assert_eq!(tex, "x^2\n+ y^2"),
+            other => panic!("Expected Math, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_parse_image() {
         let blocks = parse("![Photo @fill](photo.jpg)");
@@ -696,7 +1075,7 @@ mod tests {
         let input = "| A | B |\n|---|---|\n| 1 | 2 |";
         let blocks = parse(input);
         assert_eq!(blocks.len(), 1);
-        if let Block::Table { headers, rows } = &blocks[0] {
+        if let Block::Table { headers, rows, .. } = &blocks[0] {
             assert_eq!(headers.len(), 2);
             assert_eq!(rows.len(), 1);
         } else {
@@ -704,6 +1083,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_table_alignment_and_steps() {
+        use crate::parser::inlines_to_text;
+        let input = "| A | B | C | D |\n|:--|:-:|--:|---|\n| 1 | 2 | 3 | 4 |\n| + 5 | 6 | 7 | 8 |\n\
+                     | 9 | 10 | 11 | 12 |\n|+| x | y | z |\n| +1% | a | b | c |";
+        let Block::Table {
+            rows,
+            alignments,
+            row_steps,
+            ..
+        } = &parse(input)[0]
+        else {
+            panic!("Expected Table");
+        };
+        assert_eq!(
+            alignments,
+            &[
+                Alignment::Left,
+                Alignment::Center,
+                Alignment::Right,
+                Alignment::Left
+            ]
+        );
+        assert_eq!(row_steps, &[0, 1, 1, 2, 2]);
+        assert_eq!(inlines_to_text(&rows[1][0]), "5");
+        assert_eq!(inlines_to_text(&rows[3][0]), "");
+        assert_eq!(inlines_to_text(&rows[4][0]), "+1%");
+        assert_eq!(rows[1].len(), 4);
+    }
+
     #[test]
     fn test_parse_unordered_list() {
         let blocks = parse("- First\n- Second\n- Third");
@@ -744,7 +1153,47 @@ mod tests {
     #[test]
     fn test_highlight_spec() {
         let result = parse_highlight_spec("3,5-7");
-        assert_eq!(result, vec![3, 5, 6, 7]);
+        assert_eq!(result, vec![vec![3, 5, 6, 7]]);
+    }
+
+    #[test]
+    fn test_stepped_highlight_spec() {
+        let blocks = parse("```rust {|1-2| 4 |6,8}\nlet a = 1;\n```");
+        let Block::CodeBlock {
+            highlight_steps, ..
+        } = &blocks[0]
+        else {
+            panic!("Expected CodeBlock");
+        };
+        assert_eq!(highlight_steps, &[vec![], vec![1, 2], vec![4], vec![6, 8]]);
+    }
+
+    #[test]
+    fn test_code_options() {
+        let code = |src: &str| match parse(src).remove(0) {
+            Block::CodeBlock {
+                language,
+                highlight_steps,
+                options,
+                ..
+            } => (language, highlight_steps, options),
+            other => panic!("Expected CodeBlock, got {other:?}"),
+        };
+        let (language, steps, options) = code("```rust numbers=120 diff {2}\n+x\n```");
+        assert_eq!(language.as_deref(), Some("rust"));
+        assert_eq!(steps, [vec![2]]);
+        assert_eq!(options.line_numbers, Some(120));
+        assert!(options.explicit_start);
+        assert!(options.diff);
+
+        let (language, _, options) = code("```diff\n-a\n+b\n```");
+        assert_eq!(language, None);
+        assert!(options.diff);
+
+        let (_, _, options) = code("```py numbers\nx\n```");
+        assert_eq!(options.line_numbers, Some(1));
+        assert!(!options.explicit_start);
+        assert!(!options.diff);
     }
 
     #[test]
@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+
+/// A parsed `@diagram` block.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagram {
+    pub nodes: Vec<DiagramNode>,
+    pub edges: Vec<DiagramEdge>,
+}
+
+impl Diagram {
+    /// Number of reveal steps (`+` lines) in the diagram.
+    pub fn steps(&self) -> usize {
+        let nodes = self.nodes.iter().map(|n| n.step);
+        nodes
+            .chain(self.edges.iter().map(|e| e.step))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The step at which an edge appears: its own, or later if one of its
+    /// components is revealed after it.
+    pub fn edge_step(&self, edge: &DiagramEdge) -> usize {
+        let node_step = |name: &str| {
+            self.nodes
+                .iter()
+                .find(|n| n.name == name)
+                .map_or(0, |n| n.step)
+        };
+        edge.step
+            .max(node_step(&edge.from))
+            .max(node_step(&edge.to))
+    }
+}
+
+/// The language a diagram block is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DiagramSyntax {
+    /// ```` ```@diagram ````
+    #[default]
+    Native,
+    /// ```` ```mermaid ````, see [`super::mermaid`].
+    Mermaid,
+}
+
+/// Flow direction of an automatically laid out diagram, from
+/// `@diagram direction: LR|TB` on the fence line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Direction {
+    /// Layers run left to right.
+    #[default]
+    LeftRight,
+    /// Layers run top to bottom.
+    TopBottom,
+}
+
+impl Direction {
+    /// Read `direction: ...` from a fence info string. Missing or unknown
+    /// values fall back to left to right.
+    pub fn from_info(info: &str) -> Self {
+        let Some(start) = info.find("direction:") else {
+            return Self::default();
+        };
+        let value = info[start + "direction:".len()..]
+            .split_whitespace()
+            .next()
+            .unwrap_or("");
+        match value.to_ascii_uppercase().as_str() {
+            "TB" | "TD" => Direction::TopBottom,
+            _ => Direction::LeftRight,
+        }
+    }
+}
+
+/// A component, declared explicitly or inferred from a relationship.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramNode {
+    pub name: String,
+    pub label: String,
+    /// Icon name from the built-in set. `None` draws the node without an icon.
+    pub icon: Option<String>,
+    /// Grid position from `pos: x,y` (1-based). `None` is laid out automatically.
+    pub pos: Option<(f32, f32)>,
+    pub style: NodeStyle,
+    pub shape: NodeShape,
+    /// Reveal step: 0 is visible from the start.
+    pub step: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NodeStyle {
+    #[default]
+    Primary,
+    Secondary,
+    Muted,
+}
+
+/// Outline of a component. `Auto` is a pill, or a card when the diagram uses
+/// icons; the others come from Mermaid node syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NodeShape {
+    #[default]
+    Auto,
+    /// `[text]`
+    Rect,
+    /// `(text)`
+    Rounded,
+    /// `([text])`
+    Stadium,
+    /// `((text))`
+    Circle,
+    /// `{text}`
+    Diamond,
+    /// `{{text}}`
+    Hexagon,
+    /// `[(text)]`
+    Cylinder,
+}
+
+/// A relationship, kept in the order it was written: `A <- B` has `from: A`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagramEdge {
+    pub from: String,
+    pub to: String,
+    pub label: String,
+    pub arrow: Arrow,
+    /// Reveal step: 0 is visible from the start.
+    pub step: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Arrow {
+    /// `->`
+    #[default]
+    Forward,
+    /// `<-`
+    Reverse,
+    /// `<->`
+    Both,
+    /// `--`, dashed and undirected
+    Dashed,
+    /// `-->`
+    DashedForward,
+    /// Solid and undirected. Only written in Mermaid, as `---`.
+    Line,
+}
+
+impl Arrow {
+    /// Arrow tokens as written between two component names.
+    const TOKENS: [(&str, Arrow); 5] = [
+        (" <-> ", Arrow::Both),
+        (" --> ", Arrow::DashedForward),
+        (" -> ", Arrow::Forward),
+        (" <- ", Arrow::Reverse),
+        (" -- ", Arrow::Dashed),
+    ];
+
+    pub fn is_dashed(self) -> bool {
+        matches!(self, Arrow::Dashed | Arrow::DashedForward)
+    }
+
+    /// Whether a head is drawn at the `to` end.
+    pub fn head_at_end(self) -> bool {
+        matches!(self, Arrow::Forward | Arrow::Both | Arrow::DashedForward)
+    }
+
+    /// Whether a head is drawn at the `from` end.
+    pub fn head_at_start(self) -> bool {
+        matches!(self, Arrow::Reverse | Arrow::Both)
+    }
+
+    /// Whether the relationship points one way.
+    pub fn is_directed(self) -> bool {
+        self.head_at_end() != self.head_at_start()
+    }
+}
+
+/// Find the first arrow in a line: its byte range and kind.
+fn find_arrow(s: &str) -> Option<(usize, usize, Arrow)> {
+    Arrow::TOKENS
+        .iter()
+        .filter_map(|&(token, arrow)| s.find(token).map(|i| (i, i + token.len(), arrow)))
+        .min_by_key(|&(start, _, _)| start)
+}
+
+impl DiagramNode {
+    pub fn new(name: &str, step: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            label: name.to_string(),
+            icon: None,
+            pos: None,
+            style: NodeStyle::default(),
+            shape: NodeShape::default(),
+            step,
+        }
+    }
+}
+
+/// Parse a diagram from `- Node: label (icon: ..., pos: x,y)` component lines
+/// and `- A -> B: label` relationship lines (also `<-`, `<->`, `--` and `-->`).
+/// Lines starting with `#` are comments.
+///
+/// As in lists, `+` lines appear on the next reveal step and `*` lines with
+/// the previous one. Components inferred from a relationship appear with it.
+pub fn parse(content: &str) -> Diagram {
+    let mut diagram = Diagram::default();
+    let mut seen_nodes: HashMap<String, usize> = HashMap::new();
+    let mut step_counter = 0;
+
+    let mut node_index = |diagram: &mut Diagram, name: &str, step: usize| -> usize {
+        *seen_nodes.entry(name.to_string()).or_insert_with(|| {
+            diagram.nodes.push(DiagramNode::new(name, step));
+            diagram.nodes.len() - 1
+        })
+    };
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+
+        // Strip list-style prefixes: - , + , *
+        let (trimmed, step) = if let Some(rest) = trimmed.strip_prefix("+ ") {
+            step_counter += 1;
+            (rest, step_counter)
+        } else if let Some(rest) = trimmed.strip_prefix("* ") {
+            (rest, step_counter)
+        } else {
+            (trimmed.strip_prefix("- ").unwrap_or(trimmed), 0)
+        };
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // Split off trailing metadata like (icon: ..., pos: ...) FIRST, so that
+        // colons inside the parens don't confuse node vs edge detection.
+        let (trimmed, metadata) = split_metadata(trimmed);
+
+        if let Some((arrow_start, arrow_end, arrow)) = find_arrow(trimmed) {
+            let from = trimmed[..arrow_start].trim().to_string();
+            let rest = &trimmed[arrow_end..];
+            let (to, label) = split_label(rest);
+            let to = to.to_string();
+
+            // Auto-create nodes for edges
+            node_index(&mut diagram, &from, step);
+            node_index(&mut diagram, &to, step);
+
+            diagram.edges.push(DiagramEdge {
+                from,
+                to,
+                label: label.unwrap_or_default().to_string(),
+                arrow,
+                step,
+            });
+        } else {
+            let (name, label) = split_label(trimmed);
+            if name.is_empty() {
+                continue;
+            }
+            let idx = node_index(&mut diagram, name, step);
+            let node = &mut diagram.nodes[idx];
+            // A declaration decides when the component appears, even after
+            // a relationship mentioned it
+            node.step = step;
+            if let Some(label) = label {
+                node.label = label.to_string();
+            }
+            for (key, value) in &metadata {
+                apply_metadata(node, key, value);
+            }
+        }
+    }
+
+    diagram
+}
+
+/// Split `Name: label` into the name and the optional label.
+fn split_label(s: &str) -> (&str, Option<&str>) {
+    match s.find(": ") {
+        Some(colon_pos) => (s[..colon_pos].trim(), Some(s[colon_pos + 2..].trim())),
+        None => (s.trim(), None),
+    }
+}
+
+/// Split a trailing `(key: value, ...)` group off a line. Parentheses without
+/// a `key:` inside are part of the text, e.g. `API (v2)`.
+fn split_metadata(s: &str) -> (&str, Vec<(String, String)>) {
+    let trimmed = s.trim_end();
+    let Some(inner_end) = trimmed.strip_suffix(')') else {
+        return (trimmed, Vec::new());
+    };
+    let Some(paren_start) = inner_end.rfind('(') else {
+        return (trimmed, Vec::new());
+    };
+    // Only metadata if there's whitespace before the paren
+    if paren_start == 0 || trimmed.as_bytes()[paren_start - 1] != b' ' {
+        return (trimmed, Vec::new());
+    }
+    let inner = &inner_end[paren_start + 1..];
+    if !inner.contains(':') {
+        return (trimmed, Vec::new());
+    }
+
+    // Split on commas, but keep pieces without a key with the previous value
+    // so `pos: 1,2` stays one entry.
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for piece in inner.split(',') {
+        match piece.split_once(':') {
+            Some((key, value)) => pairs.push((key.trim().to_string(), value.trim().to_string())),
+            None => {
+                if let Some((_, value)) = pairs.last_mut() {
+                    value.push(',');
+                    value.push_str(piece.trim());
+                }
+            }
+        }
+    }
+
+    (trimmed[..paren_start].trim_end(), pairs)
+}
+
+/// Apply one `key: value` pair. Diagrams are parsed while drawing, so invalid
+/// values are ignored rather than reported.
+fn apply_metadata(node: &mut DiagramNode, key: &str, value: &str) {
+    match key {
+        "icon" => node.icon = Some(value.to_string()),
+        "label" => node.label = value.to_string(),
+        "pos" => {
+            let coord = |s: &str| {
+                s.trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| v.is_finite() && *v >= 0.0)
+            };
+            if let Some(pos) = value
+                .split_once(',')
+                .and_then(|(x, y)| Some((coord(x)?, coord(y)?)))
+            {
+                node.pos = Some(pos);
+            }
+        }
+        "style" => match value {
+            "primary" => node.style = NodeStyle::Primary,
+            "secondary" => node.style = NodeStyle::Secondary,
+            "muted" => node.style = NodeStyle::Muted,
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_component_metadata() {
+        let diagram = parse(
+            "# Components\n\
+             - User        (icon: user,      pos: 1,1)\n\
+             - Server: Web server (icon: server, pos: 2, 1, style: secondary)\n\
+             - Cache       (label: Redis, style: muted)\n\
+             - API (v2)\n",
+        );
+        assert_eq!(diagram.nodes.len(), 4);
+
+        let user = &diagram.nodes[0];
+        assert_eq!(user.name, "User");
+        assert_eq!(user.label, "User");
+        assert_eq!(user.icon.as_deref(), Some("user"));
+        assert_eq!(user.pos, Some((1.0, 1.0)));
+        assert_eq!(user.style, NodeStyle::Primary);
+
+        let server = &diagram.nodes[1];
+        assert_eq!(server.label, "Web server");
+        assert_eq!(server.pos, Some((2.0, 1.0)));
+        assert_eq!(server.style, NodeStyle::Secondary);
+
+        let cache = &diagram.nodes[2];
+        assert_eq!(cache.label, "Redis");
+        assert_eq!(cache.pos, None);
+        assert_eq!(cache.style, NodeStyle::Muted);
+
+        // Parens without a key are part of the name
+        assert_eq!(diagram.nodes[3].name, "API (v2)");
+    }
+
+    #[test]
+    fn ignores_positions_off_the_grid() {
+        let diagram = parse(
+            "- A (icon: user, pos: inf,1)\n\
+             - B (pos: NaN,0)\n\
+             - C (pos: -1,2)\n\
+             - D (pos: 0,2.5)\n",
+        );
+        let positions: Vec<_> = diagram.nodes.iter().map(|n| n.pos).collect();
+        assert_eq!(positions, [None, None, None, Some((0.0, 2.5))]);
+        assert_eq!(diagram.nodes[0].icon.as_deref(), Some("user"));
+    }
+
+    #[test]
+    fn edges_infer_nodes_and_keep_declared_ones() {
+        let diagram = parse(
+            "- Server (icon: server, pos: 2,1)\n\
+             - User -> Server: Sends request\n\
+             - Server -> Database\n",
+        );
+        let names: Vec<&str> = diagram.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["Server", "User", "Database"]);
+        assert_eq!(diagram.nodes[0].icon.as_deref(), Some("server"));
+        assert_eq!(diagram.edges.len(), 2);
+        assert_eq!(diagram.edges[0].label, "Sends request");
+        assert_eq!(diagram.edges[1].label, "");
+    }
+
+    #[test]
+    fn direction_from_fence_info() {
+        assert_eq!(Direction::from_info("@diagram"), Direction::LeftRight);
+        assert_eq!(
+            Direction::from_info("@diagram direction: TB"),
+            Direction::TopBottom
+        );
+        assert_eq!(
+            Direction::from_info("@diagram direction: lr"),
+            Direction::LeftRight
+        );
+        assert_eq!(
+            Direction::from_info("@diagram direction: sideways"),
+            Direction::LeftRight
+        );
+    }
+
+    #[test]
+    fn reveal_steps_follow_list_markers() {
+        let diagram = parse(
+            "- User     (icon: user, pos: 1,1)\n\
+             - Server   (icon: server, pos: 2,1)\n\
+             - Server -- Logs: sends logs to\n\
+             + User -> Server: Sends request\n\
+             + Server -> Database: Queries data\n\
+             + Database -> Server: Returns results\n\
+             * Server -> User: Sends response\n",
+        );
+        let steps: Vec<usize> = diagram.edges.iter().map(|e| e.step).collect();
+        assert_eq!(steps, [0, 1, 2, 3, 3]);
+        assert_eq!(diagram.steps(), 3);
+
+        let node_steps: Vec<(&str, usize)> = diagram
+            .nodes
+            .iter()
+            .map(|n| (n.name.as_str(), n.step))
+            .collect();
+        assert_eq!(
+            node_steps,
+            [("User", 0), ("Server", 0), ("Logs", 0), ("Database", 2)]
+        );
+    }
+
+    #[test]
+    fn edges_wait_for_their_components() {
+        let diagram = parse("- A -> B\n+ C\n- A -> C");
+        assert_eq!(diagram.edges[1].step, 0);
+        assert_eq!(diagram.edge_step(&diagram.edges[0]), 0);
+        assert_eq!(diagram.edge_step(&diagram.edges[1]), 1);
+        assert_eq!(diagram.steps(), 1);
+    }
+
+    #[test]
+    fn parses_every_arrow_form() {
+        let cases = [
+            ("- A -> B: calls", Arrow::Forward),
+            ("- A <- B: calls", Arrow::Reverse),
+            ("- A <-> B: calls", Arrow::Both),
+            ("- A -- B: calls", Arrow::Dashed),
+            ("- A --> B: calls", Arrow::DashedForward),
+        ];
+        for (line, arrow) in cases {
+            let diagram = parse(line);
+            assert_eq!(
+                diagram.edges,
+                [DiagramEdge {
+                    from: "A".to_string(),
+                    to: "B".to_string(),
+                    label: "calls".to_string(),
+                    arrow,
+                    step: 0,
+                }],
+                "{line}"
+            );
+            assert_eq!(diagram.nodes.len(), 2, "{line}");
+        }
+    }
+
+    #[test]
+    fn arrow_heads_and_dashes() {
+        assert!(Arrow::Forward.head_at_end() && !Arrow::Forward.head_at_start());
+        assert!(Arrow::Reverse.head_at_start() && !Arrow::Reverse.head_at_end());
+        assert!(Arrow::Both.head_at_start() && Arrow::Both.head_at_end());
+        assert!(!Arrow::Dashed.head_at_start() && !Arrow::Dashed.head_at_end());
+        assert!(Arrow::Dashed.is_dashed() && Arrow::DashedForward.is_dashed());
+        assert!(!Arrow::Both.is_directed() && !Arrow::Dashed.is_directed());
+        assert!(Arrow::Reverse.is_directed() && Arrow::DashedForward.is_directed());
+    }
+
+    #[test]
+    fn arrows_inside_names_use_the_first_one() {
+        // Dashes inside names aren't arrows without surrounding spaces
+        let diagram = parse("- front-end --> back-end: REST");
+        assert_eq!(diagram.edges[0].from, "front-end");
+        assert_eq!(diagram.edges[0].to, "back-end");
+        assert_eq!(diagram.edges[0].arrow, Arrow::DashedForward);
+    }
+}
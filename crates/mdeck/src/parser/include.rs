@@ -0,0 +1,393 @@
+use std::path::{Path, PathBuf};
+
+use super::{Block, Presentation};
+
+/// Code pulled into a fenced block from another file with `@file=path` (or
+/// `@include=path`) on the opening fence, optionally narrowed to
+/// `lines=10-42` or `region=name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeInclude {
+    /// The path as written, relative to the deck.
+    pub file: String,
+    /// A line number or inclusive range counting from 1, within the region
+    /// when one is given.
+    pub lines: Option<String>,
+    /// Name of a `// region: name` ... `// endregion` section of the file.
+    pub region: Option<String>,
+    /// The file resolved against the deck's directory.
+    pub path: PathBuf,
+    /// Why the code couldn't be included. The block shows this instead.
+    pub error: Option<String>,
+    /// The line number in the file of each included line, which numbered
+    /// blocks show unless `numbers=N` gives a start. Region markers are left
+    /// out of the code, so the numbers can skip.
+    pub line_numbers: Vec<usize>,
+}
+
+/// Take the include options out of a code fence's info string, returning
+/// the rest of it. Without `@file=` or `@include=` the info is left alone.
+pub fn parse_options(info: &str) -> (String, Option<CodeInclude>) {
+    let mut file = None;
+    let mut lines = None;
+    let mut region = None;
+    let mut rest = Vec::new();
+    for word in info.split_whitespace() {
+        if let Some(path) = word
+            .strip_prefix("@file=")
+            .or_else(|| word.strip_prefix("@include="))
+        {
+            file = Some(path.to_string());
+        } else if let Some(range) = word.strip_prefix("lines=") {
+            lines = Some(range.to_string());
+        } else if let Some(name) = word.strip_prefix("region=") {
+            region = Some(name.to_string());
+        } else {
+            rest.push(word);
+        }
+    }
+    match file {
+        Some(file) => (
+            rest.join(" "),
+            Some(CodeInclude {
+                path: PathBuf::from(&file),
+                file,
+                lines,
+                region,
+                error: None,
+                line_numbers: Vec::new(),
+            }),
+        ),
+        None => (info.to_string(), None),
+    }
+}
+
+/// Fill in included code blocks from their files, relative to `base_path`.
+/// Blocks whose file, region or lines can't be found, or whose file is
+/// outside the deck's directory, are left empty with the reason in
+/// [`CodeInclude::error`].
+pub fn resolve(blocks: &mut [Block], base_path: &Path) {
+    let dir = if base_path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        base_path
+    };
+    let root = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    for block in blocks {
+        let Block::CodeBlock {
+            code,
+            include: Some(include),
+            ..
+        } = block
+        else {
+            continue;
+        };
+        include.path = base_path.join(&include.file);
+        match load(include, &root) {
+            Ok((text, line_numbers)) => {
+                *code = text;
+                include.error = None;
+                include.line_numbers = line_numbers;
+            }
+            Err(error) => {
+                code.clear();
+                include.error = Some(error);
+                include.line_numbers.clear();
+            }
+        }
+    }
+}
+
+/// Files included by code blocks anywhere in the deck, for live reload.
+pub fn included_files(presentation: &Presentation) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for block in presentation.slides.iter().flat_map(|s| &s.blocks) {
+        if let Block::CodeBlock {
+            include: Some(include),
+            ..
+        } = block
+            && !files.contains(&include.path)
+        {
+            files.push(include.path.clone());
+        }
+    }
+    files
+}
+
+/// One warning per code block whose include failed.
+pub fn check_includes(presentation: &Presentation) -> Vec<String> {
+    presentation
+        .slides
+        .iter()
+        .enumerate()
+        .flat_map(|(i, slide)| {
+            slide.blocks.iter().filter_map(move |block| match block {
+                Block::CodeBlock {
+                    include:
+                        Some(CodeInclude {
+                            error: Some(error), ..
+                        }),
+                    ..
+                } => Some(format!("Slide {}: {error}", i + 1)),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+/// The included lines and the line number of each in the file, as long as
+/// the file resolves to somewhere inside `root`, so a deck can't pull `../`
+/// or absolute paths into an export.
+fn load(include: &CodeInclude, root: &Path) -> Result<(String, Vec<usize>), String> {
+    let file = &include.file;
+    let cannot = |e: std::io::Error| format!("Cannot include {file}: {e}");
+    let path = include.path.canonicalize().map_err(cannot)?;
+    if !path.starts_with(root) {
+        return Err(format!(
+            "Cannot include {file}: it is outside the deck's directory"
+        ));
+    }
+    let text = std::fs::read_to_string(&path).map_err(cannot)?;
+    let lines: Vec<(usize, &str)> = match &include.region {
+        Some(name) => region(&text, name).ok_or_else(|| format!("No region '{name}' in {file}"))?,
+        None => numbered(&text).collect(),
+    };
+    let lines = match &include.lines {
+        Some(spec) => {
+            let (first, last) = parse_range(spec)
+                .ok_or_else(|| format!("Invalid line range '{spec}' for {file}"))?;
+            if last > lines.len() {
+                return Err(format!(
+                    "Lines {spec} are past the end of {file} ({} lines)",
+                    lines.len()
+                ));
+            }
+            lines[first - 1..last].to_vec()
+        }
+        None => lines,
+    };
+    let lines: Vec<(usize, &str)> = lines
+        .into_iter()
+        .filter(|(_, line)| marker(line).is_none())
+        .collect();
+    let (numbers, text): (Vec<usize>, Vec<&str>) = lines.into_iter().unzip();
+    Ok((text.join("\n"), numbers))
+}
+
+/// The lines of `text` with their numbers, counting from 1.
+fn numbered(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines().enumerate().map(|(i, line)| (i + 1, line))
+}
+
+/// `10-42` or `7`, counting from 1.
+fn parse_range(spec: &str) -> Option<(usize, usize)> {
+    let (first, last) = match spec.split_once('-') {
+        Some((first, last)) => (first.trim().parse().ok()?, last.trim().parse().ok()?),
+        None => {
+            let line = spec.trim().parse().ok()?;
+            (line, line)
+        }
+    };
+    (first >= 1 && first <= last).then_some((first, last))
+}
+
+enum Marker {
+    Start(String),
+    End,
+}
+
+/// A `region: name` or `endregion` line after any comment leader, such as
+/// `// region: setup`, `# endregion` or `<!-- region: markup -->`.
+fn marker(line: &str) -> Option<Marker> {
+    let trimmed = line.trim();
+    let comment = trimmed.trim_start_matches(['/', '#', '-', ';', '%', '<', '!', '*']);
+    if comment.len() == trimmed.len() {
+        return None;
+    }
+    let comment = comment
+        .trim()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim_end();
+    if comment.starts_with("endregion") {
+        return Some(Marker::End);
+    }
+    let name = comment.strip_prefix("region")?;
+    if !name.starts_with([':', ' ']) {
+        return None;
+    }
+    Some(Marker::Start(
+        name.trim_start_matches(':').trim().to_string(),
+    ))
+}
+
+/// The numbered lines between `region: name` and its `endregion`, leaving
+/// out the markers of any regions nested inside.
+fn region<'a>(text: &'a str, name: &str) -> Option<Vec<(usize, &'a str)>> {
+    let mut lines = numbered(text);
+    lines.find(|(_, line)| matches!(marker(line), Some(Marker::Start(n)) if n == name))?;
+    let mut depth = 0;
+    let mut body = Vec::new();
+    for (number, line) in lines {
+        match marker(line) {
+            Some(Marker::Start(_)) => depth += 1,
+            Some(Marker::End) if depth == 0 => return Some(body),
+            Some(Marker::End) => depth -= 1,
+            None => body.push((number, line)),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    const SOURCE: &str = "use std::io;\n\n// region: main\nfn main() {\n    // region: body\n    \
+                          run();\n    // endregion\n}\n// endregion\n\nfn run() {}";
+
+    #[test]
+    fn options_come_off_the_info_string() {
+        let (rest, include) = parse_options("rust {2} @file=src/lib.rs lines=3-4");
+        assert_eq!(rest, "rust {2}");
+        let include = include.unwrap();
+        assert_eq!(include.file, "src/lib.rs");
+        assert_eq!(include.lines.as_deref(), Some("3-4"));
+        assert_eq!(include.region, None);
+
+        let (rest, include) = parse_options("@include=a.py region=setup");
+        assert_eq!(rest, "");
+        assert_eq!(include.unwrap().region.as_deref(), Some("setup"));
+
+        assert_eq!(parse_options("rust lines=1").0, "rust lines=1");
+    }
+
+    #[test]
+    fn regions_and_lines_narrow_the_file() {
+        let lines = region(SOURCE, "main").unwrap();
+        assert_eq!(lines.first(), Some(&(4, "fn main() {")));
+        assert_eq!(lines.last(), Some(&(8, "}")));
+        assert_eq!(region(SOURCE, "body").unwrap(), [(6, "    run();")]);
+        assert!(region(SOURCE, "missing").is_none());
+        assert!(region("// region: open\nx", "open").is_none());
+
+        assert!(
+            matches!(marker("<!-- region: markup -->"), Some(Marker::Start(n)) if n == "markup")
+        );
+        assert!(matches!(marker("# endregion"), Some(Marker::End)));
+        assert!(marker("// regional settings").is_none());
+        assert!(marker("region: x").is_none());
+
+        assert_eq!(parse_range("10-42"), Some((10, 42)));
+        assert_eq!(parse_range("7"), Some((7, 7)));
+        assert_eq!(parse_range("0-3"), None);
+        assert_eq!(parse_range("5-2"), None);
+    }
+
+    #[test]
+    fn includes_resolve_against_the_deck() {
+        let dir = std::env::temp_dir().join(format!("mdeck-include-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.rs"), SOURCE).unwrap();
+
+        let pres = parser::parse(
+            "```rust @file=main.rs region=main lines=2-3\n```\n\n\
+             ```@file=main.rs lines=1\n```\n\n\
+             ```rust @file=main.rs lines=40-50\n```\n\n\
+             ```rust @file=gone.rs\n```",
+            &dir,
+        );
+        let blocks = &pres.slides[0].blocks;
+        let code = |i: usize| match &blocks[i] {
+            Block::CodeBlock {
+                code,
+                language,
+                include: Some(include),
+                ..
+            } => (code.as_str(), language.as_deref(), include.error.as_deref()),
+            other => panic!("Expected an included CodeBlock, got {other:?}"),
+        };
+        assert_eq!(code(0), ("    run();\n}", Some("rust"), None));
+        assert_eq!(code(1), ("use std::io;", Some("rs"), None));
+        assert_eq!(code(2).0, "");
+        assert!(code(2).2.unwrap().contains("past the end"));
+        assert!(code(3).2.unwrap().starts_with("Cannot include gone.rs"));
+
+        assert_eq!(
+            included_files(&pres),
+            [dir.join("main.rs"), dir.join("gone.rs")]
+        );
+        assert_eq!(check_includes(&pres).len(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn included_ranges_number_from_the_file() {
+        let dir =
+            std::env::temp_dir().join(format!("mdeck-include-numbers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("main.rs"), SOURCE).unwrap();
+
+        let pres = parser::parse(
+            "```rust numbers @file=main.rs region=main\n```\n\n\
+             ```rust numbers @file=main.rs region=main lines=2-3\n```\n\n\
+             ```rust numbers @file=main.rs lines=4-7\n```\n\n\
+             ```rust numbers=1 @file=main.rs lines=10-11\n```",
+            &dir,
+        );
+        let blocks: Vec<(&str, &[usize])> = pres.slides[0]
+            .blocks
+            .iter()
+            .map(|block| match block {
+                Block::CodeBlock {
+                    code,
+                    include: Some(include),
+                    ..
+                } => (code.as_str(), include.line_numbers.as_slice()),
+                other => panic!("Expected an included CodeBlock, got {other:?}"),
+            })
+            .collect();
+        // The nested region's markers are left out, so the numbers skip them
+        assert_eq!(blocks[0], ("fn main() {\n    run();\n}", &[4, 6, 8][..]));
+        assert_eq!(blocks[1], ("    run();\n}", &[6, 8][..]));
+        assert_eq!(blocks[2], ("fn main() {\n    run();", &[4, 6][..]));
+        assert_eq!(blocks[3].1, [10, 11]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn includes_stay_inside_the_deck() {
+        let base = std::env::temp_dir().join(format!("mdeck-include-root-{}", std::process::id()));
+        let dir = base.join("deck");
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(base.join("secret.txt"), "secret").unwrap();
+        std::fs::write(dir.join("src").join("main.rs"), "fn main() {}").unwrap();
+
+        let outside = base.join("secret.txt");
+        let pres = parser::parse(
+            &format!(
+                "```@file=../secret.txt\n```\n\n\
+                 ```@file={}\n```\n\n\
+                 ```rust @file=src/../src/main.rs\n```",
+                outside.display()
+            ),
+            &dir,
+        );
+        let blocks = &pres.slides[0].blocks;
+        let block = |i: usize| match &blocks[i] {
+            Block::CodeBlock {
+                code,
+                include: Some(include),
+                ..
+            } => (code.as_str(), include.error.clone()),
+            other => panic!("Expected an included CodeBlock, got {other:?}"),
+        };
+        for i in 0..2 {
+            let (code, error) = block(i);
+            assert_eq!(code, "");
+            assert!(error.unwrap().ends_with("outside the deck's directory"));
+        }
+        assert_eq!(block(2), ("fn main() {}", None));
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
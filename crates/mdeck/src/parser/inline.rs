@@ -18,6 +18,33 @@ pub fn parse(text: &str) -> Vec<Inline> {
             }
         }
 
+        // Escaped dollar sign: \$
+        if chars[i] == '\\' && peek(&chars, i + 1) == Some('$') {
+            current_text.push('$');
+            i += 2;
+            continue;
+        }
+
+        // Inline math: $tex$
+        if chars[i] == '$' {
+            if let Some((tex, end)) = parse_inline_math(&chars, i) {
+                flush_text(&mut current_text, &mut result);
+                result.push(Inline::Math(tex));
+                i = end;
+                continue;
+            }
+        }
+
+        // Slide reference: {{ref:id}}
+        if chars[i] == '{' && peek(&chars, i + 1) == Some('{') {
+            if let Some((id, end)) = parse_ref(&chars, i) {
+                flush_text(&mut current_text, &mut result);
+                result.push(Inline::Ref(id));
+                i = end;
+                continue;
+            }
+        }
+
         // Bold: **text**
         if chars[i] == '*' && peek(&chars, i + 1) == Some('*') {
             flush_text(&mut current_text, &mut result);
@@ -92,6 +119,49 @@ fn parse_inline_code(chars: &[char], start: usize) -> Option<(String, usize)> {
     None
 }
 
+/// `$tex$`, where the opening `$` isn't followed by a space and the closing
+/// one isn't preceded by a space or followed by a digit, so prices like
+/// "$5 and $10" stay text.
+fn parse_inline_math(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let first = peek(chars, start + 1)?;
+    if first.is_whitespace() || first == '$' {
+        return None;
+    }
+    let mut i = start + 1;
+    let mut tex = String::new();
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if peek(chars, i + 1).is_some() => {
+                tex.push(chars[i]);
+                tex.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            '$' => {
+                let closes = !chars[i - 1].is_whitespace()
+                    && !peek(chars, i + 1).is_some_and(|c| c.is_ascii_digit());
+                return closes.then_some((tex, i + 1));
+            }
+            c => tex.push(c),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// `{{ref:id}}`, with optional spaces around the id.
+fn parse_ref(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let rest: String = chars[start..].iter().collect();
+    let inner = rest.strip_prefix("{{")?;
+    let close = inner.find("}}")?;
+    let id = inner[..close].trim().strip_prefix("ref:")?.trim();
+    if id.is_empty() || id.contains(char::is_whitespace) {
+        return None;
+    }
+    let end = start + 2 + inner[..close].chars().count() + 2;
+    Some((id.to_string(), end))
+}
+
 fn parse_delimited(
     chars: &[char],
     start: usize,
@@ -260,4 +330,28 @@ mod tests {
         assert!(matches!(&result[0], Inline::Bold(_)));
         assert!(matches!(&result[2], Inline::Italic(_)));
     }
+
+    #[test]
+    fn test_inline_math() {
+        let result = parse(r"Euler: $e^{i\pi} + 1 = 0$, and `$x$` is code");
+        assert!(matches!(&result[1], Inline::Math(s) if s == r"e^{i\pi} + 1 = 0"));
+        assert!(matches!(&result[3], Inline::Code(s) if s == "$x$"));
+    }
+
+    #[test]
+    fn test_dollar_amounts_are_text() {
+        let result = parse(r"Costs $5 or $10, and \$x\$ is literal");
+        assert_eq!(result.len(), 1);
+        assert!(
+            matches!(&result[0], Inline::Text(s) if s == "Costs $5 or $10, and $x$ is literal")
+        );
+    }
+
+    #[test]
+    fn test_slide_ref() {
+        let result = parse("See {{ref:agenda}} and {{ ref: q-a }}, not {{name}}");
+        assert!(matches!(&result[1], Inline::Ref(id) if id == "agenda"));
+        assert!(matches!(&result[3], Inline::Ref(id) if id == "q-a"));
+        assert!(matches!(&result[4], Inline::Text(s) if s == ", not {{name}}"));
+    }
 }
@@ -0,0 +1,724 @@
+/// A parsed TeX math expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MathNode {
+    /// A variable or lowercase Greek letter, set in italics.
+    Ident(String),
+    /// Digits, punctuation, function names and symbols, set upright.
+    Upright(String),
+    /// A binary operator or relation, with space on both sides.
+    Operator(String),
+    /// `\text{...}`
+    Text(String),
+    /// `\mathbb{...}`: letters set double-struck.
+    DoubleStruck(String),
+    Row(Vec<MathNode>),
+    /// `\frac{num}{den}`, or `\binom` without the bar.
+    Frac {
+        num: Box<MathNode>,
+        den: Box<MathNode>,
+        bar: bool,
+    },
+    /// `\sqrt{body}` or `\sqrt[index]{body}`
+    Sqrt {
+        index: Option<Box<MathNode>>,
+        body: Box<MathNode>,
+    },
+    Scripts {
+        base: Box<MathNode>,
+        sub: Option<Box<MathNode>>,
+        sup: Option<Box<MathNode>>,
+    },
+    /// `\sum`, `\int`, `\lim`, ... With `limits`, scripts go above and below
+    /// in display math.
+    BigOp {
+        symbol: String,
+        limits: bool,
+    },
+    /// `\hat{x}`, `\bar{x}`, `\vec{x}`, ...
+    Accent {
+        accent: Accent,
+        body: Box<MathNode>,
+    },
+    /// `\left( ... \right)`. An empty delimiter is `\left.`.
+    Delimited {
+        open: String,
+        body: Box<MathNode>,
+        close: String,
+    },
+    /// `\begin{pmatrix} ... \end{pmatrix}` and friends.
+    Matrix {
+        rows: Vec<Vec<MathNode>>,
+        open: String,
+        close: String,
+        align: ColumnAlign,
+    },
+    /// Horizontal space in ems, e.g. `\,` or `\quad`.
+    Space(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Accent {
+    Hat,
+    Bar,
+    Vec,
+    Dot,
+    Tilde,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnAlign {
+    Center,
+    Left,
+    /// `aligned`: right, left, right, ... so columns meet at the `&`.
+    Alternate,
+}
+
+impl MathNode {
+    /// Whether the node is more than a single symbol, and needs parentheses
+    /// when written out on one line.
+    pub fn is_compound(&self) -> bool {
+        match self {
+            MathNode::Row(nodes) => nodes.len() > 1 || nodes.iter().any(MathNode::is_compound),
+            MathNode::Ident(_)
+            | MathNode::Upright(_)
+            | MathNode::Text(_)
+            | MathNode::DoubleStruck(_)
+            | MathNode::Space(_) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Parse a TeX math expression. Unknown commands are kept as written, so a
+/// typo shows up on the slide rather than disappearing.
+pub fn parse(tex: &str) -> MathNode {
+    let tokens = tokenize(tex);
+    let mut parser = Parser { tokens, pos: 0 };
+    MathNode::Row(parser.row(&[]))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Command(String),
+    Char(char),
+    Open,
+    Close,
+    Sup,
+    Sub,
+    Align,
+    NewRow,
+}
+
+fn tokenize(tex: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = tex.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\\' => match chars.next() {
+                Some('\\') => Token::NewRow,
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let mut name = c.to_string();
+                    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+                        name.push(c);
+                        chars.next();
+                    }
+                    Token::Command(name)
+                }
+                Some(c) => Token::Command(c.to_string()),
+                None => break,
+            },
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '^' => Token::Sup,
+            '_' => Token::Sub,
+            '&' => Token::Align,
+            c if c.is_whitespace() => continue,
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Atoms with their scripts, up to the end or one of `stop` (left unconsumed).
+    fn row(&mut self, stop: &[Token]) -> Vec<MathNode> {
+        let mut nodes = Vec::new();
+        while let Some(token) = self.peek() {
+            if stop.contains(token) || is_end(token) {
+                break;
+            }
+            let Some(atom) = self.atom() else {
+                continue;
+            };
+            nodes.push(self.scripts(atom));
+        }
+        nodes
+    }
+
+    /// Attach any `_` / `^` (and primes) following `base`.
+    fn scripts(&mut self, base: MathNode) -> MathNode {
+        let (mut sub, mut sup) = (None, None);
+        loop {
+            match self.peek() {
+                Some(Token::Sub) if sub.is_none() => {
+                    self.next();
+                    sub = self.argument().map(Box::new);
+                }
+                Some(Token::Sup) if sup.is_none() => {
+                    self.next();
+                    sup = self.argument().map(Box::new);
+                }
+                Some(Token::Char('\'')) if sup.is_none() => {
+                    let mut primes = String::new();
+                    while self.peek() == Some(&Token::Char('\'')) {
+                        self.next();
+                        primes.push('\u{2032}');
+                    }
+                    sup = Some(Box::new(MathNode::Upright(primes)));
+                }
+                _ => break,
+            }
+        }
+        if sub.is_none() && sup.is_none() {
+            return base;
+        }
+        MathNode::Scripts {
+            base: Box::new(base),
+            sub,
+            sup,
+        }
+    }
+
+    /// A `{group}` or a single atom, as taken by `^`, `\frac` and the like.
+    fn argument(&mut self) -> Option<MathNode> {
+        if self.peek() == Some(&Token::Open) {
+            self.next();
+            let nodes = self.row(&[Token::Close]);
+            self.next();
+            return Some(MathNode::Row(nodes));
+        }
+        self.atom()
+    }
+
+    /// Raw text of a `{...}` group, for `\text` and `\begin`.
+    fn text_argument(&mut self) -> String {
+        if self.peek() != Some(&Token::Open) {
+            return String::new();
+        }
+        self.next();
+        let mut text = String::new();
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token {
+                Token::Close if depth == 0 => break,
+                Token::Close => depth -= 1,
+                Token::Open => depth += 1,
+                Token::Char(c) => text.push(c),
+                Token::Command(name) if name.len() == 1 => text.push_str(&name),
+                Token::Command(name) => {
+                    text.push('\\');
+                    text.push_str(&name);
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+
+    fn atom(&mut self) -> Option<MathNode> {
+        match self.next()? {
+            Token::Open => {
+                let nodes = self.row(&[Token::Close]);
+                self.next();
+                Some(MathNode::Row(nodes))
+            }
+            Token::Char(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(Token::Char(c)) = self.peek() {
+                    if !c.is_ascii_digit() && *c != '.' {
+                        break;
+                    }
+                    number.push(*c);
+                    self.next();
+                }
+                Some(MathNode::Upright(number))
+            }
+            Token::Char(c) if c.is_alphabetic() => Some(MathNode::Ident(c.to_string())),
+            Token::Char(c) => Some(char_node(c)),
+            Token::Command(name) => Some(self.command(&name)),
+            // Stray `}`, `&`, `\\` and scripts without a base are skipped
+            _ => None,
+        }
+    }
+
+    fn command(&mut self, name: &str) -> MathNode {
+        if let Some(node) = symbol(name) {
+            return node;
+        }
+        match name {
+            "frac" | "dfrac" | "tfrac" | "binom" => {
+                let num = self.argument().unwrap_or(MathNode::Row(Vec::new()));
+                let den = self.argument().unwrap_or(MathNode::Row(Vec::new()));
+                let frac = MathNode::Frac {
+                    num: Box::new(num),
+                    den: Box::new(den),
+                    bar: name != "binom",
+                };
+                if name == "binom" {
+                    MathNode::Delimited {
+                        open: "(".to_string(),
+                        body: Box::new(frac),
+                        close: ")".to_string(),
+                    }
+                } else {
+                    frac
+                }
+            }
+            "sqrt" => {
+                let index = if self.peek() == Some(&Token::Char('[')) {
+                    self.next();
+                    let nodes = self.row(&[Token::Char(']')]);
+                    self.next();
+                    Some(Box::new(MathNode::Row(nodes)))
+                } else {
+                    None
+                };
+                let body = self.argument().unwrap_or(MathNode::Row(Vec::new()));
+                MathNode::Sqrt {
+                    index,
+                    body: Box::new(body),
+                }
+            }
+            "text" | "textrm" | "mbox" => MathNode::Text(self.text_argument()),
+            "mathrm" | "operatorname" | "mathbf" | "textbf" => {
+                MathNode::Upright(self.text_argument())
+            }
+            "mathbb" => MathNode::DoubleStruck(self.text_argument()),
+            "hat" | "widehat" | "bar" | "overline" | "vec" | "dot" | "tilde" | "widetilde" => {
+                let accent = match name {
+                    "hat" | "widehat" => Accent::Hat,
+                    "bar" | "overline" => Accent::Bar,
+                    "vec" => Accent::Vec,
+                    "dot" => Accent::Dot,
+                    _ => Accent::Tilde,
+                };
+                let body = self.argument().unwrap_or(MathNode::Row(Vec::new()));
+                MathNode::Accent {
+                    accent,
+                    body: Box::new(body),
+                }
+            }
+            "left" => {
+                let open = self.delimiter();
+                let nodes = self.row(&[Token::Command("right".to_string())]);
+                self.next();
+                let close = self.delimiter();
+                MathNode::Delimited {
+                    open,
+                    body: Box::new(MathNode::Row(nodes)),
+                    close,
+                }
+            }
+            "begin" => self.environment(),
+            // Size hints around delimiters don't change anything here
+            "big" | "Big" | "bigg" | "Bigg" | "displaystyle" | "textstyle" | "limits" => {
+                MathNode::Row(Vec::new())
+            }
+            _ => MathNode::Upright(format!("\\{name}")),
+        }
+    }
+
+    /// The delimiter after `\left` or `\right`.
+    fn delimiter(&mut self) -> String {
+        match self.next() {
+            Some(Token::Char('.')) | None => String::new(),
+            Some(Token::Char(c)) => c.to_string(),
+            Some(Token::Command(name)) => match symbol(&name) {
+                Some(MathNode::Upright(s)) | Some(MathNode::Operator(s)) => s,
+                _ => String::new(),
+            },
+            Some(_) => String::new(),
+        }
+    }
+
+    /// `\begin{name}` ... `\end{name}`: rows split by `\\`, cells by `&`.
+    fn environment(&mut self) -> MathNode {
+        let name = self.text_argument();
+        let (open, close, align) = match name.as_str() {
+            "pmatrix" => ("(", ")", ColumnAlign::Center),
+            "bmatrix" => ("[", "]", ColumnAlign::Center),
+            "Bmatrix" => ("{", "}", ColumnAlign::Center),
+            "vmatrix" => ("|", "|", ColumnAlign::Center),
+            "Vmatrix" => ("\u{2016}", "\u{2016}", ColumnAlign::Center),
+            "cases" => ("{", "", ColumnAlign::Left),
+            "aligned" | "align" | "align*" | "split" => ("", "", ColumnAlign::Alternate),
+            _ => ("", "", ColumnAlign::Center),
+        };
+
+        let end = Token::Command("end".to_string());
+        let stop = [Token::Align, Token::NewRow, end.clone()];
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        loop {
+            row.push(MathNode::Row(self.row(&stop)));
+            match self.next() {
+                Some(Token::Align) => {}
+                Some(Token::NewRow) => rows.push(std::mem::take(&mut row)),
+                _ => {
+                    self.text_argument();
+                    break;
+                }
+            }
+        }
+        // A trailing `\\` leaves an empty last row
+        if !(row.len() == 1 && row[0] == MathNode::Row(Vec::new()) && !rows.is_empty()) {
+            rows.push(row);
+        }
+
+        MathNode::Matrix {
+            rows,
+            open: open.to_string(),
+            close: close.to_string(),
+            align,
+        }
+    }
+}
+
+fn is_end(token: &Token) -> bool {
+    matches!(token, Token::Close | Token::Align | Token::NewRow)
+        || *token == Token::Command("end".to_string())
+        || *token == Token::Command("right".to_string())
+}
+
+/// A plain character: operators get space around them, the rest is upright.
+fn char_node(c: char) -> MathNode {
+    match c {
+        '+' | '=' | '<' | '>' => MathNode::Operator(c.to_string()),
+        '-' => MathNode::Operator("\u{2212}".to_string()),
+        '*' => MathNode::Operator("\u{2217}".to_string()),
+        _ => MathNode::Upright(c.to_string()),
+    }
+}
+
+/// Commands that stand for a single symbol.
+fn symbol(name: &str) -> Option<MathNode> {
+    // Variant letters the bundled fonts lack map to the plain form
+    const GREEK: [(&str, &str); 40] = [
+        ("alpha", "α"),
+        ("beta", "β"),
+        ("gamma", "γ"),
+        ("delta", "δ"),
+        ("epsilon", "ε"),
+        ("varepsilon", "ε"),
+        ("zeta", "ζ"),
+        ("eta", "η"),
+        ("theta", "θ"),
+        ("vartheta", "θ"),
+        ("iota", "ι"),
+        ("kappa", "κ"),
+        ("lambda", "λ"),
+        ("mu", "μ"),
+        ("nu", "ν"),
+        ("xi", "ξ"),
+        ("pi", "π"),
+        ("varpi", "π"),
+        ("rho", "ρ"),
+        ("varrho", "ρ"),
+        ("sigma", "σ"),
+        ("varsigma", "ς"),
+        ("tau", "τ"),
+        ("upsilon", "υ"),
+        ("phi", "φ"),
+        ("varphi", "φ"),
+        ("chi", "χ"),
+        ("psi", "ψ"),
+        ("omega", "ω"),
+        ("Gamma", "Γ"),
+        ("Delta", "Δ"),
+        ("Theta", "Θ"),
+        ("Lambda", "Λ"),
+        ("Xi", "Ξ"),
+        ("Pi", "Π"),
+        ("Sigma", "Σ"),
+        ("Upsilon", "Υ"),
+        ("Phi", "Φ"),
+        ("Psi", "Ψ"),
+        ("Omega", "Ω"),
+    ];
+    const OPERATORS: [(&str, &str); 34] = [
+        ("cdot", "⋅"),
+        ("times", "×"),
+        ("div", "÷"),
+        ("pm", "±"),
+        ("mp", "∓"),
+        ("le", "≤"),
+        ("leq", "≤"),
+        ("ge", "≥"),
+        ("geq", "≥"),
+        ("ne", "≠"),
+        ("neq", "≠"),
+        ("lt", "<"),
+        ("gt", ">"),
+        ("approx", "≈"),
+        ("equiv", "≡"),
+        ("sim", "∼"),
+        ("simeq", "≃"),
+        ("propto", "∝"),
+        ("to", "→"),
+        ("rightarrow", "→"),
+        ("leftarrow", "←"),
+        ("Rightarrow", "⇒"),
+        ("Leftarrow", "⇐"),
+        ("Leftrightarrow", "⇔"),
+        ("iff", "⇔"),
+        ("implies", "⇒"),
+        ("mapsto", "↦"),
+        ("in", "∈"),
+        ("notin", "∉"),
+        ("subset", "⊂"),
+        ("subseteq", "⊆"),
+        ("cup", "∪"),
+        ("cap", "∩"),
+        ("mid", "∣"),
+    ];
+    const SYMBOLS: [(&str, &str); 30] = [
+        ("infty", "∞"),
+        ("partial", "∂"),
+        ("nabla", "∇"),
+        ("forall", "∀"),
+        ("exists", "∃"),
+        ("emptyset", "∅"),
+        ("ldots", "…"),
+        ("dots", "…"),
+        ("cdots", "⋯"),
+        ("vdots", "⋮"),
+        ("ddots", "⋱"),
+        ("ell", "ℓ"),
+        ("hbar", "ħ"),
+        ("prime", "′"),
+        ("circ", "∘"),
+        ("angle", "∠"),
+        ("neg", "¬"),
+        ("land", "∧"),
+        ("lor", "∨"),
+        ("langle", "⟨"),
+        ("rangle", "⟩"),
+        ("lfloor", "⌊"),
+        ("rfloor", "⌋"),
+        ("lceil", "⌈"),
+        ("rceil", "⌉"),
+        ("{", "{"),
+        ("}", "}"),
+        ("|", "‖"),
+        ("%", "%"),
+        ("$", "$"),
+    ];
+    const BIG_OPS: [(&str, &str, bool); 10] = [
+        ("sum", "∑", true),
+        ("prod", "∏", true),
+        ("coprod", "∐", true),
+        ("bigcup", "⋃", true),
+        ("bigcap", "⋂", true),
+        ("int", "∫", false),
+        ("iint", "∬", false),
+        ("iiint", "∭", false),
+        ("oint", "∮", false),
+        ("lim", "lim", true),
+    ];
+    const FUNCTIONS: [&str; 24] = [
+        "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh",
+        "tanh", "log", "ln", "lg", "exp", "det", "dim", "ker", "deg", "arg", "gcd", "Pr", "mod",
+    ];
+    const LIMIT_FUNCTIONS: [&str; 5] = ["max", "min", "sup", "inf", "argmax"];
+    const SPACES: [(&str, f32); 7] = [
+        (",", 3.0 / 18.0),
+        (":", 4.0 / 18.0),
+        (">", 4.0 / 18.0),
+        (";", 5.0 / 18.0),
+        (" ", 0.25),
+        ("quad", 1.0),
+        ("qquad", 2.0),
+    ];
+
+    let find = |table: &[(&str, &str)]| {
+        table
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, s)| s.to_string())
+    };
+    if let Some(s) = find(&GREEK) {
+        // Capital Greek letters are upright, as in TeX
+        let upright = s.chars().all(char::is_uppercase);
+        return Some(if upright {
+            MathNode::Upright(s)
+        } else {
+            MathNode::Ident(s)
+        });
+    }
+    if let Some(s) = find(&OPERATORS) {
+        return Some(MathNode::Operator(s));
+    }
+    if let Some(s) = find(&SYMBOLS) {
+        return Some(MathNode::Upright(s));
+    }
+    if let Some(&(_, symbol, limits)) = BIG_OPS.iter().find(|(n, _, _)| *n == name) {
+        return Some(MathNode::BigOp {
+            symbol: symbol.to_string(),
+            limits,
+        });
+    }
+    if FUNCTIONS.contains(&name) {
+        return Some(MathNode::Upright(name.to_string()));
+    }
+    if LIMIT_FUNCTIONS.contains(&name) {
+        let symbol = if name == "argmax" { "arg max" } else { name };
+        return Some(MathNode::BigOp {
+            symbol: symbol.to_string(),
+            limits: true,
+        });
+    }
+    if name == "!" {
+        return Some(MathNode::Space(-3.0 / 18.0));
+    }
+    SPACES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, em)| MathNode::Space(em))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(tex: &str) -> Vec<MathNode> {
+        match parse(tex) {
+            MathNode::Row(nodes) => nodes,
+            other => panic!("expected a row, got {other:?}"),
+        }
+    }
+
+    fn ident(s: &str) -> MathNode {
+        MathNode::Ident(s.to_string())
+    }
+
+    #[test]
+    fn symbols_and_operators() {
+        assert_eq!(
+            row(r"\alpha + 12.5 \le \Omega"),
+            [
+                ident("α"),
+                MathNode::Operator("+".to_string()),
+                MathNode::Upright("12.5".to_string()),
+                MathNode::Operator("≤".to_string()),
+                MathNode::Upright("Ω".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn scripts_attach_to_the_previous_atom() {
+        assert_eq!(
+            row("x_i^{2}"),
+            [MathNode::Scripts {
+                base: Box::new(ident("x")),
+                sub: Some(Box::new(ident("i"))),
+                sup: Some(Box::new(MathNode::Row(vec![MathNode::Upright(
+                    "2".to_string()
+                )]))),
+            }]
+        );
+        assert!(matches!(
+            &row("f''")[0],
+            MathNode::Scripts { sup: Some(sup), .. } if **sup == MathNode::Upright("′′".to_string())
+        ));
+    }
+
+    #[test]
+    fn fractions_roots_and_big_operators() {
+        let nodes = row(r"\sum_{i=1}^{n} \frac{1}{\sqrt[3]{x}}");
+        let MathNode::Scripts { base, sub, sup } = &nodes[0] else {
+            panic!("expected scripts");
+        };
+        assert_eq!(
+            **base,
+            MathNode::BigOp {
+                symbol: "∑".to_string(),
+                limits: true
+            }
+        );
+        assert!(sub.is_some() && sup.is_some());
+
+        let MathNode::Frac { num, den, bar } = &nodes[1] else {
+            panic!("expected a fraction");
+        };
+        assert!(bar);
+        assert_eq!(
+            **num,
+            MathNode::Row(vec![MathNode::Upright("1".to_string())])
+        );
+        assert!(matches!(
+            &**den,
+            MathNode::Row(r) if matches!(&r[0], MathNode::Sqrt { index: Some(_), .. })
+        ));
+    }
+
+    #[test]
+    fn matrices_and_cases() {
+        let nodes = row(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix} = 1");
+        let MathNode::Matrix {
+            rows, open, close, ..
+        } = &nodes[0]
+        else {
+            panic!("expected a matrix");
+        };
+        assert_eq!((open.as_str(), close.as_str()), ("(", ")"));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1],
+            [
+                MathNode::Row(vec![ident("c")]),
+                MathNode::Row(vec![ident("d")])
+            ]
+        );
+        assert_eq!(nodes.len(), 3);
+
+        let nodes = row(r"\begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \\ \end{cases}");
+        let MathNode::Matrix { rows, align, .. } = &nodes[0] else {
+            panic!("expected cases");
+        };
+        assert_eq!(*align, ColumnAlign::Left);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1][1],
+            MathNode::Row(vec![MathNode::Text("otherwise".to_string())])
+        );
+    }
+
+    #[test]
+    fn delimiters_accents_and_unknown_commands() {
+        let nodes = row(r"\left( \hat{\beta} \right] \foo");
+        assert!(matches!(
+            &nodes[0],
+            MathNode::Delimited { open, close, body }
+                if open == "(" && close == "]"
+                && matches!(&**body, MathNode::Row(r) if matches!(r[0], MathNode::Accent { accent: Accent::Hat, .. }))
+        ));
+        assert_eq!(nodes[1], MathNode::Upright(r"\foo".to_string()));
+    }
+}
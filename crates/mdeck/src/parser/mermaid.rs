@@ -0,0 +1,602 @@
+use std::collections::HashMap;
+
+use super::diagram::{Arrow, Diagram, DiagramEdge, DiagramNode, Direction, NodeShape};
+
+/// A ```` ```mermaid ```` block in the subset MDeck draws itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mermaid {
+    /// `flowchart` or `graph`, drawn like an `@diagram`.
+    Flowchart {
+        diagram: Diagram,
+        direction: Direction,
+    },
+    /// `sequenceDiagram`
+    Sequence(SequenceDiagram),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SequenceDiagram {
+    /// In declaration order, then in order of first mention.
+    pub participants: Vec<Participant>,
+    pub events: Vec<SequenceEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub label: String,
+    /// Declared with `actor`: drawn as a person rather than a box.
+    pub actor: bool,
+}
+
+/// One row of a sequence diagram, top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SequenceEvent {
+    Message {
+        from: String,
+        to: String,
+        label: String,
+        arrow: Arrow,
+    },
+    /// `Note left of A`, `Note right of A`, `Note over A` or `Note over A,B`.
+    Note {
+        side: NoteSide,
+        first: String,
+        last: String,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteSide {
+    Left,
+    Right,
+    Over,
+}
+
+/// Parse a Mermaid block. `None` for diagram types MDeck doesn't draw, which
+/// are shown as code instead.
+pub fn parse(content: &str) -> Option<Mermaid> {
+    let mut lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("%%"))
+        .peekable();
+
+    // Skip a `---` config/title block
+    if lines.peek() == Some(&"---") {
+        lines.next();
+        lines.by_ref().find(|&l| l == "---");
+    }
+
+    let header = lines.next()?.trim_end_matches(';');
+    let mut words = header.split_whitespace();
+    match words.next()? {
+        "flowchart" | "graph" => {
+            let direction = match words.next() {
+                Some("LR" | "RL") => Direction::LeftRight,
+                _ => Direction::TopBottom,
+            };
+            Some(Mermaid::Flowchart {
+                diagram: parse_flowchart(lines),
+                direction,
+            })
+        }
+        "sequenceDiagram" => Some(Mermaid::Sequence(parse_sequence(lines))),
+        _ => None,
+    }
+}
+
+/// Flowchart statements that only style or group nodes.
+const FLOWCHART_SKIPPED: [&str; 8] = [
+    "subgraph",
+    "end",
+    "direction",
+    "classDef",
+    "class",
+    "style",
+    "linkStyle",
+    "click",
+];
+
+fn parse_flowchart<'a>(lines: impl Iterator<Item = &'a str>) -> Diagram {
+    let mut flowchart = Flowchart::default();
+    for statement in lines.flat_map(|l| l.split(';')).map(str::trim) {
+        let keyword = statement.split_whitespace().next().unwrap_or("");
+        if statement.is_empty() || FLOWCHART_SKIPPED.contains(&keyword) {
+            continue;
+        }
+        flowchart.statement(statement);
+    }
+    flowchart.diagram
+}
+
+#[derive(Default)]
+struct Flowchart {
+    diagram: Diagram,
+    seen_nodes: HashMap<String, usize>,
+}
+
+impl Flowchart {
+    /// `A[Label] --> B & C -->|label| D`: nodes joined by a chain of links.
+    /// Anything unparseable ends the statement.
+    fn statement(&mut self, s: &str) {
+        let Some((mut sources, mut rest)) = self.node_group(s) else {
+            return;
+        };
+        while let Some((arrow, label, after)) = parse_link(rest.trim_start()) {
+            let Some((targets, after)) = self.node_group(after.trim_start()) else {
+                return;
+            };
+            for &from in &sources {
+                for &to in &targets {
+                    self.diagram.edges.push(DiagramEdge {
+                        from: self.diagram.nodes[from].name.clone(),
+                        to: self.diagram.nodes[to].name.clone(),
+                        label: label.clone(),
+                        arrow,
+                        step: 0,
+                    });
+                }
+            }
+            sources = targets;
+            rest = after;
+        }
+    }
+
+    /// Nodes joined with `&`.
+    fn node_group<'s>(&mut self, s: &'s str) -> Option<(Vec<usize>, &'s str)> {
+        let (first, mut rest) = self.node(s)?;
+        let mut nodes = vec![first];
+        while let Some(after) = rest.trim_start().strip_prefix('&') {
+            let (node, after) = self.node(after.trim_start())?;
+            nodes.push(node);
+            rest = after;
+        }
+        Some((nodes, rest))
+    }
+
+    /// A node id with an optional shaped label, e.g. `db[(Orders DB)]`.
+    fn node<'s>(&mut self, s: &'s str) -> Option<(usize, &'s str)> {
+        let (id, rest) = split_id(s)?;
+        let idx = *self.seen_nodes.entry(id.to_string()).or_insert_with(|| {
+            let mut node = DiagramNode::new(id, 0);
+            node.shape = NodeShape::Rect;
+            self.diagram.nodes.push(node);
+            self.diagram.nodes.len() - 1
+        });
+
+        let mut rest = rest;
+        if let Some((label, shape, after)) = parse_shape(rest) {
+            let node = &mut self.diagram.nodes[idx];
+            node.label = label;
+            node.shape = shape;
+            rest = after;
+        }
+
+        // Drop a `:::className` suffix
+        let rest = match rest.strip_prefix(":::") {
+            Some(class) => {
+                class.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '-')
+            }
+            None => rest,
+        };
+        Some((idx, rest))
+    }
+}
+
+/// Split a node id off the front: letters, digits, `_`, and `-` between them.
+fn split_id(s: &str) -> Option<(&str, &str)> {
+    let bytes = s.as_bytes();
+    let mut end = 0;
+    while end < bytes.len() {
+        let c = bytes[end];
+        let inner_dash =
+            c == b'-' && end > 0 && bytes.get(end + 1).is_some_and(u8::is_ascii_alphanumeric);
+        if c.is_ascii_alphanumeric() || c == b'_' || inner_dash {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+    (end > 0).then(|| s.split_at(end))
+}
+
+/// Node brackets and the shapes they give, longest openers first.
+const SHAPES: [(&str, &str, NodeShape); 12] = [
+    ("([", "])", NodeShape::Stadium),
+    ("[[", "]]", NodeShape::Rect),
+    ("[(", ")]", NodeShape::Cylinder),
+    ("((", "))", NodeShape::Circle),
+    ("{{", "}}", NodeShape::Hexagon),
+    ("[/", "/]", NodeShape::Rect),
+    ("[\\", "\\]", NodeShape::Rect),
+    ("[/", "\\]", NodeShape::Rect),
+    ("(", ")", NodeShape::Rounded),
+    ("[", "]", NodeShape::Rect),
+    ("{", "}", NodeShape::Diamond),
+    (">", "]", NodeShape::Rect),
+];
+
+/// A bracketed label right after a node id, and the shape it gives.
+fn parse_shape(s: &str) -> Option<(String, NodeShape, &str)> {
+    SHAPES.iter().find_map(|&(open, close, shape)| {
+        let inner = s.strip_prefix(open)?;
+        let (text, rest) = match inner.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], quoted[end + 1..].strip_prefix(close)?)
+            }
+            None => {
+                let end = inner.find(close)?;
+                (&inner[..end], &inner[end + close.len()..])
+            }
+        };
+        Some((label_text(text), shape, rest))
+    })
+}
+
+/// Label text with `<br>` line breaks turned into newlines.
+fn label_text(s: &str) -> String {
+    s.trim()
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("<br>", "\n")
+}
+
+/// A link between nodes: `-->`, `---`, `-.->`, `==>`, `<-->` and longer
+/// forms, with a label as `-->|text|` or `-- text -->`.
+fn parse_link(s: &str) -> Option<(Arrow, String, &str)> {
+    let (both, body) = match s.strip_prefix('<') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let (line, mut rest) = split_line(body);
+    if line.len() < 2 || !line.starts_with(['-', '=']) {
+        return None;
+    }
+    let dashed = line.contains('.');
+
+    // `-- text -->`: the label runs up to the closing half of the link
+    let mut label = String::new();
+    if matches!(line, "--" | "==" | "-.") && rest.starts_with(' ') {
+        let closer = if line == "-." { ".-" } else { line };
+        let end = rest.find(closer)?;
+        label = label_text(&rest[..end]);
+        rest = split_line(&rest[end..]).1;
+    }
+
+    // `x` and `o` heads only count when they don't start the next node id
+    let head = rest.starts_with('>')
+        || (rest.starts_with(['x', 'o']) && !rest[1..].starts_with(|c: char| c.is_alphanumeric()));
+    if head {
+        rest = &rest[1..];
+    }
+    let arrow = match (head, both, dashed) {
+        (true, true, _) => Arrow::Both,
+        (true, false, true) => Arrow::DashedForward,
+        (true, false, false) => Arrow::Forward,
+        (false, _, true) => Arrow::Dashed,
+        (false, _, false) => Arrow::Line,
+    };
+
+    if let Some(inner) = rest.strip_prefix('|') {
+        let end = inner.find('|')?;
+        label = label_text(inner[..end].trim_matches('"'));
+        rest = &inner[end + 1..];
+    }
+    Some((arrow, label, rest))
+}
+
+/// Split a run of `-`, `=` and `.` off the front.
+fn split_line(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !matches!(c, '-' | '=' | '.'))
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Sequence statements that group or annotate messages without adding rows.
+const SEQUENCE_SKIPPED: [&str; 19] = [
+    "activate",
+    "deactivate",
+    "autonumber",
+    "title",
+    "loop",
+    "alt",
+    "else",
+    "opt",
+    "par",
+    "and",
+    "critical",
+    "option",
+    "break",
+    "rect",
+    "box",
+    "end",
+    "create",
+    "destroy",
+    "link",
+];
+
+/// Message arrows, longest first so `-->>` isn't read as `-->`.
+const MESSAGE_ARROWS: [(&str, Arrow); 10] = [
+    ("<<-->>", Arrow::Both),
+    ("<<->>", Arrow::Both),
+    ("-->>", Arrow::DashedForward),
+    ("--x", Arrow::DashedForward),
+    ("--)", Arrow::DashedForward),
+    ("->>", Arrow::Forward),
+    ("-x", Arrow::Forward),
+    ("-)", Arrow::Forward),
+    ("-->", Arrow::Dashed),
+    ("->", Arrow::Line),
+];
+
+fn parse_sequence<'a>(lines: impl Iterator<Item = &'a str>) -> SequenceDiagram {
+    let mut sequence = SequenceDiagram::default();
+    for line in lines {
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match keyword {
+            "participant" | "actor" => {
+                let (name, label) = rest.split_once(" as ").unwrap_or((rest, rest));
+                let idx = sequence.participant(name.trim());
+                let participant = &mut sequence.participants[idx];
+                participant.label = label.trim().to_string();
+                participant.actor = keyword == "actor";
+            }
+            "Note" | "note" => {
+                if let Some(note) = parse_note(rest) {
+                    if let SequenceEvent::Note { first, last, .. } = &note {
+                        sequence.participant(first);
+                        sequence.participant(last);
+                    }
+                    sequence.events.push(note);
+                }
+            }
+            _ if SEQUENCE_SKIPPED.contains(&keyword) => {}
+            _ => {
+                if let Some(message) = parse_message(line) {
+                    if let SequenceEvent::Message { from, to, .. } = &message {
+                        sequence.participant(from);
+                        sequence.participant(to);
+                    }
+                    sequence.events.push(message);
+                }
+            }
+        }
+    }
+    sequence
+}
+
+impl SequenceDiagram {
+    /// Index of a participant, adding it on first mention.
+    fn participant(&mut self, name: &str) -> usize {
+        if let Some(idx) = self.participants.iter().position(|p| p.name == name) {
+            return idx;
+        }
+        self.participants.push(Participant {
+            name: name.to_string(),
+            label: name.to_string(),
+            actor: false,
+        });
+        self.participants.len() - 1
+    }
+
+    /// Position of a participant from left to right.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.participants.iter().position(|p| p.name == name)
+    }
+}
+
+/// `A->>+B: label`. The `+`/`-` activation markers are dropped.
+fn parse_message(line: &str) -> Option<SequenceEvent> {
+    let (start, token, arrow) = MESSAGE_ARROWS
+        .iter()
+        .filter_map(|&(token, arrow)| line.find(token).map(|i| (i, token, arrow)))
+        .min_by_key(|&(i, token, _)| (i, std::cmp::Reverse(token.len())))?;
+    let from = line[..start].trim();
+    let (to, label) = line[start + token.len()..]
+        .split_once(':')
+        .unwrap_or((&line[start + token.len()..], ""));
+    let to = to.trim().trim_start_matches(['+', '-']).trim();
+    if from.is_empty() || to.is_empty() {
+        return None;
+    }
+    Some(SequenceEvent::Message {
+        from: from.to_string(),
+        to: to.to_string(),
+        label: label_text(label),
+        arrow,
+    })
+}
+
+/// `right of A: text`, `left of A: text`, `over A: text` or `over A,B: text`.
+fn parse_note(rest: &str) -> Option<SequenceEvent> {
+    let (target, text) = rest.split_once(':')?;
+    let (side, names) = if let Some(names) = target.strip_prefix("right of ") {
+        (NoteSide::Right, names)
+    } else if let Some(names) = target.strip_prefix("left of ") {
+        (NoteSide::Left, names)
+    } else {
+        (NoteSide::Over, target.strip_prefix("over ")?)
+    };
+    let (first, last) = names.split_once(',').unwrap_or((names, names));
+    Some(SequenceEvent::Note {
+        side,
+        first: first.trim().to_string(),
+        last: last.trim().to_string(),
+        text: label_text(text),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flowchart(content: &str) -> (Diagram, Direction) {
+        match parse(content) {
+            Some(Mermaid::Flowchart { diagram, direction }) => (diagram, direction),
+            other => panic!("expected a flowchart, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn flowchart_shapes_and_labels() {
+        let (diagram, direction) = flowchart(
+            "flowchart LR\n\
+             A[Start] --> B{Is it?}\n\
+             B -->|Yes| C([Done])\n\
+             B -- No --> D[(Orders DB)]\n\
+             %% a comment\n\
+             E((Hub)); F{{Prepare}} -.-> E\n\
+             G(\"Quoted (text)\") --- A\n",
+        );
+        assert_eq!(direction, Direction::LeftRight);
+
+        let shapes: Vec<(&str, &str, NodeShape)> = diagram
+            .nodes
+            .iter()
+            .map(|n| (n.name.as_str(), n.label.as_str(), n.shape))
+            .collect();
+        assert_eq!(
+            shapes,
+            [
+                ("A", "Start", NodeShape::Rect),
+                ("B", "Is it?", NodeShape::Diamond),
+                ("C", "Done", NodeShape::Stadium),
+                ("D", "Orders DB", NodeShape::Cylinder),
+                ("E", "Hub", NodeShape::Circle),
+                ("F", "Prepare", NodeShape::Hexagon),
+                ("G", "Quoted (text)", NodeShape::Rounded),
+            ]
+        );
+
+        let edges: Vec<(&str, &str, &str, Arrow)> = diagram
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.label.as_str(), e.arrow))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("A", "B", "", Arrow::Forward),
+                ("B", "C", "Yes", Arrow::Forward),
+                ("B", "D", "No", Arrow::Forward),
+                ("F", "E", "", Arrow::DashedForward),
+                ("G", "A", "", Arrow::Line),
+            ]
+        );
+    }
+
+    #[test]
+    fn flowchart_chains_and_groups() {
+        let (diagram, direction) = flowchart(
+            "graph TD\n\
+             subgraph Backend\n\
+             api-server --> a & b ==> db\n\
+             end\n\
+             classDef hot fill:#f00\n\
+             db:::hot <--> cache\n",
+        );
+        assert_eq!(direction, Direction::TopBottom);
+        let names: Vec<&str> = diagram.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["api-server", "a", "b", "db", "cache"]);
+        let edges: Vec<(&str, &str)> = diagram
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("api-server", "a"),
+                ("api-server", "b"),
+                ("a", "db"),
+                ("b", "db"),
+                ("db", "cache"),
+            ]
+        );
+        assert_eq!(diagram.edges[4].arrow, Arrow::Both);
+    }
+
+    #[test]
+    fn sequence_participants_messages_and_notes() {
+        let Some(Mermaid::Sequence(sequence)) = parse(
+            "sequenceDiagram\n\
+             actor U as User\n\
+             participant API\n\
+             U->>+API: GET /orders\n\
+             API-->>DB: query\n\
+             loop Every minute\n\
+             API->API: refresh\n\
+             end\n\
+             Note over API,DB: cached\n\
+             Note right of U: waits\n\
+             API-->>-U: 200 OK\n",
+        ) else {
+            panic!("expected a sequence diagram");
+        };
+
+        let participants: Vec<(&str, &str, bool)> = sequence
+            .participants
+            .iter()
+            .map(|p| (p.name.as_str(), p.label.as_str(), p.actor))
+            .collect();
+        assert_eq!(
+            participants,
+            [
+                ("U", "User", true),
+                ("API", "API", false),
+                ("DB", "DB", false)
+            ]
+        );
+
+        assert_eq!(sequence.events.len(), 6);
+        assert_eq!(
+            sequence.events[0],
+            SequenceEvent::Message {
+                from: "U".to_string(),
+                to: "API".to_string(),
+                label: "GET /orders".to_string(),
+                arrow: Arrow::Forward,
+            }
+        );
+        assert!(matches!(
+            &sequence.events[1],
+            SequenceEvent::Message {
+                arrow: Arrow::DashedForward,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &sequence.events[2],
+            SequenceEvent::Message { from, to, arrow: Arrow::Line, .. } if from == to
+        ));
+        assert_eq!(
+            sequence.events[3],
+            SequenceEvent::Note {
+                side: NoteSide::Over,
+                first: "API".to_string(),
+                last: "DB".to_string(),
+                text: "cached".to_string(),
+            }
+        );
+        assert!(matches!(
+            &sequence.events[4],
+            SequenceEvent::Note { side: NoteSide::Right, first, .. } if first == "U"
+        ));
+        assert!(matches!(
+            &sequence.events[5],
+            SequenceEvent::Message { to, .. } if to == "U"
+        ));
+    }
+
+    #[test]
+    fn unsupported_types_are_not_parsed() {
+        assert_eq!(parse("gantt\n  title Plan"), None);
+        assert_eq!(parse(""), None);
+        assert!(parse("---\ntitle: Flow\n---\nflowchart\nA --> B").is_some());
+    }
+}
@@ -1,16 +1,66 @@
+pub mod background;
 pub mod blocks;
+pub mod diagram;
 pub mod frontmatter;
+pub mod include;
 pub mod inline;
+pub mod math;
+pub mod mermaid;
 pub mod splitter;
 
 use std::path::Path;
 
+pub use background::Background;
+pub use include::CodeInclude;
+
 #[derive(Debug, Clone)]
 pub struct Presentation {
     pub meta: PresentationMeta,
     pub slides: Vec<Slide>,
 }
 
+impl Presentation {
+    /// The slide an internal link points to: a slide's `@id`, `#slide-5`
+    /// (counting from 1), or `#section-title` for the first slide with a
+    /// heading of that slug.
+    pub fn find_anchor(&self, anchor: &str) -> Option<usize> {
+        find_anchor(&self.slides, anchor)
+    }
+
+    /// The slide a `--slide` argument names: a number counting from 1 (past
+    /// the end means the last slide), or anything [`Presentation::find_anchor`]
+    /// accepts.
+    pub fn resolve_slide(&self, target: &str) -> Option<usize> {
+        match target.trim().parse::<usize>() {
+            Ok(number) => Some(
+                number
+                    .saturating_sub(1)
+                    .min(self.slides.len().saturating_sub(1)),
+            ),
+            Err(_) => self.find_anchor(target.trim()),
+        }
+    }
+}
+
+fn find_anchor(slides: &[Slide], anchor: &str) -> Option<usize> {
+    let anchor = anchor.strip_prefix('#').unwrap_or(anchor);
+    if let Some(index) = slides.iter().position(|s| s.id() == Some(anchor)) {
+        return Some(index);
+    }
+    if let Some(number) = anchor
+        .strip_prefix("slide-")
+        .and_then(|n| n.parse::<usize>().ok())
+    {
+        return (1..=slides.len()).contains(&number).then(|| number - 1);
+    }
+    slides.iter().position(|slide| {
+        slide.blocks.iter().any(|block| match block {
+            Block::Heading { inlines, .. } => slugify(&inlines_to_text(inlines)) == anchor,
+            _ => false,
+        })
+    })
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PresentationMeta {
     pub title: Option<String>,
@@ -25,10 +75,68 @@ pub struct PresentationMeta {
 
 #[derive(Debug, Clone)]
 pub struct Slide {
-    #[allow(dead_code)]
     pub directives: Vec<Directive>,
     pub blocks: Vec<Block>,
     pub layout: Layout,
+    /// Speaker notes, shown only in the presenter view.
+    pub notes: Option<String>,
+    /// Theme, transition, footer and code theme in effect for this slide.
+    pub settings: SlideSettings,
+}
+
+impl Slide {
+    /// Value of the slide-level `@name` directive, if present.
+    pub fn directive(&self, name: &str) -> Option<&str> {
+        self.directives
+            .iter()
+            .find(|d| d.name == name)
+            .map(|d| d.value.as_str())
+    }
+
+    /// The slide's `@id`, for links and `{{ref:id}}`.
+    pub fn id(&self) -> Option<&str> {
+        self.directive("id").filter(|v| !v.is_empty())
+    }
+
+    /// Plain text of the first heading on the slide.
+    pub fn title(&self) -> Option<String> {
+        self.blocks.iter().find_map(|b| match b {
+            Block::Heading { inlines, .. } => Some(inlines_to_text(inlines)),
+            _ => None,
+        })
+    }
+}
+
+/// Settings for a single slide: the slide's own `@theme`, `@transition`,
+/// `@footer` and `@code-theme` directives, falling back to the frontmatter.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SlideSettings {
+    pub theme: Option<String>,
+    pub transition: Option<String>,
+    pub footer: Option<String>,
+    pub code_theme: Option<String>,
+    /// `@background` has slide scope only.
+    pub background: Option<Background>,
+}
+
+impl SlideSettings {
+    /// Resolve a slide's settings. An empty `@footer:` hides the footer.
+    pub fn resolve(slide: &Slide, meta: &PresentationMeta) -> Self {
+        let get = |name: &str, global: &Option<String>| {
+            slide
+                .directive(name)
+                .map(str::to_string)
+                .or_else(|| global.clone())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            theme: get("theme", &meta.theme),
+            transition: get("transition", &meta.transition),
+            footer: get("footer", &meta.footer),
+            code_theme: get("code-theme", &meta.code_theme),
+            background: slide.directive("background").and_then(background::parse),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -59,7 +167,12 @@ pub enum Block {
     CodeBlock {
         language: Option<String>,
         code: String,
-        highlight_lines: Vec<usize>,
+        /// Lines to highlight, one set per reveal step; a single set when the
+        /// spec has no `|`.
+        highlight_steps: Vec<Vec<usize>>,
+        /// Where the code comes from when it's included from another file.
+        include: Option<CodeInclude>,
+        options: CodeOptions,
     },
     BlockQuote {
         inlines: Vec<Inline>,
@@ -67,14 +180,50 @@ pub enum Block {
     Table {
         headers: Vec<Vec<Inline>>,
         rows: Vec<Vec<Vec<Inline>>>,
+        /// Alignment of each column, from the `:---:` row.
+        alignments: Vec<Alignment>,
+        /// Reveal step of each row: 0 for rows shown from the start, then one
+        /// more for each row whose first cell starts with `+`. Other rows
+        /// appear with the row above.
+        row_steps: Vec<usize>,
     },
     HorizontalRule,
+    /// Display math from `$$ ... $$`, as TeX source.
+    Math {
+        tex: String,
+    },
     Diagram {
         content: String,
+        direction: diagram::Direction,
+        syntax: diagram::DiagramSyntax,
     },
     ColumnSeparator,
 }
 
+/// Horizontal alignment of a table column: `:---` or `---` left, `:---:`
+/// centered, `---:` right.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Flags on a code block's opening fence, after the language.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CodeOptions {
+    /// Number the lines starting from this one: `numbers` counts from 1,
+    /// `numbers=120` from 120.
+    pub line_numbers: Option<usize>,
+    /// Whether `numbers=N` gave the start. Without it, included code is
+    /// numbered by its lines in the file.
+    pub explicit_start: bool,
+    /// Lines starting with `+` or `-` are shown as added or removed, from a
+    /// ```` ```diff ```` block or a `diff` flag after another language.
+    pub diff: bool,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ImageDirectives {
     pub width: Option<String>,
@@ -92,11 +241,15 @@ pub enum Inline {
     Italic(Vec<Inline>),
     Strikethrough(Vec<Inline>),
     Code(String),
+    /// Inline math from `$...$`, as TeX source.
+    Math(String),
     Link {
         text: Vec<Inline>,
-        #[allow(dead_code)]
         url: String,
     },
+    /// `{{ref:id}}` naming a slide that doesn't exist. References that match a
+    /// slide become links when the deck is parsed.
+    Ref(String),
 }
 
 #[derive(Debug, Clone)]
@@ -128,26 +281,138 @@ pub enum Layout {
     Content,
 }
 
-pub fn parse(content: &str, _base_path: &Path) -> Presentation {
+/// Read and parse a presentation file. Fails if the file cannot be read or
+/// contains no slides.
+pub fn load(file: &Path) -> anyhow::Result<Presentation> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", file.display()))?;
+    let base_path = file.parent().unwrap_or(Path::new("."));
+    let presentation = parse(&content, base_path);
+
+    if presentation.slides.is_empty() {
+        anyhow::bail!("No slides found in {}", file.display());
+    }
+
+    Ok(presentation)
+}
+
+pub fn parse(content: &str, base_path: &Path) -> Presentation {
     let (meta, body) = frontmatter::extract(content);
     let raw_slides = splitter::split(&body);
-    let slides: Vec<Slide> = raw_slides
+    let mut slides: Vec<Slide> = raw_slides
         .into_iter()
         .filter(|raw| !raw.trim().is_empty())
         .map(|raw| {
             let (directives, content) = blocks::extract_directives(&raw);
-            let blocks = blocks::parse(&content);
+            let (content, notes) = blocks::extract_notes(&content);
+            let mut blocks = blocks::parse(&content);
+            include::resolve(&mut blocks, base_path);
             let layout = classify_layout(&directives, &blocks);
-            Slide {
+            let mut slide = Slide {
                 directives,
                 blocks,
                 layout,
-            }
+                notes,
+                settings: SlideSettings::default(),
+            };
+            slide.settings = SlideSettings::resolve(&slide, &meta);
+            slide
         })
         .collect();
+    resolve_refs(&mut slides);
     Presentation { meta, slides }
 }
 
+/// Replace each `{{ref:id}}` with a link to the slide it names, showing that
+/// slide's title and number, e.g. "Agenda (slide 3)".
+fn resolve_refs(slides: &mut [Slide]) {
+    let labels: Vec<String> = slides
+        .iter()
+        .enumerate()
+        .map(|(i, slide)| match slide.title() {
+            Some(title) if !title.trim().is_empty() => {
+                format!("{} (slide {})", title.trim(), i + 1)
+            }
+            _ => format!("slide {}", i + 1),
+        })
+        .collect();
+    let mut ids = Vec::new();
+    for slide in slides.iter_mut() {
+        for_each_inline_run_mut(&mut slide.blocks, &mut |inlines| {
+            collect_refs(inlines, &mut ids)
+        });
+    }
+    let targets: Vec<Option<usize>> = ids.iter().map(|id| find_anchor(slides, id)).collect();
+    let mut targets = targets.into_iter();
+    for slide in slides.iter_mut() {
+        for_each_inline_run_mut(&mut slide.blocks, &mut |inlines| {
+            replace_refs(inlines, &mut targets, &labels)
+        });
+    }
+}
+
+fn collect_refs(inlines: &[Inline], ids: &mut Vec<String>) {
+    for inline in inlines {
+        match inline {
+            Inline::Ref(id) => ids.push(id.clone()),
+            Inline::Bold(children)
+            | Inline::Italic(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link { text: children, .. } => collect_refs(children, ids),
+            _ => {}
+        }
+    }
+}
+
+fn replace_refs(
+    inlines: &mut [Inline],
+    targets: &mut impl Iterator<Item = Option<usize>>,
+    labels: &[String],
+) {
+    for inline in inlines {
+        match inline {
+            Inline::Ref(id) => {
+                if let Some(Some(index)) = targets.next() {
+                    *inline = Inline::Link {
+                        text: vec![Inline::Text(labels[index].clone())],
+                        url: format!("#{id}"),
+                    };
+                }
+            }
+            Inline::Bold(children)
+            | Inline::Italic(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link { text: children, .. } => replace_refs(children, targets, labels),
+            _ => {}
+        }
+    }
+}
+
+/// Call `f` on every run of inline text in `blocks`, in document order.
+fn for_each_inline_run_mut(blocks: &mut [Block], f: &mut impl FnMut(&mut [Inline])) {
+    fn list(items: &mut [ListItem], f: &mut impl FnMut(&mut [Inline])) {
+        for item in items {
+            f(&mut item.inlines);
+            list(&mut item.children, f);
+        }
+    }
+    for block in blocks {
+        match block {
+            Block::Heading { inlines, .. }
+            | Block::Paragraph { inlines }
+            | Block::BlockQuote { inlines } => f(inlines),
+            Block::List { items, .. } => list(items, f),
+            Block::Table { headers, rows, .. } => {
+                headers
+                    .iter_mut()
+                    .chain(rows.iter_mut().flatten())
+                    .for_each(|cell| f(cell));
+            }
+            _ => {}
+        }
+    }
+}
+
 fn classify_layout(directives: &[Directive], blocks: &[Block]) -> Layout {
     // Check for explicit @layout directive
     for d in directives {
@@ -182,6 +447,7 @@ fn classify_layout(directives: &[Directive], blocks: &[Block]) -> Layout {
     for block in blocks {
         match block {
             Block::Heading { level, .. } => headings.push(*level),
+            Block::Math { .. } => paragraphs += 1,
             Block::Paragraph { inlines } => {
                 paragraphs += 1;
                 let text_len: usize = inlines.iter().map(inline_text_len).sum();
@@ -287,12 +553,22 @@ fn classify_layout(directives: &[Directive], blocks: &[Block]) -> Layout {
 }
 
 /// Count the maximum number of reveal steps in a slide's blocks.
-/// Each `+` (NextStep) marker in any list counts as one step.
+/// Each `+` (NextStep) marker in any list or table counts as one step, as
+/// does each highlight stage after the first in a code block.
 pub fn compute_max_steps(blocks: &[Block]) -> usize {
     blocks
         .iter()
         .map(|b| match b {
             Block::List { items, .. } => count_next_steps(items),
+            Block::Diagram {
+                content,
+                syntax: diagram::DiagramSyntax::Native,
+                ..
+            } => diagram::parse(content).steps(),
+            Block::CodeBlock {
+                highlight_steps, ..
+            } => highlight_steps.len().saturating_sub(1),
+            Block::Table { row_steps, .. } => row_steps.last().copied().unwrap_or(0),
             _ => 0,
         })
         .max()
@@ -311,7 +587,6 @@ fn count_next_steps(items: &[ListItem]) -> usize {
 }
 
 /// Extract plain text from inline elements.
-#[allow(dead_code)]
 pub fn inlines_to_text(inlines: &[Inline]) -> String {
     let mut text = String::new();
     for inline in inlines {
@@ -320,21 +595,37 @@ pub fn inlines_to_text(inlines: &[Inline]) -> String {
             Inline::Bold(children) | Inline::Italic(children) | Inline::Strikethrough(children) => {
                 text.push_str(&inlines_to_text(children));
             }
-            Inline::Code(s) => text.push_str(s),
+            Inline::Code(s) | Inline::Math(s) => text.push_str(s),
             Inline::Link { text: t, .. } => text.push_str(&inlines_to_text(t)),
+            Inline::Ref(id) => text.push_str(&format!("{{{{ref:{id}}}}}")),
         }
     }
     text
 }
 
+/// Heading text as a link anchor: lowercase, with runs of spaces and
+/// punctuation turned into single hyphens, e.g. "Why Rust?" becomes `why-rust`.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
 fn inline_text_len(inline: &Inline) -> usize {
     match inline {
         Inline::Text(s) => s.len(),
         Inline::Bold(children) | Inline::Italic(children) | Inline::Strikethrough(children) => {
             children.iter().map(inline_text_len).sum()
         }
-        Inline::Code(s) => s.len(),
+        Inline::Code(s) | Inline::Math(s) => s.len(),
         Inline::Link { text, .. } => text.iter().map(inline_text_len).sum(),
+        Inline::Ref(id) => id.len() + 8,
     }
 }
 
@@ -445,10 +736,93 @@ mod tests {
         assert_eq!(pres.slides.len(), 2);
     }
 
+    #[test]
+    fn test_speaker_notes() {
+        let content = "# One\n\nBody\n\n???\nSay hello\n\n---\n\n# Two";
+        let pres = parse(content, Path::new("."));
+        assert_eq!(pres.slides.len(), 2);
+        assert_eq!(pres.slides[0].notes.as_deref(), Some("Say hello"));
+        assert_eq!(pres.slides[0].blocks.len(), 2);
+        assert!(pres.slides[1].notes.is_none());
+    }
+
+    #[test]
+    fn test_slide_settings_override_frontmatter() {
+        let content = "---\n@theme: light\n@transition: fade\n@footer: ACME\n---\n\n# One\n\n---\n\n@theme: dark\n@transition: none\n@footer:\n@code-theme: Solarized (dark)\n\n# Two";
+        let pres = parse(content, Path::new("."));
+        let first = &pres.slides[0].settings;
+        assert_eq!(first.theme.as_deref(), Some("light"));
+        assert_eq!(first.transition.as_deref(), Some("fade"));
+        assert_eq!(first.footer.as_deref(), Some("ACME"));
+        assert_eq!(first.code_theme, None);
+
+        let second = &pres.slides[1].settings;
+        assert_eq!(second.theme.as_deref(), Some("dark"));
+        assert_eq!(second.transition.as_deref(), Some("none"));
+        assert_eq!(second.footer, None);
+        assert_eq!(second.code_theme.as_deref(), Some("Solarized (dark)"));
+    }
+
+    #[test]
+    fn test_diagram_reveal_steps() {
+        let content = "```@diagram\n- A -> B\n+ B -> C\n* C -> A\n+ C -> D\n```\n\n- one\n+ two";
+        let pres = parse(content, Path::new("."));
+        assert_eq!(compute_max_steps(&pres.slides[0].blocks), 2);
+    }
+
     #[test]
     fn test_heading_inference() {
         let content = "# First\n\nSome content\n\n# Second\n\nMore content";
         let pres = parse(content, Path::new("."));
         assert_eq!(pres.slides.len(), 2);
     }
+
+    #[test]
+    fn test_find_anchor() {
+        let content = "# Intro\n\n---\n\n# Why Rust?\n\nBecause\n\n---\n\n# End";
+        let pres = parse(content, Path::new("."));
+        assert_eq!(pres.find_anchor("#slide-1"), Some(0));
+        assert_eq!(pres.find_anchor("#slide-3"), Some(2));
+        assert_eq!(pres.find_anchor("#slide-4"), None);
+        assert_eq!(pres.find_anchor("#why-rust"), Some(1));
+        assert_eq!(pres.find_anchor("#missing"), None);
+        assert_eq!(slugify("  Q&A: Part 2 "), "q-a-part-2");
+    }
+
+    #[test]
+    fn test_code_highlight_stages_count_as_steps() {
+        let blocks = blocks::parse("```rust {1-3|5|7-9}\nfn main() {}\n```\n\n- a\n+ b");
+        assert_eq!(compute_max_steps(&blocks), 2);
+        let blocks = blocks::parse("```rust {2,4}\nfn main() {}\n```");
+        assert_eq!(compute_max_steps(&blocks), 0);
+    }
+
+    #[test]
+    fn test_slide_ids_and_refs() {
+        let content = "# Intro\n\nSee {{ref:agenda}} or {{ref:nope}}\n\n---\n\n\
+                       @id: agenda\n\n## Today\n\n- **{{ref:slide-1}}**";
+        let pres = parse(content, Path::new("."));
+        assert_eq!(pres.slides[1].id(), Some("agenda"));
+        assert_eq!(pres.find_anchor("agenda"), Some(1));
+        assert_eq!(pres.resolve_slide("agenda"), Some(1));
+        assert_eq!(pres.resolve_slide("2"), Some(1));
+        assert_eq!(pres.resolve_slide("9"), Some(1));
+        assert_eq!(pres.resolve_slide("today"), Some(1));
+        assert_eq!(pres.resolve_slide("missing"), None);
+
+        let Block::Paragraph { inlines } = &pres.slides[0].blocks[1] else {
+            panic!("expected a paragraph");
+        };
+        assert_eq!(
+            inlines_to_text(inlines),
+            "See Today (slide 2) or {{ref:nope}}"
+        );
+        assert!(matches!(&inlines[1], Inline::Link { url, .. } if url == "#agenda"));
+        assert!(matches!(&inlines[3], Inline::Ref(id) if id == "nope"));
+
+        let Block::List { items, .. } = &pres.slides[1].blocks[1] else {
+            panic!("expected a list");
+        };
+        assert_eq!(inlines_to_text(&items[0].inlines), "Intro (slide 1)");
+    }
 }
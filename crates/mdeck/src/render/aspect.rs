@@ -0,0 +1,146 @@
+use eframe::egui;
+
+use crate::config::Config;
+use crate::parser::PresentationMeta;
+
+/// Width of the reference canvas slides are laid out on. Its height follows
+/// the aspect ratio: 1080 for 16:9, 1440 for 4:3, 1200 for 16:10.
+pub const REFERENCE_WIDTH: f32 = 1920.0;
+
+/// Slide aspect ratio, e.g. 16:9.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aspect {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for Aspect {
+    fn default() -> Self {
+        Self {
+            width: 16,
+            height: 9,
+        }
+    }
+}
+
+impl Aspect {
+    /// Parse an aspect ratio such as `16:9`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (w, h) = value.trim().split_once(':')?;
+        let width: u32 = w.trim().parse().ok()?;
+        let height: u32 = h.trim().parse().ok()?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        Some(Self { width, height })
+    }
+
+    /// Resolve the aspect ratio for a presentation: `@aspect` in the
+    /// frontmatter, then `defaults.aspect` from the config, then 16:9.
+    pub fn resolve(meta: &PresentationMeta, config: &Config) -> Self {
+        let value = meta
+            .aspect
+            .as_deref()
+            .or_else(|| config.defaults.as_ref().and_then(|d| d.aspect.as_deref()));
+        match value {
+            Some(v) => Self::parse(v).unwrap_or_else(|| {
+                eprintln!("Warning: invalid aspect ratio '{v}', using 16:9");
+                Self::default()
+            }),
+            None => Self::default(),
+        }
+    }
+
+    /// Width divided by height.
+    pub fn ratio(self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Size of the reference canvas that slide measurements are based on.
+    pub fn reference_size(self) -> egui::Vec2 {
+        egui::vec2(REFERENCE_WIDTH, REFERENCE_WIDTH / self.ratio())
+    }
+
+    /// Scale factor from the reference canvas to `rect`.
+    pub fn scale(self, rect: egui::Rect) -> f32 {
+        let reference = self.reference_size();
+        (rect.width() / reference.x).min(rect.height() / reference.y)
+    }
+
+    /// The largest rect with this aspect ratio centered in `rect`.
+    pub fn fit(self, rect: egui::Rect) -> egui::Rect {
+        let ratio = self.ratio();
+        let size = if rect.width() / rect.height() > ratio {
+            egui::vec2(rect.height() * ratio, rect.height())
+        } else {
+            egui::vec2(rect.width(), rect.width() / ratio)
+        };
+        egui::Rect::from_center_size(rect.center(), size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DefaultsConfig;
+
+    #[test]
+    fn parses_ratios() {
+        assert_eq!(
+            Aspect::parse("16:9"),
+            Some(Aspect {
+                width: 16,
+                height: 9
+            })
+        );
+        assert_eq!(
+            Aspect::parse(" 4 : 3 "),
+            Some(Aspect {
+                width: 4,
+                height: 3
+            })
+        );
+        assert_eq!(Aspect::parse("16x9"), None);
+        assert_eq!(Aspect::parse("0:9"), None);
+    }
+
+    #[test]
+    fn reference_height_follows_ratio() {
+        assert_eq!(
+            Aspect::default().reference_size(),
+            egui::vec2(1920.0, 1080.0)
+        );
+        assert_eq!(
+            Aspect::parse("4:3").unwrap().reference_size(),
+            egui::vec2(1920.0, 1440.0)
+        );
+    }
+
+    #[test]
+    fn fit_letterboxes_and_pillarboxes() {
+        let four_three = Aspect::parse("4:3").unwrap();
+        let wide = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1600.0, 900.0));
+        let fitted = four_three.fit(wide);
+        assert_eq!(fitted.size(), egui::vec2(1200.0, 900.0));
+        assert_eq!(fitted.center(), wide.center());
+
+        let tall = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 800.0));
+        assert_eq!(Aspect::default().fit(tall).size(), egui::vec2(800.0, 450.0));
+    }
+
+    #[test]
+    fn frontmatter_overrides_config_default() {
+        let config = Config {
+            defaults: Some(DefaultsConfig {
+                aspect: Some("4:3".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut meta = PresentationMeta::default();
+        assert_eq!(Aspect::resolve(&meta, &config).ratio(), 4.0 / 3.0);
+
+        meta.aspect = Some("16:10".to_string());
+        assert_eq!(Aspect::resolve(&meta, &config).ratio(), 1.6);
+    }
+}
@@ -0,0 +1,181 @@
+use eframe::egui::{self, Color32, Mesh, Pos2, Rect};
+
+use crate::parser::Slide;
+use crate::parser::background::{Background, BackgroundFill, BackgroundFit};
+use crate::theme::Theme;
+
+use super::image_cache::ImageCache;
+
+/// Grid resolution of the gradient mesh. Colors are interpolated linearly
+/// between vertices, so stops need a few cells to look smooth.
+const GRADIENT_CELLS: usize = 16;
+
+/// Paint the slide's `@background`, or the theme background if it has none.
+pub fn draw_slide(
+    ui: &egui::Ui,
+    slide: &Slide,
+    theme: &Theme,
+    rect: Rect,
+    opacity: f32,
+    image_cache: &ImageCache,
+) {
+    match slide.settings.background {
+        Some(ref background) => draw(ui, background, theme, rect, opacity, image_cache),
+        None => {
+            let bg = Theme::with_opacity(theme.background, opacity);
+            ui.painter().rect_filled(rect, 0.0, bg);
+        }
+    }
+}
+
+/// Paint a slide's `@background` over `rect`. Images that fail to load fall
+/// back to the theme background.
+pub fn draw(
+    ui: &egui::Ui,
+    background: &Background,
+    theme: &Theme,
+    rect: Rect,
+    opacity: f32,
+    image_cache: &ImageCache,
+) {
+    let painter = ui.painter();
+    match &background.fill {
+        BackgroundFill::Color(rgba) => {
+            painter.rect_filled(rect, 0.0, with_opacity(to_color(*rgba), opacity));
+        }
+        BackgroundFill::Gradient { angle, stops } => {
+            painter.add(gradient_mesh(rect, *angle, stops, opacity));
+        }
+        BackgroundFill::Image { path, fit } => {
+            painter.rect_filled(rect, 0.0, Theme::with_opacity(theme.background, opacity));
+            if let Some(texture) = image_cache.get_or_load(ui, path) {
+                let tint = Color32::from_white_alpha((opacity * 255.0) as u8);
+                let (image_rect, uv) = image_placement(texture.size_vec2(), rect, *fit);
+                let clipped = painter.with_clip_rect(rect.intersect(painter.clip_rect()));
+                clipped.image(texture.id(), image_rect, uv, tint);
+            }
+        }
+    }
+
+    if background.dim > 0.0 {
+        let overlay = Theme::with_opacity(theme.background, background.dim * opacity);
+        painter.rect_filled(rect, 0.0, overlay);
+    }
+}
+
+/// Where to draw an image of `size` inside `rect`, and which part of it (uv).
+/// Cover crops the image through its uv rect; contain shrinks the draw rect.
+fn image_placement(size: egui::Vec2, rect: Rect, fit: BackgroundFit) -> (Rect, Rect) {
+    let full_uv = Rect::from_min_max(Pos2::ZERO, egui::pos2(1.0, 1.0));
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return (rect, full_uv);
+    }
+    let image_aspect = size.x / size.y;
+    let rect_aspect = rect.width() / rect.height();
+
+    match fit {
+        BackgroundFit::Cover => {
+            let uv = if image_aspect > rect_aspect {
+                // Image is wider: crop left and right
+                let visible = rect_aspect / image_aspect;
+                let inset = (1.0 - visible) / 2.0;
+                Rect::from_min_max(egui::pos2(inset, 0.0), egui::pos2(1.0 - inset, 1.0))
+            } else {
+                let visible = image_aspect / rect_aspect;
+                let inset = (1.0 - visible) / 2.0;
+                Rect::from_min_max(egui::pos2(0.0, inset), egui::pos2(1.0, 1.0 - inset))
+            };
+            (rect, uv)
+        }
+        BackgroundFit::Contain => {
+            let draw_size = if image_aspect > rect_aspect {
+                egui::vec2(rect.width(), rect.width() / image_aspect)
+            } else {
+                egui::vec2(rect.height() * image_aspect, rect.height())
+            };
+            (Rect::from_center_size(rect.center(), draw_size), full_uv)
+        }
+    }
+}
+
+/// Build a mesh covering `rect` with a CSS-style linear gradient.
+fn gradient_mesh(rect: Rect, angle: f32, stops: &[[u8; 4]], opacity: f32) -> Mesh {
+    // Gradient line through the center; its length makes the corners land on 0 and 1
+    let radians = angle.to_radians();
+    let dir = egui::vec2(radians.sin(), -radians.cos());
+    let half_len = (rect.width() / 2.0 * dir.x).abs() + (rect.height() / 2.0 * dir.y).abs();
+
+    let mut mesh = Mesh::default();
+    let n = GRADIENT_CELLS;
+    for row in 0..=n {
+        for col in 0..=n {
+            let pos = egui::pos2(
+                rect.left() + rect.width() * col as f32 / n as f32,
+                rect.top() + rect.height() * row as f32 / n as f32,
+            );
+            let t = 0.5 + (pos - rect.center()).dot(dir) / (2.0 * half_len).max(f32::EPSILON);
+            let color = with_opacity(sample_stops(stops, t), opacity);
+            mesh.colored_vertex(pos, color);
+        }
+    }
+    let stride = (n + 1) as u32;
+    for row in 0..n as u32 {
+        for col in 0..n as u32 {
+            let i = row * stride + col;
+            mesh.add_triangle(i, i + 1, i + stride);
+            mesh.add_triangle(i + 1, i + stride + 1, i + stride);
+        }
+    }
+    mesh
+}
+
+/// Color at `t` (0..1) along evenly spaced gradient stops.
+fn sample_stops(stops: &[[u8; 4]], t: f32) -> Color32 {
+    let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = (t.floor() as usize).min(stops.len() - 2);
+    let frac = t - i as f32;
+    let (a, b) = (stops[i], stops[i + 1]);
+    let mix = |c: usize| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * frac).round() as u8;
+    Color32::from_rgba_unmultiplied(mix(0), mix(1), mix(2), mix(3))
+}
+
+fn to_color([r, g, b, a]: [u8; 4]) -> Color32 {
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+fn with_opacity(color: Color32, opacity: f32) -> Color32 {
+    if opacity >= 1.0 {
+        return color;
+    }
+    color.gamma_multiply(opacity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_stops_interpolate() {
+        let stops = [[0, 0, 0, 255], [200, 100, 0, 255], [0, 0, 0, 255]];
+        assert_eq!(sample_stops(&stops, 0.0), Color32::from_rgb(0, 0, 0));
+        assert_eq!(sample_stops(&stops, 0.5), Color32::from_rgb(200, 100, 0));
+        assert_eq!(sample_stops(&stops, 0.25), Color32::from_rgb(100, 50, 0));
+        assert_eq!(sample_stops(&stops, 1.5), Color32::from_rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn cover_crops_and_contain_fits() {
+        let rect = Rect::from_min_size(Pos2::ZERO, egui::vec2(1600.0, 900.0));
+        let square = egui::vec2(100.0, 100.0);
+
+        let (draw, uv) = image_placement(square, rect, BackgroundFit::Cover);
+        assert_eq!(draw, rect);
+        assert_eq!(uv.width(), 1.0);
+        assert!((uv.height() - 0.5625).abs() < 1e-5);
+
+        let (draw, uv) = image_placement(square, rect, BackgroundFit::Contain);
+        assert_eq!(draw.size(), egui::vec2(900.0, 900.0));
+        assert_eq!(draw.center(), rect.center());
+        assert_eq!(uv.size(), egui::vec2(1.0, 1.0));
+    }
+}
@@ -0,0 +1,762 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use eframe::egui::{self, Color32, FontId, Galley, Pos2, Rect, Stroke};
+
+use crate::parser::diagram::{
+    self, Diagram, DiagramNode, DiagramSyntax, Direction, NodeShape, NodeStyle,
+};
+use crate::parser::mermaid::{self, Mermaid, SequenceDiagram};
+use crate::theme::Theme;
+
+use super::graph_layout::{self, GraphLayout};
+use super::{icons, sequence};
+
+/// Space above the grid and below it, leaving room for curved edges and labels.
+const PADDING_TOP: f32 = 50.0;
+const PADDING_BOTTOM: f32 = 90.0;
+const ROW_GAP: f32 = 90.0;
+/// Space between an edge label and the pill around it.
+const LABEL_PADDING: f32 = 6.0;
+
+const PILL_HEIGHT: f32 = 44.0;
+const CARD_HEIGHT: f32 = 104.0;
+/// Height of components with a diamond, circle, hexagon or cylinder outline.
+const SHAPE_HEIGHT: f32 = 64.0;
+const ICON_SIZE: f32 = 44.0;
+
+/// What a diagram block draws: components and relationships, from either
+/// syntax, laid out along a direction, or a Mermaid sequence diagram.
+enum Source {
+    Graph(Diagram, Direction, GraphLayout),
+    Sequence(SequenceDiagram),
+}
+
+impl Source {
+    fn parse(content: &str, direction: Direction, syntax: DiagramSyntax) -> Self {
+        let (diagram, direction) = match syntax {
+            DiagramSyntax::Native => (diagram::parse(content), direction),
+            DiagramSyntax::Mermaid => match mermaid::parse(content) {
+                Some(Mermaid::Flowchart { diagram, direction }) => (diagram, direction),
+                Some(Mermaid::Sequence(sequence)) => return Source::Sequence(sequence),
+                None => (Diagram::default(), direction),
+            },
+        };
+        let layout = layout(&diagram, direction);
+        Source::Graph(diagram, direction, layout)
+    }
+}
+
+/// The diagram in `content`, parsed and laid out once and then kept in the
+/// context's temp data, keyed by the block's content, direction and syntax.
+fn source(
+    ctx: &egui::Context,
+    content: &str,
+    direction: Direction,
+    syntax: DiagramSyntax,
+) -> Arc<Source> {
+    let id = egui::Id::new(("mdeck-diagram", content, direction, syntax));
+    if let Some(source) = ctx.data(|d| d.get_temp::<Arc<Source>>(id)) {
+        return source;
+    }
+    let source = Arc::new(Source::parse(content, direction, syntax));
+    ctx.data_mut(|d| d.insert_temp(id, source.clone()));
+    source
+}
+
+/// Height of a diagram block, matching what [`draw`] uses.
+pub fn measure_height(
+    ui: &egui::Ui,
+    content: &str,
+    direction: Direction,
+    syntax: DiagramSyntax,
+    scale: f32,
+) -> f32 {
+    let source = source(ui.ctx(), content, direction, syntax);
+    let (diagram, layout) = match &*source {
+        Source::Graph(diagram, _, layout) => (diagram, layout),
+        Source::Sequence(sequence) => return sequence::measure_height(sequence, scale),
+    };
+    if diagram.nodes.is_empty() {
+        return 200.0 * scale;
+    }
+    let rows = grid_size(layout).1;
+    let node_height = node_height(diagram, scale);
+    grid_height(rows, node_height, scale) + PADDING_TOP * scale + PADDING_BOTTOM * scale
+}
+
+/// Draw a diagram parsed from `- Node (icon: ..., pos: x,y)` and `- A -> B: label` lines,
+/// or from a Mermaid flowchart or sequence diagram.
+/// Without any `pos`, components are laid out in layers along `direction`.
+/// Dashed relationships (`--`, `-->`) are drawn dashed, with heads at the ends
+/// their arrow points to. Components and relationships whose reveal step is
+/// past `reveal_step` are left out, but keep their place in the layout.
+#[allow(clippy::too_many_arguments)]
+pub fn draw(
+    ui: &egui::Ui,
+    content: &str,
+    direction: Direction,
+    syntax: DiagramSyntax,
+    theme: &Theme,
+    pos: Pos2,
+    max_width: f32,
+    opacity: f32,
+    reveal_step: usize,
+    scale: f32,
+) -> f32 {
+    let source = source(ui.ctx(), content, direction, syntax);
+    let (diagram, direction, layout) = match &*source {
+        Source::Graph(diagram, direction, layout) => (diagram, *direction, layout),
+        Source::Sequence(sequence) => {
+            return sequence::draw(ui, sequence, theme, pos, max_width, opacity, scale);
+        }
+    };
+
+    if diagram.nodes.is_empty() {
+        // Fallback for unparseable diagrams
+        let color = Theme::with_opacity(theme.foreground, opacity * 0.6);
+        let bg = Theme::with_opacity(theme.code_background, opacity);
+        let height = 200.0 * scale;
+        let rect = egui::Rect::from_min_size(pos, egui::vec2(max_width, height));
+        ui.painter().rect_filled(rect, 8.0 * scale, bg);
+        let galley = ui.painter().layout(
+            "[Diagram]".to_string(),
+            FontId::proportional(theme.body_size * 0.8 * scale),
+            color,
+            max_width,
+        );
+        let text_pos = Pos2::new(
+            pos.x + (max_width - galley.rect.width()) / 2.0,
+            pos.y + (height - galley.rect.height()) / 2.0,
+        );
+        ui.painter().galley(text_pos, galley, color);
+        return height;
+    }
+
+    let (cols, rows) = grid_size(layout);
+
+    // Cells share the width, but stay close enough that small diagrams don't scatter
+    let h_gap = 80.0 * scale;
+    let cell_width = (max_width / cols as f32).min(180.0 * scale + h_gap * 2.0);
+    let node_width = (cell_width - h_gap).clamp(80.0 * scale, 180.0 * scale);
+    let node_width = node_width.min(cell_width * 0.9);
+    let node_height = node_height(diagram, scale);
+    let row_height = node_height + ROW_GAP * scale;
+
+    let grid_left = pos.x + (max_width - cell_width * cols as f32) / 2.0;
+    let grid_top = pos.y + PADDING_TOP * scale;
+    let cell_center = |cell: Pos2| {
+        egui::pos2(
+            grid_left + (cell.x + 0.5) * cell_width,
+            grid_top + cell.y * row_height + node_height / 2.0,
+        )
+    };
+
+    let node_rects: HashMap<&str, Rect> = diagram
+        .nodes
+        .iter()
+        .zip(&layout.cells)
+        .map(|(node, &cell)| {
+            let rect =
+                Rect::from_center_size(cell_center(cell), egui::vec2(node_width, node_height));
+            (node.name.as_str(), rect)
+        })
+        .collect();
+
+    let with_icon = diagram.nodes.iter().any(|n| n.icon.is_some());
+    for node in diagram.nodes.iter().filter(|n| n.step <= reveal_step) {
+        draw_node(
+            ui,
+            node,
+            node_rects[node.name.as_str()],
+            theme,
+            opacity,
+            with_icon,
+            scale,
+        );
+    }
+
+    // Draw edges with quadratic bezier curves, or rounded paths through
+    // their route when they skip layers
+    let accent = theme.accent;
+    let edge_color = Theme::with_opacity(accent, opacity * 0.7);
+    let label_bg = Theme::with_opacity(theme.code_background, opacity * 0.9);
+    let label_color_edge = Theme::with_opacity(theme.foreground, opacity * 0.8);
+    let line_width = 2.5 * scale;
+    let arrow_size = 10.0 * scale;
+    let node_index: HashMap<&str, usize> = diagram
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.name.as_str(), i))
+        .collect();
+
+    let mut pair_edges: HashMap<(usize, usize), usize> = HashMap::new();
+
+    for (edge, route) in diagram.edges.iter().zip(&layout.routes) {
+        let (Some(&from_rect), Some(&to_rect)) = (
+            node_rects.get(edge.from.as_str()),
+            node_rects.get(edge.to.as_str()),
+        ) else {
+            continue;
+        };
+        let stroke = Stroke::new(line_width, edge_color);
+        let (from_idx, to_idx) = (node_index[edge.from.as_str()], node_index[edge.to.as_str()]);
+
+        // A node's edges to itself loop out of one side, each one wider
+        if from_idx == to_idx {
+            let loops = pair_edges.entry((from_idx, to_idx)).or_insert(0);
+            let points = self_loop(from_rect, direction, *loops, scale);
+            *loops += 1;
+            if diagram.edge_step(edge) > reveal_step {
+                continue;
+            }
+            draw_edge_path(ui, &points, edge.arrow.is_dashed(), stroke, scale);
+            let (start, end) = (points[0], points[points.len() - 1]);
+            if edge.arrow.head_at_end() {
+                draw_arrow_head(ui, end, points[points.len() - 2], arrow_size, edge_color);
+            }
+            if edge.arrow.head_at_start() {
+                draw_arrow_head(ui, start, points[1], arrow_size, edge_color);
+            }
+            if !edge.label.is_empty() {
+                let (galley, size) = label_galley(ui, &edge.label, theme, label_color_edge, scale);
+                let bounds = Rect::from_points(&points);
+                // Level with the loop's top, so labels of nested loops stack
+                let center = egui::pos2(
+                    bounds.right() + 4.0 * scale + size.x / 2.0,
+                    bounds.top() + size.y / 2.0,
+                );
+                draw_label(ui, galley, center, size, label_bg, label_color_edge, scale);
+            }
+            continue;
+        }
+
+        let (from_center, to_center) = (from_rect.center(), to_rect.center());
+        let diff = to_center - from_center;
+        if diff.length() < 1.0 {
+            continue;
+        }
+
+        // Bend repeated edges between the same pair to alternate sides, measured
+        // along the same direction for A -> B and B -> A so they don't overlap.
+        let pair = (from_idx.min(to_idx), from_idx.max(to_idx));
+        let pair_count = pair_edges.entry(pair).or_insert(0);
+        let canonical = if from_idx <= to_idx { diff } else { -diff };
+        let perp = egui::vec2(-canonical.y, canonical.x).normalized();
+        // Undirected and bidirectional edges run straight unless they share
+        // the pair with an earlier edge; one-way edges always bend.
+        let curve_offset = match *pair_count {
+            _ if !route.is_empty() => 0.0,
+            0 if !edge.arrow.is_directed() => 0.0,
+            n if n % 2 == 0 => -35.0 * scale,
+            _ => 35.0 * scale,
+        };
+        *pair_count += 1;
+        // Hidden edges still claim their bend so revealed ones stay put
+        if diagram.edge_step(edge) > reveal_step {
+            continue;
+        }
+        let mid = from_center + diff / 2.0;
+        let control = mid + perp * curve_offset;
+
+        // Corners the path bends around; a plain edge has the one control point
+        let corners: Vec<Pos2> = if route.is_empty() {
+            vec![control]
+        } else {
+            route.iter().map(|&cell| cell_center(cell)).collect()
+        };
+        let start = boundary_point(from_rect, corners[0], 2.0 * scale);
+        let end = boundary_point(to_rect, corners[corners.len() - 1], 2.0 * scale);
+        let points = rounded_path(start, &corners, end);
+
+        draw_edge_path(ui, &points, edge.arrow.is_dashed(), stroke, scale);
+
+        if edge.arrow.head_at_end() {
+            draw_arrow_head(ui, end, corners[corners.len() - 1], arrow_size, edge_color);
+        }
+        if edge.arrow.head_at_start() {
+            draw_arrow_head(ui, start, corners[0], arrow_size, edge_color);
+        }
+
+        // Edge label with pill background
+        if !edge.label.is_empty() {
+            let (galley, size) = label_galley(ui, &edge.label, theme, label_color_edge, scale);
+            // Straight and routed edges carry the label on the line. Bent ones put
+            // it beside the bulge, so labels on vertical edges don't cover the curve.
+            let center = if !route.is_empty() {
+                points[points.len() / 2]
+            } else if curve_offset == 0.0 {
+                mid
+            } else {
+                let outward = (control - mid).normalized();
+                let extent = outward.x.abs() * size.x / 2.0 + outward.y.abs() * size.y / 2.0;
+                mid + outward * (curve_offset.abs() * 0.5 + extent)
+            };
+            draw_label(ui, galley, center, size, label_bg, label_color_edge, scale);
+        }
+    }
+
+    grid_height(rows, node_height, scale) + PADDING_TOP * scale + PADDING_BOTTOM * scale
+}
+
+fn draw_edge_path(ui: &egui::Ui, points: &[Pos2], dashed: bool, stroke: Stroke, scale: f32) {
+    if dashed {
+        ui.painter().extend(egui::Shape::dashed_line(
+            points,
+            stroke,
+            8.0 * scale,
+            6.0 * scale,
+        ));
+    } else {
+        ui.painter().add(egui::Shape::line(points.to_vec(), stroke));
+    }
+}
+
+/// An edge label laid out, with the size of the pill around it.
+fn label_galley(
+    ui: &egui::Ui,
+    label: &str,
+    theme: &Theme,
+    color: Color32,
+    scale: f32,
+) -> (Arc<Galley>, egui::Vec2) {
+    let galley = ui.painter().layout_no_wrap(
+        label.to_string(),
+        FontId::proportional(theme.body_size * 0.5 * scale),
+        color,
+    );
+    let size = galley.rect.size() + egui::Vec2::splat(LABEL_PADDING * 2.0 * scale);
+    (galley, size)
+}
+
+fn draw_label(
+    ui: &egui::Ui,
+    galley: Arc<Galley>,
+    center: Pos2,
+    size: egui::Vec2,
+    background: Color32,
+    color: Color32,
+    scale: f32,
+) {
+    let rect = Rect::from_center_size(center, size);
+    ui.painter().rect_filled(rect, size.y / 2.0, background);
+    ui.painter().galley(
+        rect.min + egui::Vec2::splat(LABEL_PADDING * scale),
+        galley,
+        color,
+    );
+}
+
+/// Points of an edge from a node to itself: a loop out of the middle of the
+/// side that edges don't run through, the top when layers run left to right
+/// and the right side when they run top to bottom. Later loops on the same
+/// node (`index` 1, 2, ...) reach further out so they don't overlap.
+fn self_loop(rect: Rect, direction: Direction, index: usize, scale: f32) -> Vec<Pos2> {
+    let (side, out) = match direction {
+        Direction::LeftRight => (rect.center_top(), egui::vec2(0.0, -1.0)),
+        Direction::TopBottom => (rect.right_center(), egui::vec2(1.0, 0.0)),
+    };
+    let along = egui::vec2(-out.y, out.x);
+    let spread = (14.0 + 6.0 * index as f32) * scale;
+    let reach = (44.0 + 44.0 * index as f32) * scale;
+    let start = side + out * 2.0 * scale - along * spread;
+    let end = side + out * 2.0 * scale + along * spread;
+    let curve = egui::epaint::CubicBezierShape::from_points_stroke(
+        [
+            start,
+            start + out * reach - along * spread,
+            end + out * reach + along * spread,
+            end,
+        ],
+        false,
+        Color32::TRANSPARENT,
+        Stroke::NONE,
+    );
+    curve.flatten(Some(0.25 * scale))
+}
+
+/// Points along a path from `start` through `corners` to `end`, rounding
+/// each corner with a quadratic curve between the midpoints of its sides.
+fn rounded_path(start: Pos2, corners: &[Pos2], end: Pos2) -> Vec<Pos2> {
+    let mut path = Vec::with_capacity(corners.len() + 2);
+    path.push(start);
+    path.extend_from_slice(corners);
+    path.push(end);
+
+    let mut points = vec![start];
+    for w in path.windows(3) {
+        let curve = egui::epaint::QuadraticBezierShape::from_points_stroke(
+            [w[0].lerp(w[1], 0.5), w[1], w[1].lerp(w[2], 0.5)],
+            false,
+            Color32::TRANSPARENT,
+            Stroke::NONE,
+        );
+        points.extend(curve.flatten(None));
+    }
+    points.push(end);
+    points
+}
+
+/// Filled arrowhead with its tip at `tip`, pointing away from `from`.
+pub fn draw_arrow_head(ui: &egui::Ui, tip: Pos2, from: Pos2, size: f32, color: Color32) {
+    let dir = (tip - from).normalized();
+    let perp = egui::vec2(-dir.y, dir.x);
+    let p1 = tip - dir * size + perp * size * 0.4;
+    let p2 = tip - dir * size - perp * size * 0.4;
+    ui.painter().add(egui::Shape::convex_polygon(
+        vec![tip, p1, p2],
+        color,
+        Stroke::NONE,
+    ));
+}
+
+/// Draw one component: a pill with its label, a card with the icon above
+/// the label when the diagram uses icons, or the outline of its shape.
+fn draw_node(
+    ui: &egui::Ui,
+    node: &DiagramNode,
+    rect: Rect,
+    theme: &Theme,
+    opacity: f32,
+    with_icon: bool,
+    scale: f32,
+) {
+    let painter = ui.painter();
+    let corner_radius = match node.shape {
+        NodeShape::Auto if with_icon => 12.0 * scale,
+        NodeShape::Auto | NodeShape::Stadium => rect.height() / 2.0, // pill shape
+        NodeShape::Rounded => 12.0 * scale,
+        _ => 4.0 * scale,
+    };
+
+    let (fill, stroke, content_color) = match node.style {
+        NodeStyle::Primary => (
+            Theme::with_opacity(theme.accent, opacity * 0.9),
+            Stroke::NONE,
+            Theme::with_opacity(Color32::WHITE, opacity),
+        ),
+        NodeStyle::Secondary => (
+            Theme::with_opacity(theme.code_background, opacity),
+            Stroke::new(
+                2.0 * scale,
+                Theme::with_opacity(theme.accent, opacity * 0.9),
+            ),
+            Theme::with_opacity(theme.foreground, opacity),
+        ),
+        NodeStyle::Muted => (
+            Theme::with_opacity(theme.code_background, opacity * 0.7),
+            Stroke::NONE,
+            Theme::with_opacity(theme.foreground, opacity * 0.55),
+        ),
+    };
+
+    // Drop shadow
+    if node.style != NodeStyle::Muted {
+        let shadow_color = Theme::with_opacity(Color32::from_rgb(0, 0, 0), opacity * 0.15);
+        let shadow_offset = 2.0 * scale;
+        let shadow_rect = rect.translate(egui::vec2(shadow_offset, shadow_offset));
+        paint_shape(
+            painter,
+            node.shape,
+            shadow_rect,
+            corner_radius,
+            shadow_color,
+            Stroke::NONE,
+        );
+    }
+
+    paint_shape(painter, node.shape, rect, corner_radius, fill, stroke);
+
+    // Pointed and round outlines leave less room for text
+    let text_width = match node.shape {
+        NodeShape::Diamond => rect.width() * 0.6,
+        NodeShape::Circle | NodeShape::Hexagon => rect.width() * 0.75,
+        _ => rect.width() - 16.0 * scale,
+    };
+    let font_size = theme.body_size * 0.65 * scale;
+    let galley = painter.layout(
+        node.label.clone(),
+        FontId::proportional(font_size),
+        content_color,
+        text_width,
+    );
+
+    if with_icon {
+        let icon_size = ICON_SIZE * scale;
+        let icon_rect = Rect::from_min_size(
+            egui::pos2(rect.center().x - icon_size / 2.0, rect.top() + 12.0 * scale),
+            egui::vec2(icon_size, icon_size),
+        );
+        let icon = node.icon.as_deref().unwrap_or("box");
+        icons::draw(painter, icon, icon_rect, content_color);
+
+        let text_top = icon_rect.bottom() + 8.0 * scale;
+        let text_pos = egui::pos2(
+            rect.center().x - galley.rect.width() / 2.0,
+            text_top + (rect.bottom() - text_top - galley.rect.height()) / 2.0,
+        );
+        painter.galley(text_pos, galley, content_color);
+    } else {
+        let text_pos = rect.center() - galley.rect.size() / 2.0;
+        painter.galley(text_pos, galley, content_color);
+    }
+}
+
+/// Fill `rect` with a component outline. Rectangular shapes use
+/// `corner_radius`; the others are drawn inside the rect.
+fn paint_shape(
+    painter: &egui::Painter,
+    shape: NodeShape,
+    rect: Rect,
+    corner_radius: f32,
+    fill: Color32,
+    stroke: Stroke,
+) {
+    let outline = match shape {
+        NodeShape::Diamond => vec![
+            rect.center_top(),
+            rect.right_center(),
+            rect.center_bottom(),
+            rect.left_center(),
+        ],
+        NodeShape::Hexagon => {
+            let inset = (rect.height() / 2.0).min(rect.width() / 4.0);
+            vec![
+                rect.left_center(),
+                rect.left_top() + egui::vec2(inset, 0.0),
+                rect.right_top() - egui::vec2(inset, 0.0),
+                rect.right_center(),
+                rect.right_bottom() - egui::vec2(inset, 0.0),
+                rect.left_bottom() + egui::vec2(inset, 0.0),
+            ]
+        }
+        NodeShape::Circle => {
+            let radius = rect.size() / 2.0;
+            painter.add(egui::Shape::ellipse_filled(rect.center(), radius, fill));
+            painter.add(egui::Shape::ellipse_stroke(rect.center(), radius, stroke));
+            return;
+        }
+        NodeShape::Cylinder => {
+            // A body between two elliptical caps, with the rim of the top one drawn
+            let cap = egui::vec2(
+                rect.width() / 2.0,
+                (rect.height() * 0.15).min(rect.width() / 4.0),
+            );
+            let top = rect.center_top() + egui::vec2(0.0, cap.y);
+            let bottom = rect.center_bottom() - egui::vec2(0.0, cap.y);
+            let body = Rect::from_x_y_ranges(rect.x_range(), top.y..=bottom.y);
+            painter.add(egui::Shape::ellipse_filled(bottom, cap, fill));
+            painter.rect_filled(body, 0.0, fill);
+            painter.add(egui::Shape::ellipse_filled(top, cap, fill));
+            let rim = if stroke == Stroke::NONE {
+                Stroke::new(rect.height() * 0.03, Color32::from_black_alpha(60))
+            } else {
+                stroke
+            };
+            painter.add(egui::Shape::ellipse_stroke(top, cap, rim));
+            return;
+        }
+        NodeShape::Auto | NodeShape::Rect | NodeShape::Rounded | NodeShape::Stadium => {
+            painter.rect_filled(rect, corner_radius, fill);
+            if stroke != Stroke::NONE {
+                painter.rect_stroke(rect, corner_radius, stroke, egui::StrokeKind::Inside);
+            }
+            return;
+        }
+    };
+    painter.add(egui::Shape::convex_polygon(outline, fill, stroke));
+}
+
+/// Node height: icon cards if any component declares an icon, taller boxes
+/// for outlines that need the room, otherwise pills.
+fn node_height(diagram: &Diagram, scale: f32) -> f32 {
+    let shaped = |n: &DiagramNode| {
+        matches!(
+            n.shape,
+            NodeShape::Diamond | NodeShape::Circle | NodeShape::Hexagon | NodeShape::Cylinder
+        )
+    };
+    if diagram.nodes.iter().any(|n| n.icon.is_some()) {
+        CARD_HEIGHT * scale
+    } else if diagram.nodes.iter().any(shaped) {
+        SHAPE_HEIGHT * scale
+    } else {
+        PILL_HEIGHT * scale
+    }
+}
+
+fn grid_height(rows: usize, node_height: f32, scale: f32) -> f32 {
+    rows as f32 * node_height + rows.saturating_sub(1) as f32 * ROW_GAP * scale
+}
+
+/// Grid cells for the nodes and routes for the edges. Diagrams that declare
+/// a `pos`, or have no relationships, use the declared grid; the rest are
+/// laid out in layers.
+fn layout(diagram: &Diagram, direction: Direction) -> GraphLayout {
+    if diagram.edges.is_empty() || diagram.nodes.iter().any(|n| n.pos.is_some()) {
+        return GraphLayout {
+            cells: grid_positions(&diagram.nodes),
+            routes: vec![Vec::new(); diagram.edges.len()],
+        };
+    }
+    let index: HashMap<&str, usize> = diagram
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.name.as_str(), i))
+        .collect();
+    let edges: Vec<(usize, usize)> = diagram
+        .edges
+        .iter()
+        .map(|e| (index[e.from.as_str()], index[e.to.as_str()]))
+        .collect();
+    graph_layout::layered(diagram.nodes.len(), &edges, direction)
+}
+
+/// Zero-based grid cell for every node. Declared `pos` values are used as
+/// given; the remaining nodes fill a row below them. Without any `pos`, all
+/// nodes sit in a single row in declaration order.
+fn grid_positions(nodes: &[DiagramNode]) -> Vec<Pos2> {
+    let declared: Vec<(f32, f32)> = nodes.iter().filter_map(|n| n.pos).collect();
+    let (min_x, min_y, max_y) = declared.iter().fold(
+        (f32::MAX, f32::MAX, f32::MIN),
+        |(min_x, min_y, max_y), &(x, y)| (min_x.min(x), min_y.min(y), max_y.max(y)),
+    );
+    let auto_row = if declared.is_empty() {
+        0.0
+    } else {
+        (max_y - min_y).floor() + 1.0
+    };
+
+    let mut next_auto = 0.0;
+    nodes
+        .iter()
+        .map(|node| match node.pos {
+            Some((x, y)) => egui::pos2(x - min_x, y - min_y),
+            None => {
+                let cell = egui::pos2(next_auto, auto_row);
+                next_auto += 1.0;
+                cell
+            }
+        })
+        .collect()
+}
+
+/// Number of columns and rows the nodes and edge routes span.
+fn grid_size(layout: &GraphLayout) -> (usize, usize) {
+    let cells = layout.cells.iter().chain(layout.routes.iter().flatten());
+    let max = cells.fold(Pos2::ZERO, |max, c| {
+        egui::pos2(max.x.max(c.x), max.y.max(c.y))
+    });
+    let count = |v: f32| (v.floor() as usize).saturating_add(1);
+    (count(max.x), count(max.y))
+}
+
+/// Where the line from the center of `rect` toward `target` leaves the rect,
+/// pushed out by `gap`.
+fn boundary_point(rect: Rect, target: Pos2, gap: f32) -> Pos2 {
+    let center = rect.center();
+    let dir = target - center;
+    if dir.length() < f32::EPSILON {
+        return center;
+    }
+    let half = rect.size() / 2.0;
+    let t = (half.x / dir.x.abs()).min(half.y / dir.y.abs());
+    center + dir * t + dir.normalized() * gap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_positions_and_auto_row() {
+        let diagram = diagram::parse(
+            "- User     (icon: user,     pos: 1,1)\n\
+             - Server   (icon: server,   pos: 2,1)\n\
+             - Database (icon: database, pos: 2,2)\n\
+             - Server -> Logs\n\
+             - Server -> Metrics\n",
+        );
+        let layout = layout(&diagram, Direction::LeftRight);
+        assert_eq!(
+            layout.cells,
+            [
+                egui::pos2(0.0, 0.0),
+                egui::pos2(1.0, 0.0),
+                egui::pos2(1.0, 1.0),
+                egui::pos2(0.0, 2.0),
+                egui::pos2(1.0, 2.0),
+            ]
+        );
+        assert_eq!(grid_size(&layout), (2, 3));
+    }
+
+    #[test]
+    fn unpositioned_chain_is_one_row() {
+        let diagram = diagram::parse("- A -> B\n- B -> C");
+        assert_eq!(grid_size(&layout(&diagram, Direction::LeftRight)), (3, 1));
+        assert_eq!(grid_size(&layout(&diagram, Direction::TopBottom)), (1, 3));
+    }
+
+    #[test]
+    fn diagrams_are_laid_out_once_per_content() {
+        let ctx = egui::Context::default();
+        let content = "- A -> B\n- B -> C";
+        let native = DiagramSyntax::Native;
+        let first = source(&ctx, content, Direction::LeftRight, native);
+        assert!(Arc::ptr_eq(
+            &first,
+            &source(&ctx, content, Direction::LeftRight, native)
+        ));
+        let Source::Graph(_, _, layout) = &*source(&ctx, content, Direction::TopBottom, native)
+        else {
+            panic!("Expected a graph");
+        };
+        assert_eq!(grid_size(layout), (1, 3));
+        assert!(!Arc::ptr_eq(
+            &first,
+            &source(&ctx, "- A -> C", Direction::LeftRight, native)
+        ));
+    }
+
+    #[test]
+    fn grid_size_includes_edge_routes() {
+        // The A -> C route takes its own row beside B
+        let diagram = diagram::parse("- A -> B\n- B -> C\n- A -> C");
+        assert_eq!(grid_size(&layout(&diagram, Direction::LeftRight)), (3, 2));
+    }
+
+    #[test]
+    fn boundary_point_leaves_rect_toward_target() {
+        let rect = Rect::from_center_size(Pos2::ZERO, egui::vec2(100.0, 40.0));
+        let right = boundary_point(rect, egui::pos2(500.0, 0.0), 0.0);
+        assert!((right - egui::pos2(50.0, 0.0)).length() < 1e-3);
+        let up = boundary_point(rect, egui::pos2(0.0, -300.0), 2.0);
+        assert!((up - egui::pos2(0.0, -22.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn self_loops_leave_and_rejoin_one_side() {
+        let diagram = diagram::parse("- A -> B\n- A -> A: retry");
+        let layout = layout(&diagram, Direction::LeftRight);
+        assert_eq!(layout.cells.len(), 2);
+        assert_eq!(layout.routes[1], []);
+
+        let rect = Rect::from_center_size(Pos2::ZERO, egui::vec2(100.0, 40.0));
+        let top = self_loop(rect, Direction::LeftRight, 0, 1.0);
+        let (start, end) = (top[0], top[top.len() - 1]);
+        assert!(start.y < rect.top() && end.y < rect.top());
+        assert!(start.x < 0.0 && end.x > 0.0);
+
+        // The loop rises above the node, and a second one further still
+        let bounds = Rect::from_points(&top);
+        assert!(bounds.min.y < rect.top() - 30.0);
+        let second = Rect::from_points(&self_loop(rect, Direction::LeftRight, 1, 1.0));
+        assert!(second.min.y < bounds.min.y);
+
+        let right = Rect::from_points(&self_loop(rect, Direction::TopBottom, 0, 1.0));
+        assert!(right.min.x > rect.right() && right.max.x > rect.right() + 30.0);
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::VecDeque;
+
+use eframe::egui::{self, Pos2};
+
+use crate::parser::diagram::Direction;
+
+/// Barycenter sweeps over the layers, alternating down and up.
+const ORDER_SWEEPS: usize = 8;
+
+/// Grid cells for the nodes of a layered graph, plus the cells an edge passes
+/// through when it skips layers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphLayout {
+    /// Zero-based grid cell of each node, in node order.
+    pub cells: Vec<Pos2>,
+    /// Cells between the two ends of each edge, in `from` to `to` order.
+    /// Empty for edges between neighboring layers.
+    pub routes: Vec<Vec<Pos2>>,
+}
+
+/// Lay out `node_count` nodes joined by `(from, to)` edges in layers
+/// (Sugiyama-style): cycles are broken, each node goes one layer past its
+/// predecessors, edges that skip layers get a slot of their own in every
+/// layer they cross so no node sits on their path, and each layer is ordered
+/// by the average position of its neighbors to cut down on crossings.
+pub fn layered(node_count: usize, edges: &[(usize, usize)], direction: Direction) -> GraphLayout {
+    let dag = acyclic_edges(node_count, edges);
+    let node_layers = assign_layers(node_count, &dag);
+
+    // Split edges into one-layer hops through placeholder vertices that
+    // follow the real nodes
+    let mut vertex_layers = node_layers.clone();
+    let chains: Vec<Vec<usize>> = dag
+        .iter()
+        .map(|edge| {
+            let Some((from, to)) = *edge else {
+                return Vec::new();
+            };
+            let mut chain = vec![from];
+            for layer in node_layers[from] + 1..node_layers[to] {
+                vertex_layers.push(layer);
+                chain.push(vertex_layers.len() - 1);
+            }
+            chain.push(to);
+            chain
+        })
+        .collect();
+    let hops: Vec<(usize, usize)> = chains
+        .iter()
+        .flat_map(|chain| chain.windows(2).map(|w| (w[0], w[1])))
+        .collect();
+
+    let layer_count = vertex_layers.iter().max().map_or(0, |&l| l + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for (vertex, &layer) in vertex_layers.iter().enumerate() {
+        layers[layer].push(vertex);
+    }
+    order_layers(&mut layers, &hops, &vertex_layers);
+
+    // Center every layer on the widest one
+    let widest = layers.iter().map(Vec::len).max().unwrap_or(0);
+    let mut cells = vec![Pos2::ZERO; vertex_layers.len()];
+    for (layer, vertices) in layers.iter().enumerate() {
+        let offset = (widest - vertices.len()) as f32 / 2.0;
+        for (i, &vertex) in vertices.iter().enumerate() {
+            let (along, across) = (layer as f32, i as f32 + offset);
+            cells[vertex] = match direction {
+                Direction::LeftRight => egui::pos2(along, across),
+                Direction::TopBottom => egui::pos2(across, along),
+            };
+        }
+    }
+
+    let routes = edges
+        .iter()
+        .zip(&chains)
+        .map(|(&(from, _), chain)| {
+            let inner = chain.get(1..chain.len().saturating_sub(1)).unwrap_or(&[]);
+            let mut route: Vec<Pos2> = inner.iter().map(|&v| cells[v]).collect();
+            // Edges turned around to break a cycle run backwards
+            if chain.first() != Some(&from) {
+                route.reverse();
+            }
+            route
+        })
+        .collect();
+
+    cells.truncate(node_count);
+    GraphLayout { cells, routes }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+/// The edges with every one that closes a cycle turned around, found by a
+/// depth-first search in declaration order. Self-loops have no layer to span
+/// and are `None`.
+fn acyclic_edges(node_count: usize, edges: &[(usize, usize)]) -> Vec<Option<(usize, usize)>> {
+    fn visit(
+        node: usize,
+        edges: &[(usize, usize)],
+        outgoing: &[Vec<usize>],
+        state: &mut [Visit],
+        back: &mut [bool],
+    ) {
+        state[node] = Visit::Active;
+        for &edge in &outgoing[node] {
+            let to = edges[edge].1;
+            match state[to] {
+                Visit::New => visit(to, edges, outgoing, state, back),
+                Visit::Active => back[edge] = true,
+                Visit::Done => {}
+            }
+        }
+        state[node] = Visit::Done;
+    }
+
+    let mut outgoing = vec![Vec::new(); node_count];
+    for (i, &(from, to)) in edges.iter().enumerate() {
+        if from != to {
+            outgoing[from].push(i);
+        }
+    }
+    let mut state = vec![Visit::New; node_count];
+    let mut back = vec![false; edges.len()];
+    for node in 0..node_count {
+        if state[node] == Visit::New {
+            visit(node, edges, &outgoing, &mut state, &mut back);
+        }
+    }
+
+    edges
+        .iter()
+        .zip(back)
+        .map(|(&(from, to), back)| match (from == to, back) {
+            (true, _) => None,
+            (false, true) => Some((to, from)),
+            (false, false) => Some((from, to)),
+        })
+        .collect()
+}
+
+/// Longest-path layering: every node sits one layer past its furthest
+/// predecessor. Nodes without predecessors then move up to just before their
+/// nearest successor, so they don't trail long edges from the first layer.
+fn assign_layers(node_count: usize, dag: &[Option<(usize, usize)>]) -> Vec<usize> {
+    let edges: Vec<(usize, usize)> = dag.iter().flatten().copied().collect();
+    let mut incoming = vec![0; node_count];
+    for &(_, to) in &edges {
+        incoming[to] += 1;
+    }
+
+    let mut layers = vec![0; node_count];
+    let mut remaining = incoming.clone();
+    let mut queue: VecDeque<usize> = (0..node_count).filter(|&n| incoming[n] == 0).collect();
+    while let Some(node) = queue.pop_front() {
+        for &(_, to) in edges.iter().filter(|&&(from, _)| from == node) {
+            layers[to] = layers[to].max(layers[node] + 1);
+            remaining[to] -= 1;
+            if remaining[to] == 0 {
+                queue.push_back(to);
+            }
+        }
+    }
+
+    for node in (0..node_count).filter(|&n| incoming[n] == 0) {
+        let nearest = edges
+            .iter()
+            .filter(|&&(from, _)| from == node)
+            .map(|&(_, to)| layers[to])
+            .min();
+        if let Some(nearest) = nearest {
+            layers[node] = nearest - 1;
+        }
+    }
+    layers
+}
+
+/// Reorder each layer by the average position of its neighbors in the layer
+/// before (sweeping down) or after (sweeping up), keeping the order with the
+/// fewest crossings seen.
+fn order_layers(layers: &mut [Vec<usize>], hops: &[(usize, usize)], vertex_layers: &[usize]) {
+    let mut best = layers.to_vec();
+    let mut best_crossings = crossings(layers, hops, vertex_layers);
+
+    for sweep in 0..ORDER_SWEEPS {
+        let down = sweep % 2 == 0;
+        let mut position = positions(layers, vertex_layers.len());
+        let order: Vec<usize> = if down {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+
+        for layer in order {
+            let barycenter = |vertex: usize| -> f32 {
+                let neighbors: Vec<f32> = hops
+                    .iter()
+                    .filter_map(|&(from, to)| match down {
+                        true if to == vertex => Some(position[from] as f32),
+                        false if from == vertex => Some(position[to] as f32),
+                        _ => None,
+                    })
+                    .collect();
+                if neighbors.is_empty() {
+                    position[vertex] as f32
+                } else {
+                    neighbors.iter().sum::<f32>() / neighbors.len() as f32
+                }
+            };
+            let mut keyed: Vec<(f32, usize)> =
+                layers[layer].iter().map(|&v| (barycenter(v), v)).collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[layer] = keyed.into_iter().map(|(_, v)| v).collect();
+            for (i, &vertex) in layers[layer].iter().enumerate() {
+                position[vertex] = i;
+            }
+        }
+
+        let count = crossings(layers, hops, vertex_layers);
+        if count < best_crossings {
+            best = layers.to_vec();
+            best_crossings = count;
+        }
+    }
+
+    layers.clone_from_slice(&best);
+}
+
+/// Index of every vertex within its layer.
+fn positions(layers: &[Vec<usize>], vertex_count: usize) -> Vec<usize> {
+    let mut position = vec![0; vertex_count];
+    for vertices in layers {
+        for (i, &vertex) in vertices.iter().enumerate() {
+            position[vertex] = i;
+        }
+    }
+    position
+}
+
+/// Pairs of hops between the same two layers whose ends are in opposite orders.
+fn crossings(layers: &[Vec<usize>], hops: &[(usize, usize)], vertex_layers: &[usize]) -> usize {
+    let position = positions(layers, vertex_layers.len());
+    let order = |a: usize, b: usize| position[a].cmp(&position[b]);
+    hops.iter()
+        .enumerate()
+        .flat_map(|(i, a)| hops[i + 1..].iter().map(move |b| (a, b)))
+        .filter(|&(&(a_from, a_to), &(b_from, b_to))| {
+            vertex_layers[a_from] == vertex_layers[b_from]
+                && order(a_from, b_from) == order(b_to, a_to)
+                && order(a_from, b_from).is_ne()
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_runs_along_the_direction() {
+        let edges = [(0, 1), (1, 2)];
+        let across = layered(3, &edges, Direction::LeftRight);
+        assert_eq!(
+            across.cells,
+            [
+                egui::pos2(0.0, 0.0),
+                egui::pos2(1.0, 0.0),
+                egui::pos2(2.0, 0.0)
+            ]
+        );
+        let down = layered(3, &edges, Direction::TopBottom);
+        assert_eq!(
+            down.cells,
+            [
+                egui::pos2(0.0, 0.0),
+                egui::pos2(0.0, 1.0),
+                egui::pos2(0.0, 2.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn long_edges_route_around_nodes() {
+        // A -> B -> C -> D, and A -> D skipping two layers
+        let layout = layered(4, &[(0, 1), (1, 2), (2, 3), (0, 3)], Direction::LeftRight);
+        let route = &layout.routes[3];
+        assert_eq!(route.len(), 2);
+        assert!(route.iter().all(|cell| !layout.cells.contains(cell)));
+        assert_eq!((route[0].x, route[1].x), (1.0, 2.0));
+        assert!(layout.routes[..3].iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn ordering_removes_crossings() {
+        // A -> D and B -> C cross in declaration order
+        let layout = layered(4, &[(0, 3), (1, 2)], Direction::LeftRight);
+        let cells = &layout.cells;
+        assert!(cells[0].y < cells[1].y);
+        assert!(cells[3].y < cells[2].y);
+    }
+
+    #[test]
+    fn cycles_and_self_loops_are_laid_out() {
+        let layout = layered(3, &[(0, 1), (1, 2), (2, 0), (1, 1)], Direction::LeftRight);
+        let layers: Vec<f32> = layout.cells.iter().map(|c| c.x).collect();
+        assert_eq!(layers, [0.0, 1.0, 2.0]);
+        // The edge closing the cycle runs back from C to A through the middle layer
+        assert_eq!(layout.routes[2].len(), 1);
+        assert_eq!(layout.routes[2][0].x, 1.0);
+        assert!(layout.routes[3].is_empty());
+    }
+
+    #[test]
+    fn sources_move_next_to_their_successor() {
+        // A -> B -> C, and D -> C
+        let layout = layered(4, &[(0, 1), (1, 2), (3, 2)], Direction::TopBottom);
+        assert_eq!(layout.cells[3].y, 1.0);
+        assert!(layout.routes[2].is_empty());
+    }
+}
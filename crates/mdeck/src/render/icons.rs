@@ -0,0 +1,268 @@
+use std::f32::consts::{PI, TAU};
+
+use eframe::egui::{self, Color32, Pos2, Rect, Shape, Stroke};
+
+/// Draw a built-in icon as a line drawing inside the square `rect`.
+/// Unknown names fall back to `box`.
+pub fn draw(painter: &egui::Painter, name: &str, rect: Rect, color: Color32) {
+    let icon = Icon {
+        painter,
+        rect,
+        stroke: Stroke::new((rect.width() * 0.06).max(1.0), color),
+    };
+    match name {
+        "user" => icon.user(),
+        "server" => icon.server(),
+        "database" => icon.database(),
+        "cloud" => icon.cloud(),
+        "browser" => icon.browser(),
+        "mobile" => icon.mobile(),
+        "api" => icon.api(),
+        "queue" => icon.queue(),
+        "cache" => icon.cache(),
+        "storage" => icon.storage(),
+        "function" => icon.function(),
+        "container" => icon.container(),
+        "network" => icon.network(),
+        "lock" => icon.lock(),
+        "key" => icon.key(),
+        "mail" => icon.mail(),
+        "logs" => icon.logs(),
+        "monitor" => icon.monitor(),
+        _ => icon.cube(),
+    }
+}
+
+/// Icons are drawn in unit coordinates: (0, 0) is the top-left of the icon
+/// rect and (1, 1) the bottom-right.
+struct Icon<'a> {
+    painter: &'a egui::Painter,
+    rect: Rect,
+    stroke: Stroke,
+}
+
+impl Icon<'_> {
+    fn p(&self, x: f32, y: f32) -> Pos2 {
+        self.rect.min + egui::vec2(x, y) * self.rect.width()
+    }
+
+    fn len(&self, v: f32) -> f32 {
+        v * self.rect.width()
+    }
+
+    fn line(&self, points: &[(f32, f32)]) {
+        let points = points.iter().map(|&(x, y)| self.p(x, y)).collect();
+        self.painter.add(Shape::line(points, self.stroke));
+    }
+
+    fn closed(&self, points: &[(f32, f32)]) {
+        let points = points.iter().map(|&(x, y)| self.p(x, y)).collect();
+        self.painter.add(Shape::closed_line(points, self.stroke));
+    }
+
+    fn rect(&self, x0: f32, y0: f32, x1: f32, y1: f32, rounding: f32) {
+        let rect = Rect::from_min_max(self.p(x0, y0), self.p(x1, y1));
+        self.painter.rect_stroke(
+            rect,
+            self.len(rounding),
+            self.stroke,
+            egui::StrokeKind::Middle,
+        );
+    }
+
+    fn circle(&self, x: f32, y: f32, r: f32) {
+        self.painter
+            .circle_stroke(self.p(x, y), self.len(r), self.stroke);
+    }
+
+    fn dot(&self, x: f32, y: f32, r: f32) {
+        self.painter
+            .circle_filled(self.p(x, y), self.len(r), self.stroke.color);
+    }
+
+    /// Elliptical arc from angle `a0` to `a1` (radians, 0 = right, clockwise on screen).
+    fn arc(&self, center: (f32, f32), radius: (f32, f32), a0: f32, a1: f32) {
+        let steps = 24;
+        let points: Vec<(f32, f32)> = (0..=steps)
+            .map(|i| {
+                let a = a0 + (a1 - a0) * i as f32 / steps as f32;
+                (center.0 + radius.0 * a.cos(), center.1 + radius.1 * a.sin())
+            })
+            .collect();
+        self.line(&points);
+    }
+
+    fn user(&self) {
+        self.circle(0.5, 0.3, 0.17);
+        self.arc((0.5, 0.9), (0.34, 0.32), PI, TAU);
+        self.line(&[(0.16, 0.9), (0.84, 0.9)]);
+    }
+
+    fn server(&self) {
+        for y in [0.12, 0.55] {
+            self.rect(0.12, y, 0.88, y + 0.33, 0.06);
+            self.dot(0.27, y + 0.165, 0.045);
+            self.line(&[(0.45, y + 0.165), (0.74, y + 0.165)]);
+        }
+    }
+
+    fn database(&self) {
+        let (rx, ry) = (0.34, 0.1);
+        self.painter.add(Shape::ellipse_stroke(
+            self.p(0.5, 0.2),
+            egui::vec2(self.len(rx), self.len(ry)),
+            self.stroke,
+        ));
+        self.line(&[(0.5 - rx, 0.2), (0.5 - rx, 0.8)]);
+        self.line(&[(0.5 + rx, 0.2), (0.5 + rx, 0.8)]);
+        self.arc((0.5, 0.5), (rx, ry), 0.0, PI);
+        self.arc((0.5, 0.8), (rx, ry), 0.0, PI);
+    }
+
+    fn cloud(&self) {
+        // Outline of a union of circles, traced around a point inside all of them
+        let circles = [(0.32, 0.58, 0.18), (0.54, 0.44, 0.24), (0.74, 0.6, 0.16)];
+        let center = egui::vec2(0.53, 0.6);
+        let bottom = 0.76;
+        let steps = 72;
+        let points: Vec<(f32, f32)> = (0..steps)
+            .map(|i| {
+                let a = TAU * i as f32 / steps as f32;
+                let dir = egui::vec2(a.cos(), a.sin());
+                let reach = circles
+                    .iter()
+                    .filter_map(|&(cx, cy, r)| {
+                        let to_center = egui::vec2(cx, cy) - center;
+                        let b = dir.dot(to_center);
+                        let disc = b * b - (to_center.length_sq() - r * r);
+                        (disc >= 0.0).then(|| b + disc.sqrt())
+                    })
+                    .fold(0.0, f32::max);
+                let point = center + dir * reach;
+                (point.x, point.y.min(bottom))
+            })
+            .collect();
+        self.closed(&points);
+    }
+
+    fn browser(&self) {
+        self.rect(0.08, 0.16, 0.92, 0.84, 0.06);
+        self.line(&[(0.08, 0.34), (0.92, 0.34)]);
+        for x in [0.18, 0.28, 0.38] {
+            self.dot(x, 0.25, 0.035);
+        }
+    }
+
+    fn mobile(&self) {
+        self.rect(0.28, 0.08, 0.72, 0.92, 0.08);
+        self.line(&[(0.43, 0.17), (0.57, 0.17)]);
+        self.dot(0.5, 0.82, 0.04);
+    }
+
+    fn api(&self) {
+        self.line(&[(0.32, 0.28), (0.12, 0.5), (0.32, 0.72)]);
+        self.line(&[(0.68, 0.28), (0.88, 0.5), (0.68, 0.72)]);
+        self.line(&[(0.57, 0.22), (0.43, 0.78)]);
+    }
+
+    fn queue(&self) {
+        self.rect(0.06, 0.3, 0.94, 0.7, 0.06);
+        for x in [0.28, 0.5, 0.72] {
+            self.line(&[(x, 0.3), (x, 0.7)]);
+        }
+    }
+
+    fn cache(&self) {
+        self.closed(&[
+            (0.58, 0.06),
+            (0.24, 0.56),
+            (0.47, 0.56),
+            (0.4, 0.94),
+            (0.76, 0.42),
+            (0.53, 0.42),
+        ]);
+    }
+
+    fn storage(&self) {
+        self.rect(0.08, 0.32, 0.92, 0.72, 0.08);
+        self.line(&[(0.08, 0.55), (0.92, 0.55)]);
+        self.dot(0.78, 0.635, 0.04);
+        self.dot(0.66, 0.635, 0.04);
+    }
+
+    fn function(&self) {
+        self.line(&[(0.24, 0.14), (0.36, 0.14), (0.76, 0.86)]);
+        self.line(&[(0.54, 0.47), (0.26, 0.86)]);
+    }
+
+    fn container(&self) {
+        self.rect(0.06, 0.26, 0.94, 0.74, 0.04);
+        for x in [0.24, 0.41, 0.59, 0.76] {
+            self.line(&[(x, 0.34), (x, 0.66)]);
+        }
+    }
+
+    fn network(&self) {
+        let nodes: [(f32, f32); 3] = [(0.5, 0.2), (0.2, 0.78), (0.8, 0.78)];
+        for (i, &(x0, y0)) in nodes.iter().enumerate() {
+            let (x1, y1) = nodes[(i + 1) % nodes.len()];
+            // Shorten each link so it meets the node circles
+            let (dx, dy) = (x1 - x0, y1 - y0);
+            let t = 0.12 / (dx * dx + dy * dy).sqrt();
+            self.line(&[(x0 + dx * t, y0 + dy * t), (x1 - dx * t, y1 - dy * t)]);
+            self.circle(x0, y0, 0.12);
+        }
+    }
+
+    fn lock(&self) {
+        self.rect(0.2, 0.46, 0.8, 0.9, 0.06);
+        self.arc((0.5, 0.46), (0.19, 0.26), PI, TAU);
+        self.dot(0.5, 0.64, 0.05);
+        self.line(&[(0.5, 0.66), (0.5, 0.76)]);
+    }
+
+    fn key(&self) {
+        self.circle(0.28, 0.5, 0.16);
+        self.line(&[(0.44, 0.5), (0.9, 0.5)]);
+        self.line(&[(0.76, 0.5), (0.76, 0.64)]);
+        self.line(&[(0.87, 0.5), (0.87, 0.6)]);
+    }
+
+    fn mail(&self) {
+        self.rect(0.08, 0.24, 0.92, 0.76, 0.04);
+        self.line(&[(0.08, 0.26), (0.5, 0.54), (0.92, 0.26)]);
+    }
+
+    fn logs(&self) {
+        self.closed(&[
+            (0.2, 0.08),
+            (0.62, 0.08),
+            (0.8, 0.26),
+            (0.8, 0.92),
+            (0.2, 0.92),
+        ]);
+        self.line(&[(0.62, 0.08), (0.62, 0.26), (0.8, 0.26)]);
+        for y in [0.42, 0.57, 0.72] {
+            self.line(&[(0.32, y), (0.68, y)]);
+        }
+    }
+
+    fn monitor(&self) {
+        self.rect(0.08, 0.14, 0.92, 0.66, 0.04);
+        self.line(&[(0.5, 0.66), (0.5, 0.82)]);
+        self.line(&[(0.3, 0.84), (0.7, 0.84)]);
+    }
+
+    fn cube(&self) {
+        self.closed(&[
+            (0.5, 0.08),
+            (0.87, 0.28),
+            (0.87, 0.72),
+            (0.5, 0.92),
+            (0.13, 0.72),
+            (0.13, 0.28),
+        ]);
+        self.line(&[(0.13, 0.28), (0.5, 0.48), (0.87, 0.28)]);
+        self.line(&[(0.5, 0.48), (0.5, 0.92)]);
+    }
+}
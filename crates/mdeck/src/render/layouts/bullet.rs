@@ -2,7 +2,7 @@ use eframe::egui::{self, Pos2};
 
 use crate::parser::{Block, Slide};
 use crate::render::image_cache::ImageCache;
-use crate::render::text;
+use crate::render::{math, text};
 use crate::theme::Theme;
 
 #[allow(clippy::too_many_arguments)]
@@ -122,7 +122,7 @@ fn measure_block_height(
     match block {
         Block::Heading { level, inlines } => {
             let size = theme.heading_size(*level) * scale;
-            let job = text::inlines_to_job(inlines, size, theme.heading_color, max_width);
+            let job = text::heading_job(ui, inlines, size, theme.heading_color, max_width);
             ui.painter().layout_job(job).rect.height()
         }
         Block::Paragraph { inlines } | Block::BlockQuote { inlines } => {
@@ -146,6 +146,7 @@ fn measure_block_height(
             rows.len() as f32 * row_height + 10.0 * scale
         }
         Block::HorizontalRule => 2.0 * scale,
+        Block::Math { tex } => math::measure_height(ui, tex, theme, max_width, scale),
         _ => theme.body_size * scale * 1.5,
     }
 }
@@ -1,8 +1,8 @@
 use eframe::egui::{self, Pos2};
 
-use crate::parser::{Block, Slide};
+use crate::parser::{Block, CodeInclude, Slide};
 use crate::render::image_cache::ImageCache;
-use crate::render::text;
+use crate::render::{math, text};
 use crate::theme::Theme;
 
 #[allow(clippy::too_many_arguments)]
@@ -69,13 +69,18 @@ pub fn render(
             Block::CodeBlock {
                 language,
                 code,
-                highlight_lines,
+                highlight_steps,
+                include: include @ (None | Some(CodeInclude { error: None, .. })),
+                options,
             } => {
                 let h = text::draw_code_block(
                     ui,
                     code,
                     language.as_deref(),
-                    highlight_lines,
+                    highlight_steps,
+                    *options,
+                    include.as_ref().map_or(&[], |i| &i.line_numbers),
+                    reveal_step,
                     theme,
                     Pos2::new(content_left, y),
                     content_width,
@@ -122,7 +127,7 @@ fn measure_block_height(
     match block {
         Block::Heading { level, inlines } => {
             let size = theme.heading_size(*level) * scale;
-            let job = text::inlines_to_job(inlines, size, theme.heading_color, max_width);
+            let job = text::heading_job(ui, inlines, size, theme.heading_color, max_width);
             ui.painter().layout_job(job).rect.height()
         }
         Block::Paragraph { inlines } | Block::BlockQuote { inlines } => {
@@ -136,6 +141,7 @@ fn measure_block_height(
             let padding = 16.0 * scale;
             line_count as f32 * line_height + padding * 2.0
         }
+        Block::Math { tex } => math::measure_height(ui, tex, theme, max_width, scale),
         _ => theme.body_size * scale * 1.5,
     }
 }
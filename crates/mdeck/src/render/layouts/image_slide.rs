@@ -2,7 +2,7 @@ use eframe::egui::{self, Pos2};
 
 use crate::parser::{Block, Slide};
 use crate::render::image_cache::ImageCache;
-use crate::render::text;
+use crate::render::{matches, text};
 use crate::theme::Theme;
 
 /// Image slide layout: prominent image with optional heading and caption.
@@ -143,7 +143,9 @@ pub fn render(
         let galley = ui.painter().layout_job(job);
         let caption_x =
             image_drawn_rect.left() + (image_drawn_rect.width() - galley.rect.width()) / 2.0;
-        ui.painter()
-            .galley(Pos2::new(caption_x, caption_y), galley, caption_color);
+        let caption_pos = Pos2::new(caption_x, caption_y);
+        matches::paint(ui, &galley, caption_pos);
+        text::register_links(ui, inlines, &galley, caption_pos);
+        ui.painter().galley(caption_pos, galley, caption_color);
     }
 }
@@ -1,7 +1,7 @@
 use eframe::egui::{self, Pos2};
 
 use crate::parser::{Block, Inline, Slide};
-use crate::render::text;
+use crate::render::{matches, text};
 use crate::theme::Theme;
 
 #[allow(clippy::too_many_arguments)]
@@ -25,10 +25,8 @@ pub fn render(
         match block {
             Block::Heading { level, inlines } => heading = Some((*level, inlines)),
             Block::BlockQuote { inlines } => quote_inlines = Some(inlines),
-            Block::Paragraph { inlines } => {
-                if quote_inlines.is_some() {
-                    attribution = Some(inlines);
-                }
+            Block::Paragraph { inlines } if quote_inlines.is_some() => {
+                attribution = Some(inlines);
             }
             _ => {}
         }
@@ -90,7 +88,10 @@ pub fn render(
         let text_x = quote_x + (quote_width - text_width) / 2.0;
 
         // Draw the quote text (marks are part of the text flow)
-        ui.painter().galley(Pos2::new(text_x, y), galley, color);
+        let text_pos = Pos2::new(text_x, y);
+        matches::paint(ui, &galley, text_pos);
+        text::register_links(ui, &quoted_inlines, &galley, text_pos);
+        ui.painter().galley(text_pos, galley, color);
 
         // Draw left accent bar spanning the quote text
         let bar_rect =
@@ -110,8 +111,10 @@ pub fn render(
         let job = text::inlines_to_job(&cleaned, attr_size, color, content_rect.width());
 
         let galley = ui.painter().layout_job(job);
-        let x = content_rect.right() - galley.rect.width() - 40.0 * scale;
-        ui.painter().galley(Pos2::new(x, y), galley, color);
+        let pos = Pos2::new(content_rect.right() - galley.rect.width() - 40.0 * scale, y);
+        matches::paint(ui, &galley, pos);
+        text::register_links(ui, &cleaned, &galley, pos);
+        ui.painter().galley(pos, galley, color);
     }
 }
 
@@ -1,7 +1,7 @@
 use eframe::egui::{self, Pos2};
 
 use crate::parser::{Block, Slide};
-use crate::render::text;
+use crate::render::{matches, text};
 use crate::theme::Theme;
 
 pub fn render(
@@ -23,10 +23,8 @@ pub fn render(
         match block {
             Block::Heading { level: 1, inlines } => heading_inlines = Some(inlines),
             Block::Heading { level: 2, inlines } => subtitle_inlines = Some(inlines),
-            Block::Paragraph { inlines } => {
-                if subtitle_inlines.is_none() {
-                    subtitle_inlines = Some(inlines);
-                }
+            Block::Paragraph { inlines } if subtitle_inlines.is_none() => {
+                subtitle_inlines = Some(inlines);
             }
             _ => {}
         }
@@ -51,10 +49,12 @@ pub fn render(
     // Draw title centered
     if let Some(inlines) = heading_inlines {
         let color = Theme::with_opacity(theme.heading_color, opacity);
-        let job = text::inlines_to_job(inlines, title_size, color, content_rect.width());
+        let job = text::heading_job(ui, inlines, title_size, color, content_rect.width());
         let galley = ui.painter().layout_job(job);
         let x = content_rect.left() + (content_rect.width() - galley.rect.width()) / 2.0;
         let pos = Pos2::new(x, y);
+        matches::paint(ui, &galley, pos);
+        text::register_links(ui, inlines, &galley, pos);
         ui.painter().galley(pos, galley, color);
         y += title_size * 1.2 + 20.0 * scale;
     }
@@ -66,6 +66,8 @@ pub fn render(
         let galley = ui.painter().layout_job(job);
         let x = content_rect.left() + (content_rect.width() - galley.rect.width()) / 2.0;
         let pos = Pos2::new(x, y);
+        matches::paint(ui, &galley, pos);
+        text::register_links(ui, inlines, &galley, pos);
         ui.painter().galley(pos, galley, color);
     }
 }
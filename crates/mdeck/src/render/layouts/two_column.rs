@@ -62,7 +62,7 @@ pub fn render(
         if let Block::Heading { level, inlines } = *block {
             let size = theme.heading_size(*level) * scale;
             let job =
-                text::inlines_to_job(inlines, size, theme.heading_color, content_rect.width());
+                text::heading_job(ui, inlines, size, theme.heading_color, content_rect.width());
             heading_height += ui.painter().layout_job(job).rect.height() + 30.0 * scale;
         }
     }
@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use eframe::egui::{self, Galley, Pos2, Rect};
+use egui::text::CCursor;
+
+/// A link drawn on screen, for hit-testing clicks and for export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkArea {
+    pub rect: Rect,
+    pub url: String,
+}
+
+impl LinkArea {
+    /// Whether the URL points at another slide, like `#slide-5`.
+    pub fn is_internal(&self) -> bool {
+        self.url.starts_with('#')
+    }
+}
+
+fn id() -> egui::Id {
+    egui::Id::new("mdeck-links")
+}
+
+/// Record the links in a painted galley. `ranges` are byte ranges of the
+/// galley's text with the URL each one links to.
+pub fn register(ui: &egui::Ui, galley: &Galley, pos: Pos2, ranges: &[(Range<usize>, String)]) {
+    if ranges.is_empty() {
+        return;
+    }
+    let clip = ui.clip_rect();
+    let areas: Vec<LinkArea> = ranges
+        .iter()
+        .flat_map(|(range, url)| {
+            text_rects(galley, range.clone())
+                .into_iter()
+                .map(|rect| rect.translate(pos.to_vec2()).intersect(clip))
+                .filter(|rect| rect.is_positive())
+                .map(|rect| LinkArea {
+                    rect,
+                    url: url.clone(),
+                })
+        })
+        .collect();
+    ui.ctx().data_mut(|d| {
+        d.get_temp_mut_or_default::<Vec<LinkArea>>(id())
+            .extend(areas)
+    });
+}
+
+/// Links registered since the last call, in screen coordinates.
+pub fn take(ctx: &egui::Context) -> Vec<LinkArea> {
+    ctx.data_mut(|d| std::mem::take(d.get_temp_mut_or_default::<Vec<LinkArea>>(id())))
+}
+
+/// The link under `pos`, if any.
+pub fn at(areas: &[LinkArea], pos: Pos2) -> Option<&LinkArea> {
+    areas.iter().find(|area| area.rect.contains(pos))
+}
+
+/// One rectangle per row covered by a byte range of the galley's text.
+pub fn text_rects(galley: &Galley, bytes: Range<usize>) -> Vec<Rect> {
+    let text = galley.text();
+    let to_char = |byte: usize| text[..byte.min(text.len())].chars().count();
+    let (start, end) = (to_char(bytes.start), to_char(bytes.end));
+
+    let mut rects: Vec<Rect> = Vec::new();
+    for i in start..end {
+        let left = galley.pos_from_cursor(CCursor::new(i));
+        let right = galley.pos_from_cursor(CCursor::new(i + 1));
+        // A glyph that wrapped onto the next row has its right edge there
+        if right.min.y != left.min.y {
+            continue;
+        }
+        let glyph = Rect::from_x_y_ranges(left.min.x..=right.min.x, left.y_range());
+        match rects.last_mut() {
+            Some(last) if last.min.y == glyph.min.y => *last = last.union(glyph),
+            _ => rects.push(glyph),
+        }
+    }
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_map_to_one_rect_per_row() {
+        let ctx = egui::Context::default();
+        let _ = ctx.run(Default::default(), |_| {});
+        let galley = ctx.fonts_mut(|f| {
+            let mut job = egui::text::LayoutJob::simple(
+                "see the docs here".to_string(),
+                egui::FontId::proportional(20.0),
+                egui::Color32::WHITE,
+                70.0,
+            );
+            job.wrap.break_anywhere = false;
+            f.layout_job(job)
+        });
+        assert!(galley.rows.len() > 1);
+
+        let one = text_rects(&galley, 0..3);
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].left(), 0.0);
+
+        let all = text_rects(&galley, 0..galley.text().len());
+        assert_eq!(all.len(), galley.rows.len());
+        assert!(all.windows(2).all(|w| w[0].bottom() <= w[1].top() + 0.5));
+    }
+
+    #[test]
+    fn finds_the_link_under_the_pointer() {
+        let areas = [LinkArea {
+            rect: Rect::from_min_size(egui::pos2(10.0, 10.0), egui::vec2(50.0, 20.0)),
+            url: "#slide-2".to_string(),
+        }];
+        assert!(at(&areas, egui::pos2(30.0, 20.0)).is_some_and(LinkArea::is_internal));
+        assert!(at(&areas, egui::pos2(5.0, 20.0)).is_none());
+    }
+}
@@ -0,0 +1,36 @@
+use eframe::egui::{self, Color32, Galley, Pos2};
+
+use crate::render::links;
+use crate::search;
+
+/// Background behind matched text.
+pub const MARK_COLOR: Color32 = Color32::from_rgba_premultiplied(110, 90, 0, 110);
+
+fn id() -> egui::Id {
+    egui::Id::new("mdeck-search-query")
+}
+
+/// Set the search text to mark on slides drawn from now on, or `None` to
+/// stop marking.
+pub fn set_query(ctx: &egui::Context, query: Option<&str>) {
+    let query = query.map(str::trim).filter(|q| !q.is_empty());
+    ctx.data_mut(|d| match query {
+        Some(query) => d.insert_temp(id(), query.to_string()),
+        None => d.remove::<String>(id()),
+    });
+}
+
+/// Mark every match of the search text in a galley about to be painted at
+/// `pos`. Call before painting the galley so the text stays on top.
+pub fn paint(ui: &egui::Ui, galley: &Galley, pos: Pos2) {
+    let Some(query) = ui.ctx().data(|d| d.get_temp::<String>(id())) else {
+        return;
+    };
+    let painter = ui.painter();
+    for range in search::find_all(galley.text(), &query) {
+        for rect in links::text_rects(galley, range) {
+            let rect = rect.translate(pos.to_vec2()).expand(2.0);
+            painter.rect_filled(rect, 3.0, MARK_COLOR);
+        }
+    }
+}
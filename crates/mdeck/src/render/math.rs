@@ -0,0 +1,788 @@
+use std::sync::Arc;
+
+use eframe::egui::{self, Align, Color32, FontFamily, FontId, Galley, Pos2, Stroke, Vec2};
+use egui::text::{LayoutJob, TextFormat};
+
+use crate::parser::math::{self, Accent, ColumnAlign, MathNode};
+use crate::theme::Theme;
+
+/// Display math is set a little larger than body text.
+const DISPLAY_SCALE: f32 = 1.1;
+/// Scripts, limits and inline fraction parts shrink by this much per level.
+const SCRIPT_SCALE: f32 = 0.7;
+/// Space above and below display math, in ems.
+const BLOCK_PADDING: f32 = 0.25;
+
+/// Height of a display math block, matching what [`draw`] uses.
+pub fn measure_height(ui: &egui::Ui, tex: &str, theme: &Theme, max_width: f32, scale: f32) -> f32 {
+    let (math, size) = layout_block(ui, tex, theme.foreground, theme, max_width, scale);
+    math.ascent + math.descent + size * BLOCK_PADDING * 2.0
+}
+
+/// Draw display math centered in `max_width`, shrunk to fit if it's wider.
+/// Returns the height used.
+pub fn draw(
+    ui: &egui::Ui,
+    tex: &str,
+    theme: &Theme,
+    pos: Pos2,
+    max_width: f32,
+    opacity: f32,
+    scale: f32,
+) -> f32 {
+    let color = Theme::with_opacity(theme.foreground, opacity);
+    let (math, size) = layout_block(ui, tex, color, theme, max_width, scale);
+    let padding = size * BLOCK_PADDING;
+    let origin = egui::pos2(
+        pos.x + (max_width - math.width) / 2.0,
+        pos.y + padding + math.ascent,
+    );
+    math.paint(ui.painter(), origin, color);
+    math.ascent + math.descent + padding * 2.0
+}
+
+fn layout_block(
+    ui: &egui::Ui,
+    tex: &str,
+    color: Color32,
+    theme: &Theme,
+    max_width: f32,
+    scale: f32,
+) -> (MathBox, f32) {
+    let node = math::parse(tex);
+    let size = theme.body_size * DISPLAY_SCALE * scale;
+    let style = Style {
+        painter: ui.painter(),
+        color,
+        min_size: size * 0.5,
+    };
+    let math = style.layout(&node, size, true);
+    if math.width <= max_width || math.width == 0.0 {
+        return (math, size);
+    }
+    let size = size * max_width / math.width;
+    let style = Style {
+        min_size: size * 0.5,
+        ..style
+    };
+    (style.layout(&node, size, true), size)
+}
+
+/// Append inline math to a text layout job, flattened onto the line:
+/// scripts are raised or lowered and fractions are written as `a/b`.
+pub fn append_inline(job: &mut LayoutJob, tex: &str, font_size: f32, color: Color32) {
+    let mut line = InlineMath {
+        job,
+        color,
+        min_size: font_size * 0.5,
+    };
+    line.append(&math::parse(tex), font_size, Align::Center);
+}
+
+struct InlineMath<'a> {
+    job: &'a mut LayoutJob,
+    color: Color32,
+    min_size: f32,
+}
+
+impl InlineMath<'_> {
+    fn text(&mut self, text: &str, size: f32, valign: Align, italics: bool) {
+        let format = TextFormat {
+            font_id: FontId::new(size, FontFamily::Proportional),
+            color: self.color,
+            italics,
+            valign,
+            ..Default::default()
+        };
+        self.job.append(text, 0.0, format);
+    }
+
+    /// A node that reads as one unit on its own, or in parentheses otherwise.
+    fn group(&mut self, node: &MathNode, size: f32, valign: Align) {
+        if node.is_compound() {
+            self.text("(", size, valign, false);
+            self.append(node, size, valign);
+            self.text(")", size, valign, false);
+        } else {
+            self.append(node, size, valign);
+        }
+    }
+
+    fn script_size(&self, size: f32) -> f32 {
+        (size * SCRIPT_SCALE).max(self.min_size)
+    }
+
+    fn append(&mut self, node: &MathNode, size: f32, valign: Align) {
+        match node {
+            MathNode::Ident(s) => self.text(s, size, valign, true),
+            MathNode::Upright(s) | MathNode::Text(s) | MathNode::DoubleStruck(s) => {
+                // Vertical and diagonal dots have no glyph in the bundled fonts
+                let s = match s.as_str() {
+                    "⋮" => ":",
+                    "⋱" => "…",
+                    s => s,
+                };
+                self.text(s, size, valign, false)
+            }
+            MathNode::Operator(s) => self.text(&format!(" {s} "), size, valign, false),
+            MathNode::Space(em) if *em >= 0.5 => self.text("  ", size, valign, false),
+            MathNode::Space(em) if *em > 0.0 => self.text(" ", size * 0.5, valign, false),
+            MathNode::Space(_) => {}
+            MathNode::Row(nodes) => {
+                for node in nodes {
+                    self.append(node, size, valign);
+                }
+            }
+            MathNode::Frac { num, den, bar } => {
+                self.group(num, size, valign);
+                self.text(if *bar { "/" } else { " " }, size, valign, false);
+                self.group(den, size, valign);
+            }
+            MathNode::Sqrt { index, body } => {
+                if let Some(index) = index {
+                    self.append(index, self.script_size(size), Align::TOP);
+                }
+                self.text("√", size, valign, false);
+                self.group(body, size, valign);
+            }
+            MathNode::Scripts { base, sub, sup } => {
+                self.append(base, size, valign);
+                let script = self.script_size(size);
+                if let Some(sub) = sub {
+                    self.append(sub, script, Align::BOTTOM);
+                }
+                if let Some(sup) = sup {
+                    self.append(sup, script, Align::TOP);
+                }
+            }
+            MathNode::BigOp { symbol, .. } => self.text(symbol, size, valign, false),
+            MathNode::Accent { accent, body } => {
+                self.append(body, size, valign);
+                let mark = match accent {
+                    Accent::Hat => "^",
+                    Accent::Bar => "¯",
+                    Accent::Vec => "→",
+                    Accent::Dot => "·",
+                    Accent::Tilde => "~",
+                };
+                self.text(mark, self.script_size(size), Align::TOP, false);
+            }
+            MathNode::Delimited { open, body, close } => {
+                self.text(open, size, valign, false);
+                self.append(body, size, valign);
+                self.text(close, size, valign, false);
+            }
+            MathNode::Matrix {
+                rows, open, close, ..
+            } => {
+                self.text(open, size, valign, false);
+                for (r, row) in rows.iter().enumerate() {
+                    if r > 0 {
+                        self.text("; ", size, valign, false);
+                    }
+                    for (c, cell) in row.iter().enumerate() {
+                        if c > 0 {
+                            self.text("  ", size, valign, false);
+                        }
+                        self.append(cell, size, valign);
+                    }
+                }
+                self.text(close, size, valign, false);
+            }
+        }
+    }
+}
+
+/// Something drawn as part of a [`MathBox`], relative to its origin on the
+/// baseline at the left edge.
+enum Item {
+    Text { offset: Vec2, galley: Arc<Galley> },
+    Line { points: Vec<Vec2>, width: f32 },
+    Dot { center: Vec2, radius: f32 },
+}
+
+/// Laid out math, TeX-style: a width, the height above the baseline and the
+/// depth below it.
+#[derive(Default)]
+struct MathBox {
+    width: f32,
+    ascent: f32,
+    descent: f32,
+    items: Vec<Item>,
+}
+
+impl MathBox {
+    fn space(width: f32) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Add `other` with its origin at `offset` from ours, growing to fit.
+    fn place(&mut self, other: MathBox, offset: Vec2) {
+        self.ascent = self.ascent.max(other.ascent - offset.y);
+        self.descent = self.descent.max(other.descent + offset.y);
+        self.width = self.width.max(offset.x + other.width);
+        self.items
+            .extend(other.items.into_iter().map(|item| match item {
+                Item::Text { offset: o, galley } => Item::Text {
+                    offset: o + offset,
+                    galley,
+                },
+                Item::Line { points, width } => Item::Line {
+                    points: points.into_iter().map(|p| p + offset).collect(),
+                    width,
+                },
+                Item::Dot { center, radius } => Item::Dot {
+                    center: center + offset,
+                    radius,
+                },
+            }));
+    }
+
+    /// Add `other` after everything so far, on the same baseline.
+    fn append(&mut self, other: MathBox) {
+        let offset = egui::vec2(self.width, 0.0);
+        self.place(other, offset);
+    }
+
+    fn line(&mut self, points: Vec<Vec2>, width: f32) {
+        self.items.push(Item::Line { points, width });
+    }
+
+    fn paint(&self, painter: &egui::Painter, origin: Pos2, color: Color32) {
+        for item in &self.items {
+            match item {
+                Item::Text { offset, galley } => {
+                    painter.galley(origin + *offset, galley.clone(), color);
+                }
+                Item::Line { points, width } => {
+                    let points = points.iter().map(|p| origin + *p).collect();
+                    painter.add(egui::Shape::line(points, Stroke::new(*width, color)));
+                }
+                Item::Dot { center, radius } => {
+                    painter.circle_filled(origin + *center, *radius, color);
+                }
+            }
+        }
+    }
+}
+
+struct Style<'a> {
+    painter: &'a egui::Painter,
+    color: Color32,
+    /// Scripts of scripts stop shrinking here.
+    min_size: f32,
+}
+
+impl Style<'_> {
+    fn script_size(&self, size: f32) -> f32 {
+        (size * SCRIPT_SCALE).max(self.min_size)
+    }
+
+    /// Height of the math axis above the baseline, where fraction bars and
+    /// the middle of operators sit.
+    fn axis(size: f32) -> f32 {
+        size * 0.27
+    }
+
+    fn rule(size: f32) -> f32 {
+        (size * 0.045).max(1.0)
+    }
+
+    fn text(&self, text: &str, size: f32, italics: bool) -> MathBox {
+        let job = LayoutJob::single_section(
+            text.to_string(),
+            TextFormat {
+                font_id: FontId::new(size, FontFamily::Proportional),
+                color: self.color,
+                italics,
+                ..Default::default()
+            },
+        );
+        let galley = self.painter.layout_job(job);
+        let baseline = galley
+            .rows
+            .first()
+            .and_then(|row| row.row.glyphs.first().map(|g| row.pos.y + g.pos.y))
+            .unwrap_or(size * 0.8);
+        // Leave room for the slant of italic letters
+        let slant = if italics { size * 0.06 } else { 0.0 };
+        MathBox {
+            width: galley.rect.width() + slant,
+            ascent: baseline,
+            descent: galley.rect.height() - baseline,
+            items: vec![Item::Text {
+                offset: egui::vec2(0.0, -baseline),
+                galley,
+            }],
+        }
+    }
+
+    /// Lay out `node` at font `size`. `display` puts limits above and below
+    /// big operators and keeps fraction parts full size.
+    fn layout(&self, node: &MathNode, size: f32, display: bool) -> MathBox {
+        match node {
+            MathNode::Ident(s) => self.text(s, size, true),
+            MathNode::Upright(s) if s == "⋮" || s == "⋱" => self.dots(s == "⋱", size),
+            MathNode::Upright(s) => {
+                let mut b = self.text(s, size, false);
+                // Function names like `sin` get a thin space before their argument
+                if s.chars().count() > 1 && s.chars().all(char::is_alphabetic) {
+                    b.width += size * 0.17;
+                }
+                b
+            }
+            MathNode::Text(s) => self.text(s, size, false),
+            MathNode::DoubleStruck(s) => {
+                // Overstrike each letter slightly to the right for the double stroke
+                let mut b = self.text(s, size, false);
+                let strike = self.text(s, size, false);
+                b.place(strike, egui::vec2(size * 0.07, 0.0));
+                b
+            }
+            MathNode::Operator(s) => {
+                let gap = size * 0.25;
+                let mut b = MathBox::space(gap);
+                b.append(self.text(s, size, false));
+                b.width += gap;
+                b
+            }
+            MathNode::Space(em) => MathBox::space(em * size),
+            MathNode::Row(nodes) => self.row(nodes, size, display),
+            MathNode::Frac { num, den, bar } => {
+                let part = if display {
+                    size
+                } else {
+                    self.script_size(size)
+                };
+                let num = self.layout(num, part, false);
+                let den = self.layout(den, part, false);
+                self.fraction(num, den, *bar, size)
+            }
+            MathNode::Sqrt { index, body } => self.radical(index.as_deref(), body, size, display),
+            MathNode::Scripts { base, sub, sup } => {
+                self.scripts(base, sub.as_deref(), sup.as_deref(), size, display)
+            }
+            MathNode::BigOp { symbol, .. } => self.big_op(symbol, size, display),
+            MathNode::Accent { accent, body } => self.accent(*accent, body, size, display),
+            MathNode::Delimited { open, body, close } => {
+                let body = self.layout(body, size, display);
+                self.delimited(open, body, close, size)
+            }
+            MathNode::Matrix {
+                rows,
+                open,
+                close,
+                align,
+            } => {
+                let grid = self.matrix(rows, *align, size);
+                self.delimited(open, grid, close, size)
+            }
+        }
+    }
+
+    /// `\vdots`, or `\ddots` when `diagonal`.
+    fn dots(&self, diagonal: bool, size: f32) -> MathBox {
+        let radius = Self::rule(size) * 1.2;
+        let step = size * 0.28;
+        let mut b = MathBox::space(if diagonal { step * 3.0 } else { size * 0.4 });
+        b.ascent = size * 0.8;
+        for i in 0..3 {
+            let x = if diagonal {
+                step * (i as f32 + 0.5)
+            } else {
+                size * 0.2
+            };
+            let y = -size * 0.1 - step * (2 - i) as f32;
+            b.items.push(Item::Dot {
+                center: egui::vec2(x, y),
+                radius,
+            });
+        }
+        b
+    }
+
+    fn row(&self, nodes: &[MathNode], size: f32, display: bool) -> MathBox {
+        let mut b = MathBox::default();
+        for (i, node) in nodes.iter().enumerate() {
+            // A leading minus, or one after another operator, is a sign
+            let unary = matches!(node, MathNode::Operator(_))
+                && (i == 0 || matches!(nodes[i - 1], MathNode::Operator(_)));
+            if unary {
+                let MathNode::Operator(s) = node else {
+                    unreachable!()
+                };
+                b.append(self.text(s, size, false));
+            } else {
+                b.append(self.layout(node, size, display));
+            }
+        }
+        b
+    }
+
+    fn fraction(&self, num: MathBox, den: MathBox, bar: bool, size: f32) -> MathBox {
+        let axis = Self::axis(size);
+        let rule = Self::rule(size);
+        let gap = size * 0.1;
+        let pad = size * 0.12;
+        let width = num.width.max(den.width) + pad * 2.0;
+
+        let mut b = MathBox::space(width);
+        let num_y = -(axis + rule / 2.0 + gap + num.descent);
+        let den_y = -axis + rule / 2.0 + gap + den.ascent;
+        let (num_w, den_w) = (num.width, den.width);
+        b.place(num, egui::vec2((width - num_w) / 2.0, num_y));
+        b.place(den, egui::vec2((width - den_w) / 2.0, den_y));
+        if bar {
+            b.line(
+                vec![
+                    egui::vec2(pad * 0.5, -axis),
+                    egui::vec2(width - pad * 0.5, -axis),
+                ],
+                rule,
+            );
+        }
+        b
+    }
+
+    fn radical(
+        &self,
+        index: Option<&MathNode>,
+        body: &MathNode,
+        size: f32,
+        display: bool,
+    ) -> MathBox {
+        let body = self.layout(body, size, display);
+        let rule = Self::rule(size);
+        let gap = size * 0.08;
+        let top = -(body.ascent + gap + rule);
+        let bottom = body.descent;
+        let tick = size * 0.55;
+        let mid = bottom - (bottom - top) * 0.45;
+
+        let mut b = MathBox::default();
+        // The index sits in the crook of the sign
+        let mut x = 0.0;
+        if let Some(index) = index {
+            let index = self.layout(index, self.script_size(self.script_size(size)), false);
+            let crook = tick * 0.4;
+            x = (index.width - crook).max(0.0);
+            let y = mid - size * 0.08 - index.descent;
+            let index_width = index.width;
+            b.place(index, egui::vec2(x + crook - index_width, y));
+        }
+        let body_width = body.width;
+        b.line(
+            vec![
+                egui::vec2(x, mid + size * 0.04),
+                egui::vec2(x + tick * 0.2, mid - size * 0.04),
+                egui::vec2(x + tick * 0.5, bottom),
+                egui::vec2(x + tick, top),
+                egui::vec2(x + tick + body_width + size * 0.1, top),
+            ],
+            rule,
+        );
+        b.place(body, egui::vec2(x + tick + size * 0.05, 0.0));
+        b.ascent = b.ascent.max(-top + rule);
+        b.width += size * 0.1;
+        b
+    }
+
+    fn scripts(
+        &self,
+        base: &MathNode,
+        sub: Option<&MathNode>,
+        sup: Option<&MathNode>,
+        size: f32,
+        display: bool,
+    ) -> MathBox {
+        let script = self.script_size(size);
+        let sub = sub.map(|n| self.layout(n, script, false));
+        let sup = sup.map(|n| self.layout(n, script, false));
+
+        if let MathNode::BigOp { limits: true, .. } = base {
+            if display {
+                return self.limits(self.layout(base, size, display), sub, sup, size);
+            }
+        }
+
+        let mut b = self.layout(base, size, display);
+        let x = b.width;
+        let mut width = 0.0_f32;
+        if let Some(sup) = sup {
+            let y = -(b.ascent - size * 0.45).max(size * 0.38);
+            width = width.max(sup.width);
+            b.place(sup, egui::vec2(x, y));
+        }
+        if let Some(sub) = sub {
+            let y = (b.descent + size * 0.05).max(size * 0.22);
+            width = width.max(sub.width);
+            b.place(sub, egui::vec2(x, y));
+        }
+        b.width = x + width + size * 0.05;
+        b
+    }
+
+    /// Limits stacked above and below a big operator.
+    fn limits(
+        &self,
+        op: MathBox,
+        sub: Option<MathBox>,
+        sup: Option<MathBox>,
+        size: f32,
+    ) -> MathBox {
+        let gap = size * 0.1;
+        let width = [Some(&op), sub.as_ref(), sup.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|b| b.width)
+            .fold(0.0, f32::max);
+        let (op_ascent, op_descent) = (op.ascent, op.descent);
+        let mut b = MathBox::default();
+        let op_width = op.width;
+        b.place(op, egui::vec2((width - op_width) / 2.0, 0.0));
+        if let Some(sup) = sup {
+            let y = -(op_ascent + gap + sup.descent);
+            let w = sup.width;
+            b.place(sup, egui::vec2((width - w) / 2.0, y));
+        }
+        if let Some(sub) = sub {
+            let y = op_descent + gap + sub.ascent;
+            let w = sub.width;
+            b.place(sub, egui::vec2((width - w) / 2.0, y));
+        }
+        b.width = width + size * 0.15;
+        b
+    }
+
+    fn big_op(&self, symbol: &str, size: f32, display: bool) -> MathBox {
+        // Words like `lim` and `max` stay text size
+        if symbol.chars().count() > 1 {
+            let mut b = self.text(symbol, size, false);
+            b.width += size * 0.17;
+            return b;
+        }
+        let op_size = if display { size * 1.6 } else { size * 1.15 };
+        let glyph = self.text(symbol, op_size, false);
+        // Center the symbol on the math axis
+        let center = (glyph.descent - glyph.ascent) / 2.0;
+        let y = -Self::axis(size) - center;
+        let mut b = MathBox::space(size * 0.08);
+        let x = b.width;
+        let width = glyph.width;
+        b.place(glyph, egui::vec2(x, y));
+        b.width = x + width + size * 0.12;
+        b
+    }
+
+    fn accent(&self, accent: Accent, body: &MathNode, size: f32, display: bool) -> MathBox {
+        let mut b = self.layout(body, size, display);
+        let rule = Self::rule(size);
+        let y = -(b.ascent - size * 0.16);
+        let center = b.width / 2.0;
+        let half = (b.width / 2.0).clamp(size * 0.15, size * 0.3);
+        let (left, right) = match accent {
+            Accent::Bar | Accent::Vec => (size * 0.05, b.width - size * 0.05),
+            _ => (center - half, center + half),
+        };
+        match accent {
+            Accent::Hat => b.line(
+                vec![
+                    egui::vec2(left, y),
+                    egui::vec2(center, y - size * 0.12),
+                    egui::vec2(right, y),
+                ],
+                rule,
+            ),
+            Accent::Bar => b.line(vec![egui::vec2(left, y), egui::vec2(right, y)], rule),
+            Accent::Vec => {
+                let head = size * 0.08;
+                b.line(vec![egui::vec2(left, y), egui::vec2(right, y)], rule);
+                b.line(
+                    vec![
+                        egui::vec2(right - head, y - head),
+                        egui::vec2(right, y),
+                        egui::vec2(right - head, y + head),
+                    ],
+                    rule,
+                );
+            }
+            Accent::Dot => b.items.push(Item::Dot {
+                center: egui::vec2(center, y - size * 0.04),
+                radius: rule * 1.3,
+            }),
+            Accent::Tilde => {
+                let points = (0..=12)
+                    .map(|i| {
+                        let t = i as f32 / 12.0;
+                        let wave = (t * std::f32::consts::TAU).sin() * size * 0.04;
+                        egui::vec2(left + (right - left) * t, y - size * 0.04 - wave)
+                    })
+                    .collect();
+                b.line(points, rule);
+            }
+        }
+        b.ascent += size * 0.08;
+        b
+    }
+
+    /// `body` between delimiters drawn to its full height, centered on the axis.
+    fn delimited(&self, open: &str, body: MathBox, close: &str, size: f32) -> MathBox {
+        let axis = Self::axis(size);
+        let half = (body.ascent - axis)
+            .max(body.descent + axis)
+            .max(size * 0.55);
+        let (top, bottom) = (-axis - half, -axis + half);
+
+        let mut b = MathBox::default();
+        let open = delimiter(open, top, bottom, size, false);
+        b.append(open);
+        b.append(body);
+        b.append(delimiter(close, top, bottom, size, true));
+        b
+    }
+
+    fn matrix(&self, rows: &[Vec<MathNode>], align: ColumnAlign, size: f32) -> MathBox {
+        let cells: Vec<Vec<MathBox>> = rows
+            .iter()
+            .map(|row| row.iter().map(|n| self.layout(n, size, false)).collect())
+            .collect();
+        let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0.0_f32; columns];
+        for row in &cells {
+            for (c, cell) in row.iter().enumerate() {
+                widths[c] = widths[c].max(cell.width);
+            }
+        }
+        let row_gap = size * 0.3;
+        let column_gap = |c: usize| match align {
+            // Pairs of columns meet at the `&`
+            ColumnAlign::Alternate if c % 2 == 1 => 0.0,
+            ColumnAlign::Alternate => size * 1.0,
+            ColumnAlign::Left => size * 1.0,
+            ColumnAlign::Center => size * 0.8,
+        };
+        let pad = size * 0.15;
+
+        let heights: Vec<(f32, f32)> = cells
+            .iter()
+            .map(|row| {
+                row.iter().fold((size * 0.7, size * 0.25), |(a, d), cell| {
+                    (a.max(cell.ascent), d.max(cell.descent))
+                })
+            })
+            .collect();
+        let total: f32 = heights.iter().map(|(a, d)| a + d).sum::<f32>()
+            + row_gap * rows.len().saturating_sub(1) as f32;
+
+        let mut b = MathBox::default();
+        let mut y = -Self::axis(size) - total / 2.0;
+        for (row, (ascent, descent)) in cells.into_iter().zip(heights) {
+            y += ascent;
+            let mut x = pad;
+            for (c, cell) in row.into_iter().enumerate() {
+                let free = widths[c] - cell.width;
+                let shift = match align {
+                    ColumnAlign::Center => free / 2.0,
+                    ColumnAlign::Left => 0.0,
+                    ColumnAlign::Alternate if c % 2 == 0 => free,
+                    ColumnAlign::Alternate => 0.0,
+                };
+                b.place(cell, egui::vec2(x + shift, y));
+                x += widths[c] + column_gap(c + 1);
+            }
+            y += descent + row_gap;
+        }
+        b.width =
+            pad * 2.0 + widths.iter().sum::<f32>() + (1..columns).map(column_gap).sum::<f32>();
+        b
+    }
+}
+
+/// A delimiter drawn from `top` to `bottom`. `closing` mirrors `(`, `[` and
+/// friends into `)`, `]`.
+fn delimiter(symbol: &str, top: f32, bottom: f32, size: f32, closing: bool) -> MathBox {
+    let rule = Style::rule(size) * 1.2;
+    let height = bottom - top;
+    let width = size * 0.35;
+    let mut b = MathBox::space(width);
+    b.ascent = -top;
+    b.descent = bottom;
+    // Coordinates for an opening delimiter, with x = 0 at its outer edge
+    let (near, far) = (size * 0.1, width - size * 0.08);
+    let strokes: Vec<Vec<(f32, f32)>> = match symbol {
+        "" => return MathBox::default(),
+        "(" | ")" => vec![
+            (0..=16)
+                .map(|i| {
+                    let t = i as f32 / 16.0;
+                    let bulge = (t * std::f32::consts::PI).sin();
+                    (far - (far - near) * bulge, top + height * t)
+                })
+                .collect(),
+        ],
+        "[" | "]" => vec![vec![(far, top), (near, top), (near, bottom), (far, bottom)]],
+        "⌊" | "⌋" => vec![vec![(near, top), (near, bottom), (far, bottom)]],
+        "⌈" | "⌉" => vec![vec![(far, top), (near, top), (near, bottom)]],
+        "⟨" | "⟩" => vec![vec![(far, top), (near, top + height / 2.0), (far, bottom)]],
+        "{" | "}" => {
+            let mid = top + height / 2.0;
+            let x = (near + far) / 2.0;
+            let curl = size * 0.12;
+            vec![vec![
+                (far, top),
+                (x, top + curl),
+                (x, mid - curl),
+                (near, mid),
+                (x, mid + curl),
+                (x, bottom - curl),
+                (far, bottom),
+            ]]
+        }
+        "|" => vec![vec![(width / 2.0, top), (width / 2.0, bottom)]],
+        "‖" => {
+            let (a, b) = (width * 0.35, width * 0.65);
+            vec![vec![(a, top), (a, bottom)], vec![(b, top), (b, bottom)]]
+        }
+        // Arrows and the like only keep their space
+        _ => return MathBox::space(width),
+    };
+    let mirror = closing && !matches!(symbol, "|" | "‖");
+    for points in strokes {
+        let points = points
+            .into_iter()
+            .map(|(x, y)| egui::vec2(if mirror { width - x } else { x }, y))
+            .collect();
+        b.line(points, rule);
+    }
+    b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_grows_the_box() {
+        let mut b = MathBox::space(10.0);
+        b.ascent = 5.0;
+        let mut other = MathBox::space(4.0);
+        other.ascent = 3.0;
+        other.descent = 2.0;
+        b.place(other, egui::vec2(8.0, -6.0));
+        assert_eq!((b.width, b.ascent, b.descent), (12.0, 9.0, 0.0));
+        b.place(MathBox::space(0.0), egui::vec2(0.0, 4.0));
+        assert_eq!(b.descent, 4.0);
+    }
+
+    #[test]
+    fn delimiters_span_the_requested_height() {
+        let paren = delimiter("(", -30.0, 10.0, 20.0, false);
+        assert_eq!((paren.ascent, paren.descent), (30.0, 10.0));
+        assert_eq!(paren.items.len(), 1);
+        assert_eq!(delimiter("", -30.0, 10.0, 20.0, false).width, 0.0);
+        assert_eq!(delimiter("‖", -30.0, 10.0, 20.0, true).items.len(), 2);
+    }
+}
@@ -1,16 +1,43 @@
+pub mod aspect;
+pub mod background;
+pub mod diagram;
+pub mod graph_layout;
+pub mod icons;
 pub mod image_cache;
 pub mod layouts;
+pub mod links;
+pub mod matches;
+pub mod math;
+pub mod morph;
+pub mod sequence;
 pub mod syntax;
 pub mod text;
 pub mod transition;
 
+use std::borrow::Cow;
+
 use eframe::egui;
 
-use crate::parser::{Layout, Slide};
+use crate::parser::{Layout, PresentationMeta, Slide};
 use crate::theme::Theme;
 
 use image_cache::ImageCache;
 
+/// The theme a slide is drawn with. `base` is the presentation theme (which
+/// the presenter can toggle); slides whose `@theme` or `@code-theme` differ
+/// from the frontmatter get their own.
+pub fn slide_theme<'a>(slide: &Slide, meta: &PresentationMeta, base: &'a Theme) -> Cow<'a, Theme> {
+    let settings = &slide.settings;
+    if settings.theme != meta.theme {
+        let name = settings.theme.as_deref().unwrap_or("light");
+        Cow::Owned(Theme::from_name(name).with_code_theme(settings.code_theme.as_deref()))
+    } else if settings.code_theme != meta.code_theme {
+        Cow::Owned(base.clone().with_code_theme(settings.code_theme.as_deref()))
+    } else {
+        Cow::Borrowed(base)
+    }
+}
+
 /// Estimate the total content height of a slide (for scroll/overflow detection).
 /// Returns (content_height, available_height) where available_height is the usable
 /// area within the slide rect after padding.
@@ -123,3 +150,18 @@ pub fn render_slide(
         ),
     }
 }
+
+/// Draw the presentation footer centered along the bottom edge of a slide.
+pub fn draw_footer(ui: &egui::Ui, footer: &str, theme: &Theme, rect: egui::Rect, scale: f32) {
+    let footer_color = Theme::with_opacity(theme.foreground, 0.4);
+    let galley = ui.painter().layout_no_wrap(
+        footer.to_string(),
+        egui::FontId::proportional(14.0 * scale),
+        footer_color,
+    );
+    let pos = egui::pos2(
+        rect.center().x - galley.rect.width() / 2.0,
+        rect.bottom() - 30.0 * scale,
+    );
+    ui.painter().galley(pos, galley, footer_color);
+}
@@ -0,0 +1,373 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use eframe::egui::{self, Color32, Galley, Pos2, Rect, Shape, Vec2};
+use egui::text::CCursor;
+
+use crate::theme::Theme;
+
+/// A code block laid out while a morph transition is drawn, in place of
+/// painting it.
+#[derive(Clone)]
+pub struct CodeCapture {
+    /// The highlighted code at full opacity.
+    pub galley: Arc<Galley>,
+    pub pos: Pos2,
+    /// Line numbers and diff tints, bars and signs at full opacity.
+    pub gutter: Vec<Shape>,
+    pub background: Rect,
+    pub background_color: Color32,
+    pub rounding: f32,
+}
+
+fn capturing_id() -> egui::Id {
+    egui::Id::new("mdeck-morph-capturing")
+}
+
+fn captures_id() -> egui::Id {
+    egui::Id::new("mdeck-morph-captures")
+}
+
+/// Start or stop capturing code blocks instead of painting them.
+pub fn set_capturing(ctx: &egui::Context, on: bool) {
+    ctx.data_mut(|d| d.insert_temp(capturing_id(), on));
+}
+
+/// Whether code blocks should be handed to [`capture`] rather than painted.
+pub fn is_capturing(ctx: &egui::Context) -> bool {
+    ctx.data(|d| d.get_temp::<bool>(capturing_id()).unwrap_or(false))
+}
+
+pub fn capture(ctx: &egui::Context, code: CodeCapture) {
+    ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<Vec<CodeCapture>>(captures_id())
+            .push(code)
+    });
+}
+
+/// Code blocks captured since the last call, in drawing order.
+pub fn take(ctx: &egui::Context) -> Vec<CodeCapture> {
+    ctx.data_mut(|d| std::mem::take(d.get_temp_mut_or_default::<Vec<CodeCapture>>(captures_id())))
+}
+
+fn plan_id() -> egui::Id {
+    egui::Id::new("mdeck-morph-plan")
+}
+
+/// Draw the code blocks of two slides part way through a morph. Blocks are
+/// paired in order; within a pair, tokens both versions share glide from
+/// their old place to their new one, removed tokens fade out over the first
+/// half and added ones fade in over the second. Gutters ride along with
+/// the background and cross-fade. Unpaired blocks fade.
+/// `slides` is the pair of slides being morphed between; the tokens are
+/// matched once for it and reused for the rest of the transition.
+pub fn draw(
+    ui: &egui::Ui,
+    slides: (usize, usize),
+    from: &[CodeCapture],
+    to: &[CodeCapture],
+    progress: f32,
+) {
+    let plan = plan(ui, slides, from, to);
+    let painter = ui.painter();
+    let fade_out = (1.0 - progress * 2.0).max(0.0);
+    let fade_in = (progress * 2.0 - 1.0).max(0.0);
+
+    for (i, block) in plan.blocks.iter().enumerate() {
+        match (from.get(i), to.get(i)) {
+            (Some(a), Some(b)) => {
+                let background = lerp_rect(a.background, b.background, progress);
+                let color = a
+                    .background_color
+                    .lerp_to_gamma(b.background_color, progress);
+                let rounding = a.rounding + (b.rounding - a.rounding) * progress;
+                painter.rect_filled(background, rounding, color);
+                paint_gutter(ui, a, background.min - a.background.min, 1.0 - progress);
+                paint_gutter(ui, b, background.min - b.background.min, progress);
+
+                for &(o, n) in &block.pairs {
+                    let (old, new) = (&block.old[o], &block.new[n]);
+                    let start = a.pos + old.offset;
+                    let pos = start + (b.pos + new.offset - start) * progress;
+                    let color = old.color.lerp_to_gamma(new.color, progress);
+                    paint_token(ui, new, pos, color);
+                }
+                for token in block.old.iter().filter(|t| !t.kept) {
+                    let color = Theme::with_opacity(token.color, fade_out);
+                    paint_token(ui, token, a.pos + token.offset, color);
+                }
+                for token in block.new.iter().filter(|t| !t.kept) {
+                    let color = Theme::with_opacity(token.color, fade_in);
+                    paint_token(ui, token, b.pos + token.offset, color);
+                }
+            }
+            (Some(only), None) => draw_faded(ui, only, &block.old, 1.0 - progress),
+            (None, Some(only)) => draw_faded(ui, only, &block.new, progress),
+            (None, None) => {}
+        }
+    }
+}
+
+fn draw_faded(ui: &egui::Ui, code: &CodeCapture, tokens: &[Token], opacity: f32) {
+    ui.painter().rect_filled(
+        code.background,
+        code.rounding,
+        Theme::with_opacity(code.background_color, opacity),
+    );
+    paint_gutter(ui, code, Vec2::ZERO, opacity);
+    for token in tokens {
+        let color = Theme::with_opacity(token.color, opacity);
+        paint_token(ui, token, code.pos + token.offset, color);
+    }
+}
+
+fn paint_gutter(ui: &egui::Ui, code: &CodeCapture, offset: Vec2, opacity: f32) {
+    for shape in &code.gutter {
+        let mut shape = shape.clone();
+        shape.translate(offset);
+        match &mut shape {
+            Shape::Rect(rect) => rect.fill = rect.fill.gamma_multiply(opacity),
+            Shape::Text(text) => text.opacity_factor *= opacity,
+            _ => {}
+        }
+        ui.painter().add(shape);
+    }
+}
+
+/// The tokens of both slides' code blocks and which of them correspond,
+/// worked out when a morph starts.
+struct MorphPlan {
+    slides: (usize, usize),
+    /// The layouts the plan was made from, to notice when they change
+    from: Vec<Arc<Galley>>,
+    to: Vec<Arc<Galley>>,
+    blocks: Vec<BlockPlan>,
+}
+
+/// Tokens of the blocks at one position on the two slides. One side is
+/// empty when only one slide has a block there.
+struct BlockPlan {
+    old: Vec<Token>,
+    new: Vec<Token>,
+    /// Index pairs of the tokens that glide from `old` to `new`
+    pairs: Vec<(usize, usize)>,
+}
+
+impl MorphPlan {
+    fn new(
+        ui: &egui::Ui,
+        slides: (usize, usize),
+        from: &[CodeCapture],
+        to: &[CodeCapture],
+    ) -> Self {
+        let blocks = (0..from.len().max(to.len()))
+            .map(|i| {
+                let mut old = from.get(i).map(|c| tokens(ui, c)).unwrap_or_default();
+                let mut new = to.get(i).map(|c| tokens(ui, c)).unwrap_or_default();
+                let old_text: Vec<&str> = old.iter().map(|t| t.text.as_str()).collect();
+                let new_text: Vec<&str> = new.iter().map(|t| t.text.as_str()).collect();
+                let pairs = common_tokens(&old_text, &new_text);
+                for &(o, n) in &pairs {
+                    old[o].kept = true;
+                    new[n].kept = true;
+                }
+                BlockPlan { old, new, pairs }
+            })
+            .collect();
+        let galleys = |codes: &[CodeCapture]| codes.iter().map(|c| c.galley.clone()).collect();
+        Self {
+            slides,
+            from: galleys(from),
+            to: galleys(to),
+            blocks,
+        }
+    }
+
+    /// Whether this plan was made for these slides and layouts. egui hands
+    /// out the same galley while the code and its font stay the same.
+    fn matches(&self, slides: (usize, usize), from: &[CodeCapture], to: &[CodeCapture]) -> bool {
+        let same = |galleys: &[Arc<Galley>], codes: &[CodeCapture]| {
+            galleys.len() == codes.len()
+                && galleys
+                    .iter()
+                    .zip(codes)
+                    .all(|(g, c)| Arc::ptr_eq(g, &c.galley))
+        };
+        self.slides == slides && same(&self.from, from) && same(&self.to, to)
+    }
+}
+
+/// The plan for this morph, made on its first frame and kept in the
+/// context's temp data after that.
+fn plan(
+    ui: &egui::Ui,
+    slides: (usize, usize),
+    from: &[CodeCapture],
+    to: &[CodeCapture],
+) -> Arc<MorphPlan> {
+    let ctx = ui.ctx();
+    if let Some(plan) = ctx
+        .data(|d| d.get_temp::<Arc<MorphPlan>>(plan_id()))
+        .filter(|plan| plan.matches(slides, from, to))
+    {
+        return plan;
+    }
+    let plan = Arc::new(MorphPlan::new(ui, slides, from, to));
+    ctx.data_mut(|d| d.insert_temp(plan_id(), plan.clone()));
+    plan
+}
+
+/// A word, number or punctuation mark of a code block, laid out on its own,
+/// with where it sits relative to the block's code.
+struct Token {
+    text: String,
+    offset: Vec2,
+    galley: Arc<Galley>,
+    color: Color32,
+    /// Whether the other slide has this token too
+    kept: bool,
+}
+
+fn tokens(ui: &egui::Ui, code: &CodeCapture) -> Vec<Token> {
+    let galley = &code.galley;
+    let text = galley.text();
+    let sections = &galley.job.sections;
+    split_tokens(text)
+        .into_iter()
+        .filter_map(|range| {
+            let chars = text[..range.start].chars().count();
+            let rect = galley.pos_from_cursor(CCursor::new(chars));
+            let section = sections
+                .iter()
+                .find(|s| s.byte_range.contains(&range.start))?;
+            let color = section.format.color;
+            let text = text[range].to_string();
+            let token_galley =
+                ui.painter()
+                    .layout_no_wrap(text.clone(), section.format.font_id.clone(), color);
+            Some(Token {
+                text,
+                offset: rect.min.to_vec2(),
+                galley: token_galley,
+                color,
+                kept: false,
+            })
+        })
+        .collect()
+}
+
+fn paint_token(ui: &egui::Ui, token: &Token, pos: Pos2, color: Color32) {
+    if color.a() == 0 {
+        return;
+    }
+    ui.painter()
+        .galley_with_override_text_color(pos, token.galley.clone(), color);
+}
+
+/// Byte ranges of the tokens in `text`: runs of letters, digits and `_`, and
+/// single other characters. Whitespace separates tokens and is dropped.
+fn split_tokens(text: &str) -> Vec<Range<usize>> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut word: Option<usize> = None;
+    for (i, c) in text.char_indices() {
+        if is_word(c) {
+            word.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word.take() {
+            ranges.push(start..i);
+        }
+        if !c.is_whitespace() {
+            ranges.push(i..i + c.len_utf8());
+        }
+    }
+    if let Some(start) = word {
+        ranges.push(start..text.len());
+    }
+    ranges
+}
+
+/// Index pairs of the longest common subsequence of two token lists.
+fn common_tokens(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    // lengths[i][j]: LCS length of a[i..] and b[j..]
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+fn lerp_rect(a: Rect, b: Rect, t: f32) -> Rect {
+    Rect::from_min_max(a.min + (b.min - a.min) * t, a.max + (b.max - a.max) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_split_on_words_and_punctuation() {
+        let text = "let x_1 = f(a);";
+        let tokens: Vec<&str> = split_tokens(text).into_iter().map(|r| &text[r]).collect();
+        assert_eq!(tokens, ["let", "x_1", "=", "f", "(", "a", ")", ";"]);
+    }
+
+    #[test]
+    fn common_tokens_keep_order() {
+        let a = ["let", "x", "=", "1", ";"];
+        let b = ["let", "mut", "x", "=", "2", ";"];
+        assert_eq!(common_tokens(&a, &b), [(0, 0), (1, 2), (2, 3), (4, 5)]);
+        assert!(common_tokens(&a, &[]).is_empty());
+    }
+
+    #[test]
+    fn plan_is_made_once_per_pair_of_slides() {
+        let ctx = egui::Context::default();
+        let _ = ctx.run(Default::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let capture = |text: &str| CodeCapture {
+                    galley: ui.painter().layout_no_wrap(
+                        text.to_string(),
+                        egui::FontId::monospace(12.0),
+                        Color32::WHITE,
+                    ),
+                    pos: Pos2::ZERO,
+                    gutter: Vec::new(),
+                    background: Rect::ZERO,
+                    background_color: Color32::BLACK,
+                    rounding: 0.0,
+                };
+                let from = [capture("let x = 1;")];
+                let to = [capture("let mut x = 2;"), capture("done();")];
+
+                let first = plan(ui, (0, 1), &from, &to);
+                assert_eq!(first.blocks.len(), 2);
+                assert_eq!(first.blocks[0].pairs, [(0, 0), (1, 2), (2, 3), (4, 5)]);
+                assert!(first.blocks[1].old.is_empty());
+                assert!(Arc::ptr_eq(&first, &plan(ui, (0, 1), &from, &to)));
+                assert!(!Arc::ptr_eq(&first, &plan(ui, (1, 2), &from, &to)));
+            });
+        });
+    }
+}
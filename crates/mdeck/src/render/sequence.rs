@@ -0,0 +1,306 @@
+use eframe::egui::{self, Color32, FontId, Pos2, Rect, Stroke};
+
+use crate::parser::diagram::Arrow;
+use crate::parser::mermaid::{NoteSide, Participant, SequenceDiagram, SequenceEvent};
+use crate::theme::Theme;
+
+use super::diagram::draw_arrow_head;
+use super::icons;
+
+const PADDING_TOP: f32 = 20.0;
+const PADDING_BOTTOM: f32 = 30.0;
+const HEADER_HEIGHT: f32 = 64.0;
+const BOX_HEIGHT: f32 = 48.0;
+const ROW_HEIGHT: f32 = 56.0;
+/// Messages to self loop back, so they take a taller row.
+const SELF_ROW_HEIGHT: f32 = 80.0;
+const SELF_LOOP_WIDTH: f32 = 40.0;
+
+/// Height of a sequence diagram, matching what [`draw`] uses.
+pub fn measure_height(sequence: &SequenceDiagram, scale: f32) -> f32 {
+    let rows: f32 = sequence.events.iter().map(row_height).sum();
+    (PADDING_TOP + HEADER_HEIGHT + rows + PADDING_BOTTOM) * scale
+}
+
+fn row_height(event: &SequenceEvent) -> f32 {
+    match event {
+        SequenceEvent::Message { from, to, .. } if from == to => SELF_ROW_HEIGHT,
+        _ => ROW_HEIGHT,
+    }
+}
+
+/// Draw participants across the top with dashed lifelines below them, and
+/// one row per message or note in the order they were written.
+pub fn draw(
+    ui: &egui::Ui,
+    sequence: &SequenceDiagram,
+    theme: &Theme,
+    pos: Pos2,
+    max_width: f32,
+    opacity: f32,
+    scale: f32,
+) -> f32 {
+    let painter = ui.painter();
+    let height = measure_height(sequence, scale);
+
+    let columns = sequence.participants.len().max(1);
+    let column_width = (max_width / columns as f32).min(320.0 * scale);
+    let left = pos.x + (max_width - column_width * columns as f32) / 2.0;
+    let column_x = |i: usize| left + (i as f32 + 0.5) * column_width;
+    let box_width = (column_width * 0.8).min(220.0 * scale);
+
+    let header_top = pos.y + PADDING_TOP * scale;
+    let header_bottom = header_top + HEADER_HEIGHT * scale;
+    let bottom = pos.y + height - PADDING_BOTTOM * scale;
+
+    let lifeline = Stroke::new(
+        1.5 * scale,
+        Theme::with_opacity(theme.foreground, opacity * 0.3),
+    );
+    for (i, participant) in sequence.participants.iter().enumerate() {
+        let x = column_x(i);
+        painter.extend(egui::Shape::dashed_line(
+            &[egui::pos2(x, header_bottom), egui::pos2(x, bottom)],
+            lifeline,
+            6.0 * scale,
+            5.0 * scale,
+        ));
+        let rect = Rect::from_center_size(
+            egui::pos2(x, header_top + HEADER_HEIGHT * scale / 2.0),
+            egui::vec2(box_width, BOX_HEIGHT * scale),
+        );
+        draw_participant(ui, participant, rect, theme, opacity, scale);
+    }
+
+    let mut y = header_bottom;
+    for event in &sequence.events {
+        let row =
+            Rect::from_x_y_ranges(pos.x..=pos.x + max_width, y..=y + row_height(event) * scale);
+        match event {
+            SequenceEvent::Message {
+                from,
+                to,
+                label,
+                arrow,
+            } => {
+                if let (Some(from), Some(to)) = (sequence.column(from), sequence.column(to)) {
+                    let ends = (column_x(from), column_x(to));
+                    draw_message(ui, ends, row, label, *arrow, theme, opacity, scale);
+                }
+            }
+            SequenceEvent::Note {
+                side,
+                first,
+                last,
+                text,
+            } => {
+                if let (Some(first), Some(last)) = (sequence.column(first), sequence.column(last)) {
+                    let span = (column_x(first.min(last)), column_x(first.max(last)));
+                    let reach = box_width / 2.0;
+                    draw_note(ui, *side, span, reach, row, text, theme, opacity, scale);
+                }
+            }
+        }
+        y = row.bottom();
+    }
+
+    height
+}
+
+/// A participant box with its label, or a person icon over the label for actors.
+fn draw_participant(
+    ui: &egui::Ui,
+    participant: &Participant,
+    rect: Rect,
+    theme: &Theme,
+    opacity: f32,
+    scale: f32,
+) {
+    let painter = ui.painter();
+    let font_size = theme.body_size * 0.6 * scale;
+
+    if participant.actor {
+        let color = Theme::with_opacity(theme.foreground, opacity);
+        let icon_size = 32.0 * scale;
+        let icon_rect = Rect::from_center_size(
+            egui::pos2(rect.center().x, rect.top() + icon_size / 2.0 - 4.0 * scale),
+            egui::vec2(icon_size, icon_size),
+        );
+        icons::draw(painter, "user", icon_rect, color);
+        let galley = painter.layout_no_wrap(
+            participant.label.clone(),
+            FontId::proportional(font_size),
+            color,
+        );
+        let text_pos = egui::pos2(
+            rect.center().x - galley.rect.width() / 2.0,
+            icon_rect.bottom() + 2.0 * scale,
+        );
+        painter.galley(text_pos, galley, color);
+        return;
+    }
+
+    let text_color = Theme::with_opacity(Color32::WHITE, opacity);
+    painter.rect_filled(
+        rect,
+        8.0 * scale,
+        Theme::with_opacity(theme.accent, opacity * 0.9),
+    );
+    let galley = painter.layout(
+        participant.label.clone(),
+        FontId::proportional(font_size),
+        text_color,
+        rect.width() - 16.0 * scale,
+    );
+    painter.galley(rect.center() - galley.rect.size() / 2.0, galley, text_color);
+}
+
+/// A horizontal arrow between two lifelines with its label above, or a loop
+/// back to the same lifeline with the label beside it.
+#[allow(clippy::too_many_arguments)]
+fn draw_message(
+    ui: &egui::Ui,
+    (from_x, to_x): (f32, f32),
+    row: Rect,
+    label: &str,
+    arrow: Arrow,
+    theme: &Theme,
+    opacity: f32,
+    scale: f32,
+) {
+    let painter = ui.painter();
+    let color = Theme::with_opacity(theme.accent, opacity * 0.7);
+    let stroke = Stroke::new(2.5 * scale, color);
+    let arrow_size = 10.0 * scale;
+    let label_color = Theme::with_opacity(theme.foreground, opacity * 0.8);
+    let galley = painter.layout_no_wrap(
+        label.to_string(),
+        FontId::proportional(theme.body_size * 0.5 * scale),
+        label_color,
+    );
+
+    let (points, label_pos) = if from_x == to_x {
+        let loop_width = SELF_LOOP_WIDTH * scale;
+        let (top, bottom) = (
+            row.top() + row.height() * 0.35,
+            row.top() + row.height() * 0.75,
+        );
+        let points = vec![
+            egui::pos2(from_x, top),
+            egui::pos2(from_x + loop_width, top),
+            egui::pos2(from_x + loop_width, bottom),
+            egui::pos2(from_x, bottom),
+        ];
+        let label_pos = egui::pos2(
+            from_x + loop_width + 8.0 * scale,
+            (top + bottom - galley.rect.height()) / 2.0,
+        );
+        (points, label_pos)
+    } else {
+        let y = row.top() + row.height() * 0.65;
+        let label_pos = egui::pos2(
+            (from_x + to_x - galley.rect.width()) / 2.0,
+            y - galley.rect.height() - 4.0 * scale,
+        );
+        (vec![egui::pos2(from_x, y), egui::pos2(to_x, y)], label_pos)
+    };
+
+    if arrow.is_dashed() {
+        painter.extend(egui::Shape::dashed_line(
+            &points,
+            stroke,
+            8.0 * scale,
+            6.0 * scale,
+        ));
+    } else {
+        painter.add(egui::Shape::line(points.clone(), stroke));
+    }
+    let last = points.len() - 1;
+    if arrow.head_at_end() {
+        draw_arrow_head(ui, points[last], points[last - 1], arrow_size, color);
+    }
+    if arrow.head_at_start() {
+        draw_arrow_head(ui, points[0], points[1], arrow_size, color);
+    }
+    painter.galley(label_pos, galley, label_color);
+}
+
+/// A note box beside one lifeline, or over the lifelines from `span.0` to
+/// `span.1`, reaching `reach` past them.
+#[allow(clippy::too_many_arguments)]
+fn draw_note(
+    ui: &egui::Ui,
+    side: NoteSide,
+    span: (f32, f32),
+    reach: f32,
+    row: Rect,
+    text: &str,
+    theme: &Theme,
+    opacity: f32,
+    scale: f32,
+) {
+    let painter = ui.painter();
+    let padding = 8.0 * scale;
+    let text_color = Theme::with_opacity(theme.foreground, opacity);
+    let galley = painter.layout_no_wrap(
+        text.to_string(),
+        FontId::proportional(theme.body_size * 0.5 * scale),
+        text_color,
+    );
+    let size = galley.rect.size() + egui::vec2(padding * 2.0, padding * 2.0);
+    let gap = 12.0 * scale;
+
+    let x_range = match side {
+        NoteSide::Right => span.1 + gap..=span.1 + gap + size.x,
+        NoteSide::Left => span.0 - gap - size.x..=span.0 - gap,
+        NoteSide::Over => {
+            let width = size.x.max(span.1 - span.0 + reach);
+            let center = (span.0 + span.1) / 2.0;
+            center - width / 2.0..=center + width / 2.0
+        }
+    };
+    let top = row.center().y - size.y / 2.0;
+    let rect = Rect::from_x_y_ranges(x_range, top..=top + size.y);
+
+    painter.rect_filled(
+        rect,
+        6.0 * scale,
+        Theme::with_opacity(theme.code_background, opacity),
+    );
+    painter.rect_stroke(
+        rect,
+        6.0 * scale,
+        Stroke::new(
+            1.5 * scale,
+            Theme::with_opacity(theme.accent, opacity * 0.6),
+        ),
+        egui::StrokeKind::Inside,
+    );
+    painter.galley(
+        egui::pos2(
+            rect.center().x - galley.rect.width() / 2.0,
+            rect.top() + padding,
+        ),
+        galley,
+        text_color,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::mermaid::{self, Mermaid};
+
+    #[test]
+    fn height_grows_with_rows() {
+        let Some(Mermaid::Sequence(sequence)) =
+            mermaid::parse("sequenceDiagram\nA->>B: hi\nB->>B: think\nNote over A: hm")
+        else {
+            panic!("expected a sequence diagram");
+        };
+        let rows = ROW_HEIGHT * 2.0 + SELF_ROW_HEIGHT;
+        let expected = PADDING_TOP + HEADER_HEIGHT + rows + PADDING_BOTTOM;
+        assert_eq!(measure_height(&sequence, 1.0), expected);
+        assert_eq!(measure_height(&sequence, 0.5), expected * 0.5);
+    }
+}
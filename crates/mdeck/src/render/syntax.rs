@@ -1,14 +1,88 @@
-use std::sync::LazyLock;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
 
 use eframe::egui::{self, Color32, FontFamily, FontId};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 
-use crate::theme::Theme;
+use crate::parser::Presentation;
+use crate::theme::{self, Theme};
 
 static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
-static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+static THEME_SET: LazyLock<RwLock<ThemeSet>> =
+    LazyLock::new(|| RwLock::new(ThemeSet::load_defaults()));
+
+/// Load `.tmTheme` files from the theme directories (`themes/` next to the
+/// deck, then `~/.config/mdeck/themes`). Each theme is registered under its
+/// file stem, so `themes/Dracula.tmTheme` is selected with `@code-theme: Dracula`.
+/// Calling this again starts over from the built-in themes, so edited, added
+/// and removed files are picked up.
+pub fn load_custom_themes(base_path: &Path) {
+    let ts = with_custom_themes(ThemeSet::load_defaults(), &theme::theme_dirs(base_path));
+    *THEME_SET.write().unwrap() = ts;
+}
+
+/// `ts` with the `.tmTheme` files in `dirs` added, replacing themes of the
+/// same name. Earlier directories win over later ones.
+fn with_custom_themes(mut ts: ThemeSet, dirs: &[PathBuf]) -> ThemeSet {
+    for dir in dirs.iter().rev() {
+        let Ok(paths) = ThemeSet::discover_theme_paths(dir) else {
+            continue;
+        };
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            match ThemeSet::get_theme(&path) {
+                Ok(t) => {
+                    ts.themes.insert(name.to_string(), t);
+                }
+                Err(e) => eprintln!("Warning: failed to load {}: {e}", path.display()),
+            }
+        }
+    }
+    ts
+}
+
+/// Check every `@code-theme` in the presentation (frontmatter and slides)
+/// and return a warning for each name that doesn't match a loaded theme.
+pub fn check_code_themes(presentation: &Presentation) -> Vec<String> {
+    let names = presentation
+        .meta
+        .code_theme
+        .iter()
+        .map(String::as_str)
+        .chain(
+            presentation
+                .slides
+                .iter()
+                .filter_map(|s| s.settings.code_theme.as_deref()),
+        );
+
+    let ts = THEME_SET.read().unwrap();
+    let mut warnings: Vec<String> = Vec::new();
+    for name in names {
+        if ts.themes.contains_key(name) {
+            continue;
+        }
+        let warning = format!(
+            "Unknown code theme '{name}'. Available: {}",
+            theme_names(&ts).join(", ")
+        );
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    warnings
+}
+
+/// Names of all available code themes, sorted.
+fn theme_names(ts: &ThemeSet) -> Vec<String> {
+    let mut names: Vec<String> = ts.themes.keys().cloned().collect();
+    names.sort();
+    names
+}
 
 /// Create a syntax-highlighted `LayoutJob` for a code block.
 pub fn highlight_code(
@@ -20,16 +94,17 @@ pub fn highlight_code(
     max_width: f32,
 ) -> egui::text::LayoutJob {
     let ss = &*SYNTAX_SET;
-    let ts = &*THEME_SET;
+    let ts = THEME_SET.read().unwrap();
 
     let syntax = language
         .and_then(|lang| ss.find_syntax_by_token(lang))
         .unwrap_or_else(|| ss.find_syntax_plain_text());
 
-    let theme_name = theme.syntect_theme_name();
+    // Unknown names are reported once at load time, fall back quietly here
     let syntect_theme = ts
         .themes
-        .get(theme_name)
+        .get(theme.syntect_theme_name())
+        .or_else(|| ts.themes.get(theme.default_syntect_theme_name()))
         .unwrap_or_else(|| ts.themes.values().next().unwrap());
 
     let mut job = egui::text::LayoutJob::default();
@@ -68,3 +143,140 @@ pub fn highlight_code(
 
     job
 }
+
+/// How a line of a diff changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineChange {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+/// Split the `+` and `-` markers off the lines of a diff, returning the code
+/// to highlight and how each line changed. Unchanged lines lose their leading
+/// space when they all have one, as in a unified diff. `+++`, `---` and `@@`
+/// header lines are kept as they are.
+pub fn split_diff(code: &str) -> (String, Vec<LineChange>) {
+    let is_header =
+        |line: &str| line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@");
+    let spaced = code
+        .lines()
+        .filter(|line| !line.is_empty() && !is_header(line) && !line.starts_with(['+', '-']))
+        .all(|line| line.starts_with(' '));
+
+    let mut lines = Vec::new();
+    let mut changes = Vec::new();
+    for line in code.lines() {
+        let (change, text) = if is_header(line) {
+            (LineChange::Unchanged, line)
+        } else if let Some(rest) = line.strip_prefix('+') {
+            (LineChange::Added, rest)
+        } else if let Some(rest) = line.strip_prefix('-') {
+            (LineChange::Removed, rest)
+        } else if spaced {
+            (
+                LineChange::Unchanged,
+                line.strip_prefix(' ').unwrap_or(line),
+            )
+        } else {
+            (LineChange::Unchanged, line)
+        };
+        lines.push(text);
+        changes.push(change);
+    }
+    (lines.join("\n"), changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn reports_unknown_code_themes_once() {
+        let content = "---\n@code-theme: Solarized (dark)\n---\n\n@code-theme: nope\n\n# A\n\n---\n\n@code-theme: nope\n\n# B";
+        let pres = crate::parser::parse(content, Path::new("."));
+        let warnings = check_code_themes(&pres);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Unknown code theme 'nope'"));
+        assert!(warnings[0].contains("InspiredGitHub"));
+    }
+
+    #[test]
+    fn loads_tmtheme_files_from_deck_themes_dir() {
+        let dir = std::env::temp_dir().join(format!("mdeck-code-theme-{}", std::process::id()));
+        let themes = dir.join("themes");
+        std::fs::create_dir_all(&themes).unwrap();
+        let write_theme = |name: &str, color: &str| {
+            std::fs::write(
+                themes.join(format!("{name}.tmTheme")),
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>name</key><string>{name}</string>
+  <key>settings</key>
+  <array>
+    <dict><key>settings</key><dict><key>foreground</key><string>{color}</string></dict></dict>
+  </array>
+</dict>
+</plist>"#
+                ),
+            )
+            .unwrap();
+        };
+        let load = || with_custom_themes(ThemeSet::load_defaults(), std::slice::from_ref(&themes));
+        let foreground = |ts: &ThemeSet, name: &str| {
+            ts.themes[name]
+                .settings
+                .foreground
+                .map(|c| Color32::from_rgb(c.r, c.g, c.b))
+        };
+
+        write_theme("Deck Local", "#FF0000");
+        write_theme("Solarized (light)", "#0000FF");
+        let ts = load();
+        assert_eq!(
+            foreground(&ts, "Deck Local"),
+            Some(Color32::from_rgb(0xFF, 0, 0))
+        );
+        // Deck-local themes override built-in ones of the same name
+        assert_eq!(
+            foreground(&ts, "Solarized (light)"),
+            Some(Color32::from_rgb(0, 0, 0xFF))
+        );
+
+        // Loading again picks up edits and drops removed files
+        write_theme("Deck Local", "#00FF00");
+        std::fs::remove_file(themes.join("Solarized (light).tmTheme")).unwrap();
+        let ts = load();
+        assert_eq!(
+            foreground(&ts, "Deck Local"),
+            Some(Color32::from_rgb(0, 0xFF, 0))
+        );
+        assert_ne!(
+            foreground(&ts, "Solarized (light)"),
+            Some(Color32::from_rgb(0, 0, 0xFF))
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(!load().themes.contains_key("Deck Local"));
+    }
+
+    #[test]
+    fn diff_markers_come_off_the_code() {
+        use LineChange::*;
+        let (code, changes) =
+            split_diff("@@ -1,3 +1,3 @@\n fn main() {\n-    old();\n+    new();\n }");
+        assert_eq!(
+            code,
+            "@@ -1,3 +1,3 @@\nfn main() {\n    old();\n    new();\n}"
+        );
+        assert_eq!(changes, [Unchanged, Unchanged, Removed, Added, Unchanged]);
+
+        // Hand-written diffs may leave unchanged lines unprefixed
+        let (code, _) = split_diff("fn main() {\n+    new();\n}");
+        assert_eq!(code, "fn main() {\n    new();\n}");
+    }
+}
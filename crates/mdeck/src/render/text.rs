@@ -1,9 +1,13 @@
-use std::collections::HashMap;
-
-use crate::parser::{Block, ImageDirectives, Inline, ListItem, ListMarker};
+use crate::parser::{
+    Alignment, Block, CodeInclude, CodeOptions, ImageDirectives, Inline, ListItem, ListMarker,
+};
 use crate::render::image_cache::ImageCache;
+use crate::render::syntax::{self, LineChange};
+use crate::render::{diagram, links, matches, math, morph};
 use crate::theme::Theme;
-use eframe::egui::{self, Color32, FontFamily, FontId, Pos2, Stroke};
+use eframe::egui::{self, Color32, FontFamily, FontId, Galley, Pos2, Shape, Stroke};
+use std::borrow::Cow;
+use std::ops::Range;
 
 /// Create a LayoutJob from inline elements.
 pub fn inlines_to_job(
@@ -14,10 +18,57 @@ pub fn inlines_to_job(
 ) -> egui::text::LayoutJob {
     let mut job = egui::text::LayoutJob::default();
     job.wrap.max_width = max_width;
-    append_inlines(&mut job, inlines, font_size, color, false, false);
+    append_inlines(
+        &mut job,
+        inlines,
+        font_size,
+        color,
+        false,
+        false,
+        &mut Vec::new(),
+    );
+    job
+}
+
+/// Record the links in a galley laid out from `inlines` (by [`inlines_to_job`]
+/// or [`heading_job`]) and painted at `pos`, so they can be clicked.
+pub fn register_links(ui: &egui::Ui, inlines: &[Inline], galley: &Galley, pos: Pos2) {
+    let mut ranges = Vec::new();
+    let mut job = egui::text::LayoutJob::default();
+    append_inlines(
+        &mut job,
+        inlines,
+        1.0,
+        Color32::WHITE,
+        false,
+        false,
+        &mut ranges,
+    );
+    links::register(ui, galley, pos, &ranges);
+}
+
+/// Like [`inlines_to_job`], but set in the theme's heading font if one is installed.
+pub fn heading_job(
+    ui: &egui::Ui,
+    inlines: &[Inline],
+    font_size: f32,
+    color: Color32,
+    max_width: f32,
+) -> egui::text::LayoutJob {
+    let mut job = inlines_to_job(inlines, font_size, color, max_width);
+    let family = crate::theme::heading_family(ui.ctx());
+    if family != FontFamily::Proportional {
+        for section in &mut job.sections {
+            if section.format.font_id.family == FontFamily::Proportional {
+                section.format.font_id.family = family.clone();
+            }
+        }
+    }
     job
 }
 
+/// Append `inlines` to `job`, adding the byte range of every link's text to
+/// `links` along with its URL.
 fn append_inlines(
     job: &mut egui::text::LayoutJob,
     inlines: &[Inline],
@@ -25,6 +76,7 @@ fn append_inlines(
     color: Color32,
     bold: bool,
     italic: bool,
+    links: &mut Vec<(Range<usize>, String)>,
 ) {
     for inline in inlines {
         match inline {
@@ -39,14 +91,29 @@ fn append_inlines(
                 job.append(s, 0.0, format);
             }
             Inline::Bold(children) => {
-                append_inlines(job, children, font_size, color, true, italic);
+                append_inlines(job, children, font_size, color, true, italic, links);
             }
             Inline::Italic(children) => {
-                append_inlines(job, children, font_size, color, bold, true);
+                append_inlines(job, children, font_size, color, bold, true, links);
             }
             Inline::Strikethrough(children) => {
                 let mut inner_job = egui::text::LayoutJob::default();
-                append_inlines(&mut inner_job, children, font_size, color, bold, italic);
+                let mut inner_links = Vec::new();
+                append_inlines(
+                    &mut inner_job,
+                    children,
+                    font_size,
+                    color,
+                    bold,
+                    italic,
+                    &mut inner_links,
+                );
+                let offset = job.text.len();
+                links.extend(
+                    inner_links
+                        .into_iter()
+                        .map(|(range, url)| (range.start + offset..range.end + offset, url)),
+                );
                 // Apply strikethrough to all sections
                 for section in &inner_job.sections {
                     let mut format = section.format.clone();
@@ -63,10 +130,18 @@ fn append_inlines(
                 };
                 job.append(s, 0.0, format);
             }
-            Inline::Link { text, .. } => {
+            Inline::Math(tex) => math::append_inline(job, tex, font_size, color),
+            Inline::Link { text, url } => {
                 // Render link text in accent color
                 let link_color = Color32::from_rgb(0x52, 0x94, 0xE2);
-                append_inlines(job, text, font_size, link_color, bold, italic);
+                let start = job.text.len();
+                append_inlines(job, text, font_size, link_color, bold, italic, links);
+                links.push((start..job.text.len(), url.clone()));
+            }
+            Inline::Ref(id) => {
+                // Left as written so the broken reference shows on the slide
+                let text = Inline::Text(format!("{{{{ref:{id}}}}}"));
+                append_inlines(job, &[text], font_size, color, bold, italic, links);
             }
         }
     }
@@ -84,6 +159,8 @@ pub fn draw_inlines(
     let job = inlines_to_job(inlines, font_size, color, max_width);
     let galley = ui.painter().layout_job(job);
     let height = galley.rect.height();
+    matches::paint(ui, &galley, pos);
+    register_links(ui, inlines, &galley, pos);
     ui.painter().galley(pos, galley, color);
     height
 }
@@ -102,7 +179,13 @@ pub fn draw_heading(
 ) -> f32 {
     let size = theme.heading_size(level) * scale;
     let color = Theme::with_opacity(theme.heading_color, opacity);
-    draw_inlines(ui, inlines, pos, size, color, max_width)
+    let job = heading_job(ui, inlines, size, color, max_width);
+    let galley = ui.painter().layout_job(job);
+    let height = galley.rect.height();
+    matches::paint(ui, &galley, pos);
+    register_links(ui, inlines, &galley, pos);
+    ui.painter().galley(pos, galley, color);
+    height
 }
 
 /// Draw a paragraph. Returns height used.
@@ -237,13 +320,34 @@ fn draw_list_inner(
     y_offset
 }
 
-/// Draw a code block with syntax highlighting. Returns height used.
+fn code_focus_id() -> egui::Id {
+    egui::Id::new("mdeck-code-focus")
+}
+
+/// The highlighted lines of the stepped code block drawn since the last
+/// call, in screen coordinates, so the view can scroll to them.
+pub fn take_code_focus(ctx: &egui::Context) -> Option<egui::Rect> {
+    ctx.data_mut(|d| {
+        let focus = d.get_temp::<egui::Rect>(code_focus_id());
+        d.remove::<egui::Rect>(code_focus_id());
+        focus
+    })
+}
+
+/// Draw a code block with syntax highlighting, highlighting the lines of the
+/// stage for `reveal_step`. When there are several stages the other lines
+/// are dimmed. Line numbers and diff markers go in a gutter on the left;
+/// `file_lines` numbers included code by its lines in the file.
+/// Returns height used.
 #[allow(clippy::too_many_arguments)]
 pub fn draw_code_block(
     ui: &egui::Ui,
     code: &str,
     language: Option<&str>,
-    highlight_lines: &[usize],
+    highlight_steps: &[Vec<usize>],
+    options: CodeOptions,
+    file_lines: &[usize],
+    reveal_step: usize,
     theme: &Theme,
     pos: Pos2,
     max_width: f32,
@@ -252,66 +356,258 @@ pub fn draw_code_block(
 ) -> f32 {
     let padding = 16.0 * scale;
     let bg_color = Theme::with_opacity(theme.code_background, opacity);
+    // A morph transition animates the code itself, from full-opacity layouts
+    let capturing = morph::is_capturing(ui.ctx());
+
+    let (code, changes) = if options.diff {
+        let (code, changes) = syntax::split_diff(code);
+        (Cow::Owned(code), changes)
+    } else {
+        (Cow::Borrowed(code), Vec::new())
+    };
+
+    // Removed lines of a diff aren't numbered, so the numbers follow the new
+    // code. Included code keeps its numbers from the file unless `numbers=N`
+    // gives a start.
+    let from_file = !file_lines.is_empty() && !options.explicit_start;
+    let mut next_number = options.line_numbers.unwrap_or(1);
+    let numbers: Vec<Option<usize>> = (0..code.lines().count())
+        .map(|i| match changes.get(i) {
+            Some(LineChange::Removed) => None,
+            _ if from_file => file_lines.get(i).copied(),
+            _ => {
+                next_number += 1;
+                Some(next_number - 1)
+            }
+        })
+        .collect();
+
+    let gutter_font = FontId::new(theme.code_size * scale, FontFamily::Monospace);
+    let text_width = |text: String| {
+        ui.painter()
+            .layout_no_wrap(text, gutter_font.clone(), Color32::WHITE)
+            .rect
+            .width()
+    };
+    let number_width = match options.line_numbers {
+        Some(_) => {
+            let widest = numbers.iter().flatten().max().copied().unwrap_or(1);
+            text_width(widest.to_string()) + padding
+        }
+        None => 0.0,
+    };
+    let sign_width = if options.diff {
+        text_width("+".to_string()) + padding * 0.5
+    } else {
+        0.0
+    };
+    let gutter = number_width + sign_width;
 
     // Build syntax-highlighted layout
-    let job = crate::render::syntax::highlight_code(
-        code,
+    let job = syntax::highlight_code(
+        &code,
         language,
         theme.code_size * scale,
-        opacity,
+        if capturing { 1.0 } else { opacity },
         theme,
-        max_width - padding * 2.0,
+        max_width - padding * 2.0 - gutter,
     );
     let code_galley = ui.painter().layout_job(job);
 
     let total_height = code_galley.rect.height() + padding * 2.0;
+    let bg_rect = egui::Rect::from_min_size(pos, egui::vec2(max_width, total_height));
+    let code_pos = Pos2::new(pos.x + padding + gutter, pos.y + padding);
+
+    // Screen rect of each visual row with its source line, and the top of
+    // each source line's first row. Each row in the galley is a visual line;
+    // `ends_with_newline` tells us when a source line ends.
+    let code_top = pos.y + padding;
+    let mut source_line = 1usize;
+    let mut rows = Vec::with_capacity(code_galley.rows.len());
+    let mut line_tops = vec![code_top];
+    for row in &code_galley.rows {
+        let row_rect = row.rect();
+        let rect = egui::Rect::from_min_max(
+            Pos2::new(pos.x + padding * 0.5, code_top + row_rect.top()),
+            Pos2::new(
+                pos.x + max_width - padding * 0.5,
+                code_top + row_rect.bottom(),
+            ),
+        );
+        rows.push((source_line, rect));
+        if row.ends_with_newline {
+            source_line += 1;
+            line_tops.push(code_top + row_rect.bottom());
+        }
+    }
+
+    // Tint added and removed lines, with a bar down the left edge
+    let gutter_opacity = if capturing { 1.0 } else { opacity };
+    let mut tints = Vec::new();
+    for (line, rect) in &rows {
+        let color = match changes.get(line - 1) {
+            Some(LineChange::Added) => DIFF_ADDED_COLOR,
+            Some(LineChange::Removed) => DIFF_REMOVED_COLOR,
+            _ => continue,
+        };
+        tints.push(Shape::rect_filled(
+            *rect,
+            0.0,
+            Theme::with_opacity(color, gutter_opacity * 0.15),
+        ));
+        let bar =
+            egui::Rect::from_min_max(rect.min, Pos2::new(rect.min.x + 3.0 * scale, rect.max.y));
+        tints.push(Shape::rect_filled(
+            bar,
+            0.0,
+            Theme::with_opacity(color, gutter_opacity),
+        ));
+    }
+
+    // The gutter: line numbers right-aligned, then diff signs
+    let text = |pos: Pos2, anchor: egui::Align2, text: String, color: Color32| {
+        let galley = ui
+            .painter()
+            .layout_no_wrap(text, gutter_font.clone(), color);
+        Shape::galley(anchor.anchor_size(pos, galley.size()).min, galley, color)
+    };
+    let number_color = Theme::with_opacity(theme.code_foreground, gutter_opacity * 0.45);
+    let mut marks = Vec::new();
+    for (i, top) in line_tops.iter().enumerate() {
+        if let Some(Some(number)) = numbers.get(i).filter(|_| options.line_numbers.is_some()) {
+            marks.push(text(
+                Pos2::new(pos.x + padding + number_width - padding, *top),
+                egui::Align2::RIGHT_TOP,
+                number.to_string(),
+                number_color,
+            ));
+        }
+        let (sign, color) = match changes.get(i) {
+            Some(LineChange::Added) => ("+", DIFF_ADDED_COLOR),
+            Some(LineChange::Removed) => ("-", DIFF_REMOVED_COLOR),
+            _ => continue,
+        };
+        marks.push(text(
+            Pos2::new(pos.x + padding + number_width, *top),
+            egui::Align2::LEFT_TOP,
+            sign.to_string(),
+            Theme::with_opacity(color, gutter_opacity),
+        ));
+    }
+
+    if capturing {
+        morph::capture(
+            ui.ctx(),
+            morph::CodeCapture {
+                galley: code_galley,
+                pos: code_pos,
+                gutter: tints.into_iter().chain(marks).collect(),
+                background: bg_rect,
+                background_color: theme.code_background,
+                rounding: 8.0 * scale,
+            },
+        );
+        return total_height;
+    }
 
     // Draw background
-    let bg_rect = egui::Rect::from_min_size(pos, egui::vec2(max_width, total_height));
     ui.painter().rect_filled(bg_rect, 8.0 * scale, bg_color);
+    ui.painter().extend(tints);
 
     // Draw line highlights using actual galley row positions
-    if !highlight_lines.is_empty() {
-        let accent = Theme::with_opacity(theme.accent, opacity * 0.15);
-        let code_top = pos.y + padding;
-
-        // Each row in the galley corresponds to a visual line.
-        // `ends_with_newline` tells us when a source line ends.
-        let mut source_line = 1usize;
-        for row in &code_galley.rows {
-            let row_rect = row.rect();
-
-            if highlight_lines.contains(&source_line) {
-                let hl_rect = egui::Rect::from_min_max(
-                    Pos2::new(pos.x + padding * 0.5, code_top + row_rect.top()),
-                    Pos2::new(
-                        pos.x + max_width - padding * 0.5,
-                        code_top + row_rect.bottom(),
-                    ),
-                );
-                ui.painter().rect_filled(hl_rect, 4.0 * scale, accent);
-            }
-
-            if row.ends_with_newline {
-                source_line += 1;
-            }
+    let stage = reveal_step.min(highlight_steps.len().saturating_sub(1));
+    let highlight_lines = highlight_steps.get(stage).map_or(&[][..], Vec::as_slice);
+    let stepped = highlight_steps.len() > 1 && !highlight_lines.is_empty();
+    let accent = Theme::with_opacity(theme.accent, opacity * 0.15);
+    let mut focus: Option<egui::Rect> = None;
+    for (line, rect) in &rows {
+        if highlight_lines.contains(line) {
+            ui.painter().rect_filled(*rect, 4.0 * scale, accent);
+            focus = Some(focus.map_or(*rect, |f| f.union(*rect)));
         }
     }
+    if let Some(focus) = focus.filter(|_| stepped) {
+        ui.ctx().data_mut(|d| d.insert_temp(code_focus_id(), focus));
+    }
+
+    ui.painter().extend(marks);
 
     // Draw code
-    let code_pos = Pos2::new(pos.x + padding, pos.y + padding);
+    matches::paint(ui, &code_galley, code_pos);
     let fallback = Theme::with_opacity(theme.code_foreground, opacity);
     ui.painter().galley(code_pos, code_galley, fallback);
 
+    // Fade the lines outside the current stage into the background
+    if stepped {
+        let veil = Theme::with_opacity(theme.code_background, opacity * 0.6);
+        for (line, rect) in &rows {
+            if !highlight_lines.contains(line) {
+                ui.painter().rect_filled(*rect, 0.0, veil);
+            }
+        }
+    }
+
     total_height
 }
 
-/// Draw a table. Returns height used.
+/// Gutter and tint of lines a diff adds.
+pub const DIFF_ADDED_COLOR: Color32 = Color32::from_rgb(60, 170, 90);
+
+/// Gutter and tint of lines a diff removes.
+pub const DIFF_REMOVED_COLOR: Color32 = Color32::from_rgb(210, 75, 70);
+
+/// Color of the outline and message of a code block whose included file
+/// couldn't be read.
+pub const INCLUDE_ERROR_COLOR: Color32 = Color32::from_rgb(220, 80, 70);
+
+/// Draw the placeholder for a code block whose include failed, with the
+/// reason in place of the code. Returns height used.
+pub fn draw_include_error(
+    ui: &egui::Ui,
+    message: &str,
+    theme: &Theme,
+    pos: Pos2,
+    max_width: f32,
+    opacity: f32,
+    scale: f32,
+) -> f32 {
+    let padding = 16.0 * scale;
+    let color = Theme::with_opacity(INCLUDE_ERROR_COLOR, opacity);
+    let galley = ui.painter().layout(
+        message.to_string(),
+        FontId::new(theme.code_size * scale, FontFamily::Monospace),
+        color,
+        max_width - padding * 2.0,
+    );
+    let total_height = galley.rect.height() + padding * 2.0;
+    let rect = egui::Rect::from_min_size(pos, egui::vec2(max_width, total_height));
+    ui.painter().rect(
+        rect,
+        8.0 * scale,
+        Theme::with_opacity(theme.code_background, opacity),
+        Stroke::new(2.0 * scale, color),
+        egui::StrokeKind::Inside,
+    );
+    ui.painter()
+        .galley(Pos2::new(pos.x + padding, pos.y + padding), galley, color);
+    total_height
+}
+
+/// Smallest fraction of the normal font size a wide table shrinks to before
+/// its cells wrap.
+const MIN_TABLE_FONT_SCALE: f32 = 0.6;
+
+/// Draw a table with columns sized to their content. Tables too wide for
+/// `max_width` get a smaller font, then wrap. Rows appear at their reveal
+/// step. Returns height used.
 #[allow(clippy::too_many_arguments)]
 pub fn draw_table(
     ui: &egui::Ui,
     headers: &[Vec<Inline>],
     rows: &[Vec<Vec<Inline>>],
+    alignments: &[Alignment],
+    row_steps: &[usize],
+    reveal_step: usize,
     theme: &Theme,
     pos: Pos2,
     max_width: f32,
@@ -325,29 +621,75 @@ pub fn draw_table(
     let row_spacing = 4.0 * scale;
     let font_size = theme.body_size * 0.85 * scale;
 
-    let num_cols = headers.len().max(1);
-    let col_width = (max_width - cell_padding * 2.0) / num_cols as f32;
+    let num_cols = rows
+        .iter()
+        .map(Vec::len)
+        .chain([headers.len(), 1])
+        .max()
+        .unwrap_or(1);
+
+    // Measure every cell unwrapped, including rows not revealed yet, so the
+    // columns don't move as rows appear
+    let mut natural = vec![font_size; num_cols];
+    for row in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+        for (col, cell) in row.iter().enumerate() {
+            let job = inlines_to_job(cell, font_size, color, f32::INFINITY);
+            natural[col] = natural[col].max(ui.painter().layout_job(job).rect.width());
+        }
+    }
+    let available = max_width - cell_padding * 2.0 * num_cols as f32;
+    let (font_scale, widths) = fit_columns(&natural, available);
+    let font_size = font_size * font_scale;
+
+    let mut lefts = Vec::with_capacity(num_cols);
+    let mut x = pos.x + cell_padding;
+    for width in &widths {
+        lefts.push(x);
+        x += width + cell_padding * 2.0;
+    }
+
+    let fill = |rect: egui::Rect, fill: Color32| {
+        let fill = Theme::with_opacity(fill, opacity * fill.a() as f32 / 255.0);
+        ui.painter().rect_filled(rect, 4.0 * scale, fill);
+    };
+    let row_rect = |y: f32, height: f32| {
+        egui::Rect::from_min_size(Pos2::new(pos.x, y), egui::vec2(max_width, height))
+    };
+    let draw_row = |cells: &[Vec<Inline>], y: f32, color: Color32| {
+        for (col, cell) in cells.iter().enumerate().take(num_cols) {
+            let align = alignments.get(col).copied().unwrap_or_default();
+            draw_table_cell(
+                ui,
+                cell,
+                align,
+                lefts[col],
+                y,
+                widths[col],
+                font_size,
+                color,
+            );
+        }
+    };
+    let row_height = |cells: &[Vec<Inline>]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| {
+                let job = inlines_to_job(cell, font_size, color, width + 0.5);
+                ui.painter().layout_job(job).rect.height()
+            })
+            .fold(0.0f32, f32::max)
+    };
 
     let mut y = pos.y;
 
     // Draw headers
-    let mut max_header_height = 0.0f32;
-    for (col, header) in headers.iter().enumerate() {
-        let cell_pos = Pos2::new(
-            pos.x + cell_padding + col as f32 * col_width,
-            y + cell_padding,
-        );
-        let h = draw_inlines(
-            ui,
-            header,
-            cell_pos,
-            font_size,
-            heading_color,
-            col_width - cell_padding,
-        );
-        max_header_height = max_header_height.max(h);
+    let header_height = row_height(headers) + cell_padding * 2.0;
+    if let Some(background) = theme.table_header_background {
+        fill(row_rect(y, header_height), background);
     }
-    y += max_header_height + cell_padding * 2.0;
+    draw_row(headers, y + cell_padding, heading_color);
+    y += header_height;
 
     // Draw separator line
     let line_y = y + row_spacing / 2.0;
@@ -360,30 +702,100 @@ pub fn draw_table(
     );
     y += row_spacing;
 
-    // Draw data rows
-    for row in rows {
-        let mut max_row_height = 0.0f32;
-        for (col, cell) in row.iter().enumerate() {
-            let cell_pos = Pos2::new(
-                pos.x + cell_padding + col as f32 * col_width,
-                y + cell_padding,
-            );
-            let h = draw_inlines(
-                ui,
-                cell,
-                cell_pos,
-                font_size,
-                color,
-                col_width - cell_padding,
-            );
-            max_row_height = max_row_height.max(h);
+    // Draw data rows, striping every other one
+    for (index, row) in rows.iter().enumerate() {
+        if row_steps.get(index).copied().unwrap_or(0) > reveal_step {
+            continue;
         }
-        y += max_row_height + cell_padding + row_spacing;
+        let height = row_height(row) + cell_padding + row_spacing;
+        if index % 2 == 1 {
+            fill(row_rect(y, height), theme.table_stripe);
+        }
+        draw_row(row, y + (cell_padding + row_spacing) / 2.0, color);
+        y += height;
     }
 
     y - pos.y
 }
 
+/// Paint one table cell in the column starting at `left`.
+#[allow(clippy::too_many_arguments)]
+fn draw_table_cell(
+    ui: &egui::Ui,
+    cell: &[Inline],
+    align: Alignment,
+    left: f32,
+    top: f32,
+    width: f32,
+    font_size: f32,
+    color: Color32,
+) {
+    // Wrapped rows of centered and right-aligned text line up on the anchor
+    let (halign, x) = match align {
+        Alignment::Left => (egui::Align::LEFT, left),
+        Alignment::Center => (egui::Align::Center, left + width / 2.0),
+        Alignment::Right => (egui::Align::RIGHT, left + width),
+    };
+    let mut job = inlines_to_job(cell, font_size, color, width + 0.5);
+    job.halign = halign;
+    let galley = ui.painter().layout_job(job);
+    let pos = Pos2::new(x, top);
+    matches::paint(ui, &galley, pos);
+    register_links(ui, cell, &galley, pos);
+    ui.painter().galley(pos, galley, color);
+}
+
+/// Font scale and text width of each column, for columns whose widest cells
+/// measure `natural` at full size with `available` to share. Every column
+/// gets room in proportion to its content; when that's less than it needs,
+/// the font shrinks to fit, down to [`MIN_TABLE_FONT_SCALE`]. Past that,
+/// columns narrower than an even share keep their width and the wider ones
+/// split the rest and wrap.
+fn fit_columns(natural: &[f32], available: f32) -> (f32, Vec<f32>) {
+    let total: f32 = natural.iter().sum();
+    let available = available.max(0.0);
+    let font_scale = (available / total).clamp(MIN_TABLE_FONT_SCALE, 1.0);
+    if total * font_scale <= available {
+        let widths = natural.iter().map(|w| available * w / total).collect();
+        return (font_scale, widths);
+    }
+
+    let needed: Vec<f32> = natural.iter().map(|w| w * font_scale).collect();
+    let mut fixed = vec![false; needed.len()];
+    loop {
+        let left = available
+            - needed
+                .iter()
+                .zip(&fixed)
+                .filter(|(_, f)| **f)
+                .map(|(w, _)| w)
+                .sum::<f32>();
+        let open = fixed.iter().filter(|f| !**f).count();
+        let share = left / open as f32;
+        let mut changed = false;
+        for (width, fixed) in needed.iter().zip(&mut fixed) {
+            if !*fixed && *width <= share {
+                *fixed = true;
+                changed = true;
+            }
+        }
+        if !changed || open == 1 {
+            let wide: f32 = needed
+                .iter()
+                .zip(&fixed)
+                .filter(|(_, f)| !**f)
+                .map(|(w, _)| w)
+                .sum();
+            let widths = needed
+                .iter()
+                .zip(&fixed)
+                .map(|(w, f)| if *f { *w } else { left * w / wide })
+                .collect();
+            return (font_scale, widths);
+        }
+    }
+}
+
 /// Draw a blockquote. Returns height used.
 pub fn draw_blockquote(
     ui: &egui::Ui,
@@ -477,7 +889,7 @@ pub fn measure_single_block_height(
     match block {
         Block::Heading { level, inlines } => {
             let size = theme.heading_size(*level) * scale;
-            let job = inlines_to_job(inlines, size, theme.heading_color, max_width);
+            let job = heading_job(ui, inlines, size, theme.heading_color, max_width);
             ui.painter().layout_job(job).rect.height()
         }
         Block::Paragraph { inlines } | Block::BlockQuote { inlines } => {
@@ -501,6 +913,12 @@ pub fn measure_single_block_height(
             rows.len() as f32 * row_height + 10.0 * scale
         }
         Block::HorizontalRule => 2.0 * scale,
+        Block::Math { tex } => math::measure_height(ui, tex, theme, max_width, scale),
+        Block::Diagram {
+            content,
+            direction,
+            syntax,
+        } => diagram::measure_height(ui, content, *direction, *syntax, scale),
         _ => theme.body_size * scale * 1.5,
     }
 }
@@ -545,15 +963,26 @@ pub fn draw_block(
             reveal_step,
             scale,
         ),
+        Block::CodeBlock {
+            include: Some(CodeInclude {
+                error: Some(error), ..
+            }),
+            ..
+        } => draw_include_error(ui, error, theme, pos, max_width, opacity, scale),
         Block::CodeBlock {
             language,
             code,
-            highlight_lines,
+            highlight_steps,
+            options,
+            include,
         } => draw_code_block(
             ui,
             code,
             language.as_deref(),
-            highlight_lines,
+            highlight_steps,
+            *options,
+            include.as_ref().map_or(&[], |i| &i.line_numbers),
+            reveal_step,
             theme,
             pos,
             max_width,
@@ -563,9 +992,24 @@ pub fn draw_block(
         Block::BlockQuote { inlines } => {
             draw_blockquote(ui, inlines, theme, pos, max_width, opacity, scale)
         }
-        Block::Table { headers, rows } => {
-            draw_table(ui, headers, rows, theme, pos, max_width, opacity, scale)
-        }
+        Block::Table {
+            headers,
+            rows,
+            alignments,
+            row_steps,
+        } => draw_table(
+            ui,
+            headers,
+            rows,
+            alignments,
+            row_steps,
+            reveal_step,
+            theme,
+            pos,
+            max_width,
+            opacity,
+            scale,
+        ),
         Block::Image {
             alt,
             path,
@@ -582,9 +1026,23 @@ pub fn draw_block(
             image_cache,
             scale,
         ),
-        Block::Diagram { content } => {
-            draw_diagram(ui, content, theme, pos, max_width, opacity, scale)
-        }
+        Block::Diagram {
+            content,
+            direction,
+            syntax,
+        } => diagram::draw(
+            ui,
+            content,
+            *direction,
+            *syntax,
+            theme,
+            pos,
+            max_width,
+            opacity,
+            reveal_step,
+            scale,
+        ),
+        Block::Math { tex } => math::draw(ui, tex, theme, pos, max_width, opacity, scale),
         Block::HorizontalRule => {
             let color = Theme::with_opacity(theme.accent, opacity * 0.5);
             let y = pos.y + 10.0 * scale;
@@ -773,287 +1231,95 @@ pub fn draw_image_placeholder(
     height
 }
 
-/// Draw a diagram parsed from `- Node: label` and `- A -> B: label` lines.
-#[allow(clippy::too_many_arguments)]
-fn draw_diagram(
-    ui: &egui::Ui,
-    content: &str,
-    theme: &Theme,
-    pos: Pos2,
-    max_width: f32,
-    opacity: f32,
-    scale: f32,
-) -> f32 {
-    let (nodes, edges) = parse_diagram(content);
-
-    if nodes.is_empty() {
-        // Fallback for unparseable diagrams
-        let color = Theme::with_opacity(theme.foreground, opacity * 0.6);
-        let bg = Theme::with_opacity(theme.code_background, opacity);
-        let height = 200.0 * scale;
-        let rect = egui::Rect::from_min_size(pos, egui::vec2(max_width, height));
-        ui.painter().rect_filled(rect, 8.0 * scale, bg);
-        let galley = ui.painter().layout(
-            "[Diagram]".to_string(),
-            FontId::proportional(theme.body_size * 0.8 * scale),
-            color,
-            max_width,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_columns_share_room_by_content() {
+        let (font_scale, widths) = fit_columns(&[100.0, 300.0], 800.0);
+        assert_eq!(font_scale, 1.0);
+        assert_eq!(widths, [200.0, 600.0]);
+
+        // Too wide: the font shrinks so the text just fits
+        let (font_scale, widths) = fit_columns(&[600.0, 400.0], 800.0);
+        assert_eq!(font_scale, 0.8);
+        assert_eq!(widths, [480.0, 320.0]);
+
+        // Far too wide: the font stops shrinking, narrow columns keep their
+        // width and the wide ones wrap
+        let (font_scale, widths) = fit_columns(&[100.0, 2000.0, 3000.0], 1000.0);
+        assert_eq!(font_scale, MIN_TABLE_FONT_SCALE);
+        let expected = [60.0, 376.0, 564.0];
+        assert!(
+            widths
+                .iter()
+                .zip(expected)
+                .all(|(w, e)| (w - e).abs() < 0.01)
         );
-        let text_pos = Pos2::new(
-            pos.x + (max_width - galley.rect.width()) / 2.0,
-            pos.y + (height - galley.rect.height()) / 2.0,
-        );
-        ui.painter().galley(text_pos, galley, color);
-        return height;
     }
 
-    let node_count = nodes.len();
-    let h_gap = 80.0 * scale;
-    let node_width = ((max_width - (node_count - 1) as f32 * h_gap) / node_count as f32)
-        .clamp(80.0 * scale, 180.0 * scale);
-    let node_height = 44.0 * scale;
-    let corner_radius = node_height / 2.0; // pill shape
-
-    let total_width = node_count as f32 * node_width + (node_count - 1) as f32 * h_gap;
-    let start_x = pos.x + (max_width - total_width) / 2.0;
-    let center_y = pos.y + 50.0 * scale;
-
-    let mut node_positions: HashMap<String, (f32, f32)> = HashMap::new();
-    let accent = theme.accent;
-    let node_fill = Theme::with_opacity(accent, opacity * 0.9);
-    let shadow_color = Theme::with_opacity(Color32::from_rgb(0, 0, 0), opacity * 0.15);
-
-    // Compute contrasting label color for filled nodes
-    let label_color = Theme::with_opacity(Color32::WHITE, opacity);
-
-    // Draw nodes
-    for (i, node) in nodes.iter().enumerate() {
-        let x = start_x + i as f32 * (node_width + h_gap);
-        let y = center_y;
-        node_positions.insert(
-            node.name.clone(),
-            (x + node_width / 2.0, y + node_height / 2.0),
-        );
-
-        let rect = egui::Rect::from_min_size(egui::pos2(x, y), egui::vec2(node_width, node_height));
-
-        // Drop shadow
-        let shadow_offset = 2.0 * scale;
-        let shadow_rect = rect.translate(egui::vec2(shadow_offset, shadow_offset));
-        ui.painter()
-            .rect_filled(shadow_rect, corner_radius, shadow_color);
-
-        // Filled node
-        ui.painter().rect_filled(rect, corner_radius, node_fill);
-
-        // Label text centered in node
-        let font_size = theme.body_size * 0.65 * scale;
-        let galley = ui.painter().layout(
-            node.label.clone(),
-            FontId::proportional(font_size),
-            label_color,
-            node_width - 16.0 * scale,
-        );
-        let text_pos = egui::pos2(
-            x + (node_width - galley.rect.width()) / 2.0,
-            y + (node_height - galley.rect.height()) / 2.0,
-        );
-        ui.painter().galley(text_pos, galley, label_color);
-    }
-
-    // Draw edges with quadratic bezier curves
-    let edge_color = Theme::with_opacity(accent, opacity * 0.7);
-    let label_bg = Theme::with_opacity(theme.code_background, opacity * 0.9);
-    let label_color_edge = Theme::with_opacity(theme.foreground, opacity * 0.8);
-    let line_width = 2.5 * scale;
-    let arrow_size = 10.0 * scale;
-
-    for (edge_idx, edge) in edges.iter().enumerate() {
-        let Some(&(fx, fy)) = node_positions.get(&edge.from) else {
-            continue;
-        };
-        let Some(&(tx, ty)) = node_positions.get(&edge.to) else {
-            continue;
-        };
-
-        // Determine connection points on node pill edges
-        let (start, end) = if tx > fx {
-            (
-                Pos2::new(fx + node_width / 2.0, fy),
-                Pos2::new(tx - node_width / 2.0, ty),
-            )
-        } else {
-            (
-                Pos2::new(fx - node_width / 2.0, fy),
-                Pos2::new(tx + node_width / 2.0, ty),
-            )
-        };
-
-        let diff = end - start;
-        if diff.length() < 1.0 {
-            continue;
-        }
-
-        // Quadratic bezier curve with control point above/below
-        let mid = egui::pos2((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
-        let curve_offset = if edge_idx % 2 == 0 {
-            -35.0 * scale
-        } else {
-            35.0 * scale
-        };
-        let control = egui::pos2(mid.x, mid.y + curve_offset);
-
-        // Draw bezier curve
-        let bezier = egui::epaint::QuadraticBezierShape::from_points_stroke(
-            [start, control, end],
-            false,
-            Color32::TRANSPARENT,
-            Stroke::new(line_width, edge_color),
-        );
-        ui.painter().add(bezier);
-
-        // Filled arrowhead at end
-        let dir = (end - control).normalized();
-        let perp = egui::vec2(-dir.y, dir.x);
-        let arrow_p1 = end - dir * arrow_size + perp * arrow_size * 0.4;
-        let arrow_p2 = end - dir * arrow_size - perp * arrow_size * 0.4;
-        ui.painter().add(egui::Shape::convex_polygon(
-            vec![end, arrow_p1, arrow_p2],
-            edge_color,
-            Stroke::NONE,
-        ));
-
-        // Edge label with pill background
-        if !edge.label.is_empty() {
-            let label_font_size = theme.body_size * 0.5 * scale;
-            let label_padding = 6.0 * scale;
-            let galley = ui.painter().layout_no_wrap(
-                edge.label.clone(),
-                FontId::proportional(label_font_size),
-                label_color_edge,
-            );
-            let label_w = galley.rect.width() + label_padding * 2.0;
-            let label_h = galley.rect.height() + label_padding * 2.0;
-            let label_rect = egui::Rect::from_center_size(
-                egui::pos2(mid.x, mid.y + curve_offset * 0.6),
-                egui::vec2(label_w, label_h),
-            );
-            ui.painter()
-                .rect_filled(label_rect, label_h / 2.0, label_bg);
-            ui.painter().galley(
-                egui::pos2(
-                    label_rect.left() + label_padding,
-                    label_rect.top() + label_padding,
-                ),
-                galley,
-                label_color_edge,
-            );
-        }
-    }
-
-    node_height + 140.0 * scale
-}
-
-struct DiagramNode {
-    name: String,
-    label: String,
-}
-
-struct DiagramEdge {
-    from: String,
-    to: String,
-    label: String,
-}
-
-fn parse_diagram(content: &str) -> (Vec<DiagramNode>, Vec<DiagramEdge>) {
-    let mut nodes = Vec::new();
-    let mut edges = Vec::new();
-    let mut seen_nodes: HashMap<String, usize> = HashMap::new();
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-
-        // Strip list-style prefixes: - , + , *
-        let trimmed = trimmed
-            .strip_prefix("- ")
-            .or_else(|| trimmed.strip_prefix("+ "))
-            .or_else(|| trimmed.strip_prefix("* "))
-            .unwrap_or(trimmed);
-
-        if trimmed.is_empty() {
-            continue;
-        }
-
-        // Strip trailing parenthetical metadata like (icon: ..., pos: ...) FIRST,
-        // before any other parsing, so that colons inside parens don't confuse
-        // the node-definition vs edge detection.
-        let trimmed = strip_trailing_parens(trimmed);
-
-        if let Some(arrow_pos) = trimmed.find(" -> ") {
-            let from = trimmed[..arrow_pos].trim().to_string();
-            let rest = &trimmed[arrow_pos + 4..];
-            let (to, label) = if let Some(colon_pos) = rest.find(": ") {
-                (
-                    rest[..colon_pos].trim().to_string(),
-                    rest[colon_pos + 2..].trim().to_string(),
-                )
-            } else {
-                (rest.trim().to_string(), String::new())
-            };
-
-            // Auto-create nodes for edges
-            if !seen_nodes.contains_key(&from) {
-                seen_nodes.insert(from.clone(), nodes.len());
-                nodes.push(DiagramNode {
-                    name: from.clone(),
-                    label: from.clone(),
-                });
-            }
-            if !seen_nodes.contains_key(&to) {
-                seen_nodes.insert(to.clone(), nodes.len());
-                nodes.push(DiagramNode {
-                    name: to.clone(),
-                    label: to.clone(),
-                });
-            }
-
-            edges.push(DiagramEdge { from, to, label });
-        } else if let Some(colon_pos) = trimmed.find(": ") {
-            let name = trimmed[..colon_pos].trim().to_string();
-            let label = trimmed[colon_pos + 2..].trim().to_string();
-
-            if let Some(&idx) = seen_nodes.get(&name) {
-                nodes[idx].label = label;
-            } else {
-                seen_nodes.insert(name.clone(), nodes.len());
-                nodes.push(DiagramNode { name, label });
-            }
-        } else {
-            // Plain node name with no label (e.g. after parens were stripped)
-            let name = trimmed.trim().to_string();
-            if !name.is_empty() && !seen_nodes.contains_key(&name) {
-                seen_nodes.insert(name.clone(), nodes.len());
-                nodes.push(DiagramNode {
-                    name: name.clone(),
-                    label: name,
-                });
-            }
-        }
-    }
+    #[test]
+    fn morph_captures_keep_the_gutter() {
+        let ctx = egui::Context::default();
+        let _ = ctx.run(Default::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let gutter_text = |code: &str, options: CodeOptions, file_lines: &[usize]| {
+                    morph::set_capturing(ctx, true);
+                    draw_code_block(
+                        ui,
+                        code,
+                        None,
+                        &[],
+                        options,
+                        file_lines,
+                        0,
+                        &Theme::light(),
+                        Pos2::ZERO,
+                        400.0,
+                        1.0,
+                        1.0,
+                    );
+                    morph::set_capturing(ctx, false);
+
+                    let captures = morph::take(ctx);
+                    assert_eq!(captures.len(), 1);
+                    let gutter = captures[0].gutter.clone();
+                    // A tint and a bar for each added or removed line
+                    let rects = gutter.iter().filter(|s| matches!(s, Shape::Rect(_)));
+                    let changed = code.lines().filter(|l| l.starts_with(['+', '-'])).count();
+                    assert_eq!(rects.count(), if options.diff { changed * 2 } else { 0 });
+                    gutter
+                        .iter()
+                        .filter_map(|shape| match shape {
+                            Shape::Text(text) => Some(text.galley.text().to_string()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                };
 
-    (nodes, edges)
-}
+                let diff = CodeOptions {
+                    line_numbers: Some(10),
+                    explicit_start: true,
+                    diff: true,
+                };
+                assert_eq!(gutter_text("+a\n b\n-c", diff, &[]), ["10", "+", "11", "-"]);
 
-/// Strip trailing parenthetical metadata like `(icon: database, pos: 1,1)` from a line.
-fn strip_trailing_parens(s: &str) -> &str {
-    let trimmed = s.trim_end();
-    if trimmed.ends_with(')') {
-        if let Some(paren_start) = trimmed.rfind('(') {
-            // Only strip if there's whitespace before the paren (it's metadata, not part of the name)
-            if paren_start > 0 && trimmed.as_bytes()[paren_start - 1] == b' ' {
-                return trimmed[..paren_start].trim_end();
-            }
-        }
+                // Included code shows its lines in the file, unless a start is given
+                let numbered = CodeOptions {
+                    line_numbers: Some(1),
+                    ..Default::default()
+                };
+                assert_eq!(
+                    gutter_text("a\nb\nc", numbered, &[4, 6, 8]),
+                    ["4", "6", "8"]
+                );
+                let started = CodeOptions {
+                    explicit_start: true,
+                    ..numbered
+                };
+                assert_eq!(gutter_text("a\nb\nc", started, &[4, 6, 8]), ["1", "2", "3"]);
+            });
+        });
     }
-    trimmed
 }
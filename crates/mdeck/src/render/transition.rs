@@ -2,12 +2,17 @@ use eframe::egui;
 use std::time::Instant;
 
 const TRANSITION_DURATION: f32 = 0.3;
+/// Morphs run longer so moving code can be followed.
+const MORPH_DURATION: f32 = 0.7;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TransitionKind {
     Fade,
     SlideHorizontal,
     Spatial,
+    /// Code blocks animate token by token into the next slide's version; the
+    /// rest of the slide fades.
+    Morph,
     None,
 }
 
@@ -41,13 +46,20 @@ impl ActiveTransition {
         }
     }
 
+    fn duration(&self) -> f32 {
+        match self.kind {
+            TransitionKind::Morph => MORPH_DURATION,
+            _ => TRANSITION_DURATION,
+        }
+    }
+
     pub fn progress(&self) -> f32 {
-        let raw = (self.start.elapsed().as_secs_f32() / TRANSITION_DURATION).clamp(0.0, 1.0);
+        let raw = (self.start.elapsed().as_secs_f32() / self.duration()).clamp(0.0, 1.0);
         ease_in_out(raw)
     }
 
     pub fn is_complete(&self) -> bool {
-        self.start.elapsed().as_secs_f32() >= TRANSITION_DURATION
+        self.start.elapsed().as_secs_f32() >= self.duration()
     }
 
     /// Compute the normalized direction vector for a spatial transition.
@@ -70,6 +82,7 @@ impl TransitionKind {
             "fade" => Self::Fade,
             "slide" => Self::SlideHorizontal,
             "spatial" => Self::Spatial,
+            "morph" => Self::Morph,
             "none" => Self::None,
             _ => Self::SlideHorizontal,
         }
@@ -87,7 +100,7 @@ impl TransitionKind {
         let progress = transition.progress();
 
         match self {
-            TransitionKind::Fade => {
+            TransitionKind::Fade | TransitionKind::Morph => {
                 draw_fn(transition.from, rect, 1.0 - progress);
                 draw_fn(transition.to, rect, progress);
             }
@@ -155,6 +168,7 @@ mod tests {
             TransitionKind::SlideHorizontal
         );
         assert_eq!(TransitionKind::from_name("none"), TransitionKind::None);
+        assert_eq!(TransitionKind::from_name("morph"), TransitionKind::Morph);
         // Unknown falls back to SlideHorizontal
         assert_eq!(
             TransitionKind::from_name("unknown"),
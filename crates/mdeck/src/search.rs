@@ -0,0 +1,345 @@
+use std::ops::Range;
+
+use crate::parser::{Block, ListItem, Presentation, Slide, inlines_to_text};
+
+/// Most matches listed in the jump box.
+pub const MAX_MATCHES: usize = 5;
+
+/// Slides matching what was typed into the jump box, best first: the slide
+/// with that number when only digits were typed, otherwise slides whose
+/// heading or `@id` fuzzily matches the text.
+pub fn slide_matches(presentation: &Presentation, query: &str) -> Vec<usize> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    if query.chars().all(|c| c.is_ascii_digit()) {
+        return match query.parse::<usize>() {
+            Ok(number) if (1..=presentation.slides.len()).contains(&number) => vec![number - 1],
+            _ => Vec::new(),
+        };
+    }
+
+    let mut scored: Vec<(u32, usize)> = presentation
+        .slides
+        .iter()
+        .enumerate()
+        .filter_map(|(index, slide)| {
+            let title = slide.title().and_then(|t| fuzzy_score(query, &t));
+            let id = slide.id().and_then(|id| fuzzy_score(query, id));
+            title.max(id).map(|score| (score, index))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .take(MAX_MATCHES)
+        .map(|(_, index)| index)
+        .collect()
+}
+
+/// Score `text` against `query` when every character of the query appears in
+/// it in order, ignoring case. Runs of consecutive characters and matches at
+/// the start of a word score higher; each place the first character appears
+/// is tried, keeping the best.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let query: Vec<char> = query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let first = *query.first()?;
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| score_from(&query, &text, start))
+        .max()
+}
+
+/// Greedy match of `query` in `text` with its first character at `start`.
+fn score_from(query: &[char], text: &[char], start: usize) -> Option<u32> {
+    let mut score = 0;
+    let mut next = start;
+    let mut previous: Option<usize> = None;
+    for &q in query {
+        let found = next + text[next..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// Where on a slide a search matched, in ranking order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Field {
+    Heading,
+    Body,
+    Code,
+    Notes,
+}
+
+impl Field {
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Heading => "heading",
+            Field::Body => "text",
+            Field::Code => "code",
+            Field::Notes => "notes",
+        }
+    }
+}
+
+/// A slide matching a full-text search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub slide: usize,
+    /// The best place it matched; headings rank above body text, code and notes.
+    pub field: Field,
+    /// Number of matches on the slide.
+    pub count: usize,
+    /// Text around the first match in `field`, on one line.
+    pub snippet: String,
+    /// Byte range of the match within `snippet`.
+    pub matched: Range<usize>,
+}
+
+/// Search headings, body text, code and speaker notes of every slide for
+/// `query`, ignoring case. Slides are ranked by where they matched, then by
+/// how often, then by position in the deck.
+pub fn full_text(presentation: &Presentation, query: &str) -> Vec<Hit> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut hits: Vec<Hit> = presentation
+        .slides
+        .iter()
+        .enumerate()
+        .filter_map(|(index, slide)| {
+            let texts = slide_texts(slide);
+            let count = texts.iter().map(|(_, t)| find_all(t, query).len()).sum();
+            let (field, text, range) = texts.iter().find_map(|(field, text)| {
+                let range = find_all(text, query).into_iter().next()?;
+                Some((*field, text, range))
+            })?;
+            let (snippet, matched) = snippet(text, range);
+            Some(Hit {
+                slide: index,
+                field,
+                count,
+                snippet,
+                matched,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        (a.field, std::cmp::Reverse(a.count), a.slide).cmp(&(
+            b.field,
+            std::cmp::Reverse(b.count),
+            b.slide,
+        ))
+    });
+    hits
+}
+
+/// Byte ranges of every occurrence of `query` in `text`, ignoring case.
+pub fn find_all(text: &str, query: &str) -> Vec<Range<usize>> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match match_len(&chars[i..], &query) {
+            Some(len) => {
+                let end = chars.get(i + len).map_or(text.len(), |&(byte, _)| byte);
+                ranges.push(chars[i].0..end);
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    ranges
+}
+
+/// How many characters at the start of `chars` spell `query` once lowercased.
+fn match_len(chars: &[(usize, char)], query: &[char]) -> Option<usize> {
+    let mut q = 0;
+    for (n, &(_, c)) in chars.iter().enumerate() {
+        for lower in c.to_lowercase() {
+            if query.get(q) != Some(&lower) {
+                return None;
+            }
+            q += 1;
+        }
+        if q == query.len() {
+            return Some(n + 1);
+        }
+    }
+    None
+}
+
+/// The searchable text of a slide, one entry per heading, paragraph, list
+/// item, table cell, code block and so on, in ranking order.
+fn slide_texts(slide: &Slide) -> Vec<(Field, String)> {
+    fn list(items: &[ListItem], out: &mut Vec<(Field, String)>) {
+        for item in items {
+            out.push((Field::Body, inlines_to_text(&item.inlines)));
+            list(&item.children, out);
+        }
+    }
+    let mut texts = Vec::new();
+    for block in &slide.blocks {
+        match block {
+            Block::Heading { inlines, .. } => {
+                texts.push((Field::Heading, inlines_to_text(inlines)))
+            }
+            Block::Paragraph { inlines } | Block::BlockQuote { inlines } => {
+                texts.push((Field::Body, inlines_to_text(inlines)));
+            }
+            Block::List { items, .. } => list(items, &mut texts),
+            Block::Table { headers, rows, .. } => texts.extend(
+                headers
+                    .iter()
+                    .chain(rows.iter().flatten())
+                    .map(|cell| (Field::Body, inlines_to_text(cell))),
+            ),
+            Block::Image { alt, .. } => texts.push((Field::Body, alt.clone())),
+            Block::CodeBlock { code, .. } => texts.push((Field::Code, code.clone())),
+            Block::Diagram { content, .. } => texts.push((Field::Code, content.clone())),
+            Block::Math { tex } => texts.push((Field::Code, tex.clone())),
+            Block::HorizontalRule | Block::ColumnSeparator => {}
+        }
+    }
+    if let Some(notes) = &slide.notes {
+        texts.push((Field::Notes, notes.clone()));
+    }
+    texts.sort_by_key(|(field, _)| *field);
+    texts
+}
+
+/// Up to 30 characters either side of `range` in `text`, with line breaks
+/// and runs of spaces collapsed, returning the snippet and where the match
+/// is in it.
+fn snippet(text: &str, range: Range<usize>) -> (String, Range<usize>) {
+    const CONTEXT: usize = 30;
+    let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let before = &text[..range.start];
+    let cut = before
+        .char_indices()
+        .rev()
+        .nth(CONTEXT - 1)
+        .map(|(i, _)| i)
+        .filter(|&i| i > 0);
+    let mut head = collapse(&before[cut.unwrap_or(0)..]);
+    if before.ends_with(char::is_whitespace) && !head.is_empty() {
+        head.push(' ');
+    }
+    if cut.is_some() {
+        head.insert(0, '\u{2026}');
+    }
+
+    let after = &text[range.end..];
+    let cut = after.char_indices().nth(CONTEXT).map(|(i, _)| i);
+    let mut tail = collapse(&after[..cut.unwrap_or(after.len())]);
+    if after.starts_with(char::is_whitespace) && !tail.is_empty() {
+        tail.insert(0, ' ');
+    }
+    if cut.is_some() {
+        tail.push('\u{2026}');
+    }
+
+    let matched = collapse(&text[range]);
+    let start = head.len();
+    (
+        format!("{head}{matched}{tail}"),
+        start..start + matched.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use std::path::Path;
+
+    #[test]
+    fn fuzzy_prefers_runs_and_word_starts() {
+        assert!(fuzzy_score("xyz", "Architecture").is_none());
+        assert!(fuzzy_score("ARCH", "architecture").is_some());
+        let run = fuzzy_score("arch", "Architecture").unwrap();
+        let scattered = fuzzy_score("arch", "a rich cake").unwrap();
+        assert!(run > scattered);
+        let word_start = fuzzy_score("ov", "Project overview").unwrap();
+        let inside = fuzzy_score("ov", "Removal").unwrap();
+        assert!(word_start > inside);
+    }
+
+    #[test]
+    fn numbers_and_headings_find_slides() {
+        let pres = parser::parse(
+            "# Intro\n\nHi\n\n---\n\n# Architecture\n\nx\n\n---\n\n@id: qa\n\n# Questions\n\ny",
+            Path::new("."),
+        );
+        assert_eq!(slide_matches(&pres, "2"), vec![1]);
+        assert!(slide_matches(&pres, "4").is_empty());
+        assert!(slide_matches(&pres, "0").is_empty());
+        assert_eq!(slide_matches(&pres, "arch"), vec![1]);
+        assert_eq!(slide_matches(&pres, "qa"), vec![2]);
+        assert!(slide_matches(&pres, "zzz").is_empty());
+    }
+
+    #[test]
+    fn find_all_ignores_case() {
+        assert_eq!(
+            find_all("Rust and rust, RUST", "rust"),
+            vec![0..4, 9..13, 15..19]
+        );
+        assert_eq!(find_all("Straße", "SSE").len(), 0);
+        assert_eq!(find_all("ÉCOLE école", "école"), vec![0..6, 7..13]);
+        assert!(find_all("abc", "").is_empty());
+    }
+
+    #[test]
+    fn full_text_ranks_headings_first() {
+        let pres = parser::parse(
+            "# Intro\n\nWe use a cache here.\n\n???\nMention the cache size.\n\n---\n\n\
+             # Cache design\n\n- Cache keys\n\n---\n\n# Code\n\n```rust\nlet cache = 1;\n```",
+            Path::new("."),
+        );
+        let hits = full_text(&pres, "CACHE");
+        let order: Vec<(usize, Field, usize)> =
+            hits.iter().map(|h| (h.slide, h.field, h.count)).collect();
+        assert_eq!(
+            order,
+            vec![
+                (1, Field::Heading, 2),
+                (0, Field::Body, 2),
+                (2, Field::Code, 1)
+            ]
+        );
+        assert_eq!(hits[1].snippet, "We use a cache here.");
+        assert_eq!(&hits[1].snippet[hits[1].matched.clone()], "cache");
+        assert!(full_text(&pres, "missing").is_empty());
+    }
+
+    #[test]
+    fn snippets_are_trimmed_to_context() {
+        let text = format!("{} needle {}", "a ".repeat(40), "b\n".repeat(40));
+        let start = text.find("needle").unwrap();
+        let (snippet, matched) = snippet(&text, start..start + 6);
+        assert!(snippet.starts_with('\u{2026}') && snippet.ends_with('\u{2026}'));
+        assert!(!snippet.contains('\n'));
+        assert_eq!(&snippet[matched], "needle");
+    }
+}
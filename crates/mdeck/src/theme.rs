@@ -1,4 +1,31 @@
-use eframe::egui::Color32;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use anyhow::Result;
+use eframe::egui::{self, Color32};
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::parser::background::parse_hex_color;
+use crate::parser::{Presentation, PresentationMeta};
+
+/// Names of the themes compiled into mdeck.
+pub const BUILT_IN: [&str; 2] = ["light", "dark"];
+
+/// Font family used for headings when a theme sets a heading font.
+const HEADING_FAMILY: &str = "heading";
+
+/// User themes loaded from YAML files, keyed by name.
+static USER_THEMES: LazyLock<RwLock<BTreeMap<String, UserTheme>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// A theme loaded from a YAML file.
+#[derive(Debug, Clone)]
+pub struct UserTheme {
+    pub theme: Theme,
+    pub path: PathBuf,
+}
 
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -9,11 +36,27 @@ pub struct Theme {
     pub accent: Color32,
     pub code_background: Color32,
     pub code_foreground: Color32,
+    /// Background of every other table row.
+    pub table_stripe: Color32,
+    /// Background of table header rows, if any.
+    pub table_header_background: Option<Color32>,
     pub h1_size: f32,
     pub h2_size: f32,
     pub h3_size: f32,
     pub body_size: f32,
     pub code_size: f32,
+    /// Syntect theme used for code blocks, set by `@code-theme`
+    pub code_theme: Option<String>,
+    pub fonts: ThemeFonts,
+}
+
+/// Font files set by a user theme. Fonts are installed for the whole
+/// presentation, so only the frontmatter theme's fonts are used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThemeFonts {
+    pub heading: Option<PathBuf>,
+    pub body: Option<PathBuf>,
+    pub code: Option<PathBuf>,
 }
 
 impl Theme {
@@ -26,11 +69,15 @@ impl Theme {
             accent: Color32::from_rgb(0x52, 0x94, 0xE2),
             code_background: Color32::from_rgb(0x2D, 0x2D, 0x2D),
             code_foreground: Color32::from_rgb(0xD4, 0xD4, 0xD4),
+            table_stripe: Color32::from_rgba_unmultiplied(0xFF, 0xFF, 0xFF, 0x0C),
+            table_header_background: None,
             h1_size: 96.0,
             h2_size: 72.0,
             h3_size: 52.0,
             body_size: 44.0,
             code_size: 30.0,
+            code_theme: None,
+            fonts: ThemeFonts::default(),
         }
     }
 
@@ -43,27 +90,65 @@ impl Theme {
             accent: Color32::from_rgb(0x0F, 0x34, 0x60),
             code_background: Color32::from_rgb(0xF5, 0xF5, 0xF5),
             code_foreground: Color32::from_rgb(0x33, 0x33, 0x33),
+            table_stripe: Color32::from_rgba_unmultiplied(0x0F, 0x34, 0x60, 0x0C),
+            table_header_background: None,
             h1_size: 96.0,
             h2_size: 72.0,
             h3_size: 52.0,
             body_size: 44.0,
             code_size: 30.0,
+            code_theme: None,
+            fonts: ThemeFonts::default(),
         }
     }
 
-    pub fn from_name(name: &str) -> Self {
+    /// Find a built-in or user theme by name.
+    pub fn lookup(name: &str) -> Option<Self> {
         match name {
-            "dark" => Self::dark(),
-            _ => Self::light(),
+            "light" => Some(Self::light()),
+            "dark" => Some(Self::dark()),
+            _ => USER_THEMES
+                .read()
+                .unwrap()
+                .get(name)
+                .map(|t| t.theme.clone()),
         }
     }
 
+    /// Find a theme by name, falling back to light. Unknown names are
+    /// reported by [`check_themes`].
+    pub fn from_name(name: &str) -> Self {
+        Self::lookup(name).unwrap_or_else(Self::light)
+    }
+
+    /// Build the theme selected by the frontmatter (`@theme`, `@code-theme`).
+    pub fn from_meta(meta: &PresentationMeta) -> Self {
+        Self::from_name(meta.theme.as_deref().unwrap_or("light"))
+            .with_code_theme(meta.code_theme.as_deref())
+    }
+
     pub fn toggled(&self) -> Self {
-        if self.name == "dark" {
+        let toggled = if self.is_dark() {
             Self::light()
         } else {
             Self::dark()
+        };
+        toggled.with_code_theme(self.code_theme.as_deref())
+    }
+
+    /// Override the syntax highlighting theme. `None` keeps the current one.
+    pub fn with_code_theme(mut self, name: Option<&str>) -> Self {
+        if let Some(name) = name {
+            self.code_theme = Some(name.to_string());
         }
+        self
+    }
+
+    /// Whether the background is dark, judged by its luminance.
+    pub fn is_dark(&self) -> bool {
+        let [r, g, b, _] = self.background.to_array();
+        let luminance = 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32;
+        luminance < 128.0
     }
 
     /// Apply opacity to a color
@@ -80,12 +165,406 @@ impl Theme {
         }
     }
 
-    /// Return the syntect theme name that matches this presentation theme.
+    /// Return the syntect theme name used for code blocks: the `@code-theme`
+    /// override if set, otherwise the one matching this presentation theme.
     pub fn syntect_theme_name(&self) -> &str {
-        if self.name == "dark" {
+        self.code_theme
+            .as_deref()
+            .unwrap_or_else(|| self.default_syntect_theme_name())
+    }
+
+    /// Return the syntect theme name that matches this presentation theme.
+    pub fn default_syntect_theme_name(&self) -> &str {
+        if self.is_dark() {
             "base16-ocean.dark"
         } else {
             "InspiredGitHub"
         }
     }
 }
+
+/// Directories searched for user themes: `themes/` next to the deck, then
+/// `~/.config/mdeck/themes`.
+pub fn theme_dirs(base_path: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![base_path.join("themes")];
+    if let Some(dir) = Config::themes_dir() {
+        dirs.push(dir);
+    }
+    dirs
+}
+
+/// The theme directories and the YAML and `.tmTheme` files in them, for live
+/// reload. Saving a theme changes its file; adding or removing one changes
+/// its directory.
+pub fn theme_files(base_path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in theme_dirs(base_path) {
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            files.extend(
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| {
+                        p.extension()
+                            .is_some_and(|ext| ext == "yaml" || ext == "yml" || ext == "tmTheme")
+                    }),
+            );
+        }
+        files.push(dir);
+    }
+    files
+}
+
+/// Load YAML themes (`*.yaml`, `*.yml`) from the theme directories. Each
+/// theme is registered under its file stem, so `themes/ocean.yaml` is
+/// selected with `@theme: ocean`. Deck-local themes win over the config dir.
+/// Calling this again starts over, so edited, added and removed files are
+/// picked up.
+pub fn load_user_themes(base_path: &Path) {
+    *USER_THEMES.write().unwrap() = read_themes(&theme_dirs(base_path));
+}
+
+/// The YAML themes in `dirs`, keyed by file stem. Earlier directories win
+/// over later ones.
+pub fn read_themes(dirs: &[PathBuf]) -> BTreeMap<String, UserTheme> {
+    let mut themes = BTreeMap::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|ext| ext == "yaml" || ext == "yml")
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if BUILT_IN.contains(&name) {
+                eprintln!(
+                    "Warning: {} uses the name of a built-in theme and was skipped",
+                    path.display()
+                );
+                continue;
+            }
+            if themes.contains_key(name) {
+                continue;
+            }
+            match load_theme_file(&path, name) {
+                Ok(theme) => {
+                    themes.insert(name.to_string(), UserTheme { theme, path });
+                }
+                Err(e) => eprintln!("Warning: failed to load theme {}: {e}", path.display()),
+            }
+        }
+    }
+    themes
+}
+
+/// All available theme names, built-in first, with the file each user theme
+/// was loaded from.
+pub fn available() -> Vec<(String, Option<PathBuf>)> {
+    let mut all: Vec<(String, Option<PathBuf>)> =
+        BUILT_IN.iter().map(|n| (n.to_string(), None)).collect();
+    all.extend(
+        USER_THEMES
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, t)| (name.clone(), Some(t.path.clone()))),
+    );
+    all
+}
+
+/// Check every `@theme` in the presentation (frontmatter and slides) and
+/// return a warning for each name that doesn't match a known theme.
+pub fn check_themes(presentation: &Presentation) -> Vec<String> {
+    let names = presentation.meta.theme.iter().chain(
+        presentation
+            .slides
+            .iter()
+            .filter_map(|s| s.settings.theme.as_ref()),
+    );
+
+    let mut warnings: Vec<String> = Vec::new();
+    for name in names {
+        if Theme::lookup(name).is_some() {
+            continue;
+        }
+        let known: Vec<String> = available().into_iter().map(|(n, _)| n).collect();
+        let warning = format!(
+            "Unknown theme '{name}', using light. Available: {}",
+            known.join(", ")
+        );
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    warnings
+}
+
+/// Install the theme's fonts into the egui context, replacing any fonts from
+/// a previous theme. Body and code fonts take priority in the proportional and
+/// monospace families; the heading font gets its own family. The monospace
+/// fonts back up the proportional ones, which lack many math symbols. Takes
+/// effect on the next frame.
+pub fn install_fonts(ctx: &egui::Context, theme: &Theme) {
+    let mut fonts = egui::FontDefinitions::default();
+    let mut load = |name: &str, path: &Option<PathBuf>| -> bool {
+        let Some(path) = path else {
+            return false;
+        };
+        match std::fs::read(path) {
+            Ok(bytes) => {
+                fonts.font_data.insert(
+                    name.to_string(),
+                    std::sync::Arc::new(egui::FontData::from_owned(bytes)),
+                );
+                true
+            }
+            Err(e) => {
+                eprintln!("Warning: failed to load font {}: {e}", path.display());
+                false
+            }
+        }
+    };
+
+    let body = load("theme-body", &theme.fonts.body);
+    let code = load("theme-code", &theme.fonts.code);
+    let heading = load("theme-heading", &theme.fonts.heading);
+
+    if body {
+        fonts
+            .families
+            .entry(egui::FontFamily::Proportional)
+            .or_default()
+            .insert(0, "theme-body".to_string());
+    }
+    if code {
+        fonts
+            .families
+            .entry(egui::FontFamily::Monospace)
+            .or_default()
+            .insert(0, "theme-code".to_string());
+    }
+    // Fall back to the monospace fonts for math symbols the body fonts lack
+    let monospace = fonts
+        .families
+        .get(&egui::FontFamily::Monospace)
+        .cloned()
+        .unwrap_or_default();
+    let proportional = fonts
+        .families
+        .entry(egui::FontFamily::Proportional)
+        .or_default();
+    for name in monospace {
+        if !proportional.contains(&name) {
+            proportional.push(name);
+        }
+    }
+    if heading {
+        // Fall back to the body fonts for glyphs the heading font lacks
+        let mut family = vec!["theme-heading".to_string()];
+        family.extend(
+            fonts
+                .families
+                .get(&egui::FontFamily::Proportional)
+                .cloned()
+                .unwrap_or_default(),
+        );
+        fonts
+            .families
+            .insert(egui::FontFamily::Name(HEADING_FAMILY.into()), family);
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// The font family for headings: the theme's heading font once it has been
+/// installed, otherwise the regular proportional family.
+pub fn heading_family(ctx: &egui::Context) -> egui::FontFamily {
+    let family = egui::FontFamily::Name(HEADING_FAMILY.into());
+    if ctx.fonts(|f| f.definitions().families.contains_key(&family)) {
+        family
+    } else {
+        egui::FontFamily::Proportional
+    }
+}
+
+/// Theme file format. Every field is optional; unset values come from `base`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    /// Built-in theme to start from: `light` (default) or `dark`
+    base: Option<String>,
+    colors: ThemeFileColors,
+    sizes: ThemeFileSizes,
+    fonts: ThemeFileFonts,
+    /// Syntect theme for code blocks
+    code_theme: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFileColors {
+    background: Option<String>,
+    foreground: Option<String>,
+    heading: Option<String>,
+    accent: Option<String>,
+    code_background: Option<String>,
+    code_foreground: Option<String>,
+    table_stripe: Option<String>,
+    table_header: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFileSizes {
+    h1: Option<f32>,
+    h2: Option<f32>,
+    h3: Option<f32>,
+    body: Option<f32>,
+    code: Option<f32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFileFonts {
+    heading: Option<PathBuf>,
+    body: Option<PathBuf>,
+    code: Option<PathBuf>,
+}
+
+fn load_theme_file(path: &Path, name: &str) -> Result<Theme> {
+    let contents = std::fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    parse_theme(&contents, name, dir)
+}
+
+/// Parse a YAML theme. Font paths are resolved relative to `dir`.
+fn parse_theme(yaml: &str, name: &str, dir: &Path) -> Result<Theme> {
+    let file: ThemeFile = serde_yaml::from_str(yaml)?;
+
+    let mut theme = match file.base.as_deref() {
+        None | Some("light") => Theme::light(),
+        Some("dark") => Theme::dark(),
+        Some(other) => anyhow::bail!("Invalid base: {other}. Must be 'light' or 'dark'."),
+    };
+    theme.name = name.to_string();
+
+    let parse_color = |value: &str| {
+        let [r, g, b, a] =
+            parse_hex_color(value).ok_or_else(|| anyhow::anyhow!("Invalid color: {value}"))?;
+        anyhow::Ok(Color32::from_rgba_unmultiplied(r, g, b, a))
+    };
+    let colors = [
+        (&file.colors.background, &mut theme.background),
+        (&file.colors.foreground, &mut theme.foreground),
+        (&file.colors.heading, &mut theme.heading_color),
+        (&file.colors.accent, &mut theme.accent),
+        (&file.colors.code_background, &mut theme.code_background),
+        (&file.colors.code_foreground, &mut theme.code_foreground),
+        (&file.colors.table_stripe, &mut theme.table_stripe),
+    ];
+    for (value, target) in colors {
+        if let Some(value) = value {
+            *target = parse_color(value)?;
+        }
+    }
+    if let Some(value) = &file.colors.table_header {
+        theme.table_header_background = Some(parse_color(value)?);
+    }
+
+    let sizes = [
+        (file.sizes.h1, &mut theme.h1_size),
+        (file.sizes.h2, &mut theme.h2_size),
+        (file.sizes.h3, &mut theme.h3_size),
+        (file.sizes.body, &mut theme.body_size),
+        (file.sizes.code, &mut theme.code_size),
+    ];
+    for (value, target) in sizes {
+        if let Some(value) = value {
+            if value <= 0.0 {
+                anyhow::bail!("Invalid size: {value}. Must be positive.");
+            }
+            *target = value;
+        }
+    }
+
+    theme.fonts = ThemeFonts {
+        heading: file.fonts.heading.map(|p| dir.join(p)),
+        body: file.fonts.body.map(|p| dir.join(p)),
+        code: file.fonts.code.map(|p| dir.join(p)),
+    };
+    theme.code_theme = file.code_theme;
+    Ok(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_theme_over_base() {
+        let yaml = r##"
+base: dark
+colors:
+  background: "#0B1D2A"
+  accent: "#4FC3F7"
+  table_header: "#102635"
+sizes:
+  h1: 120
+fonts:
+  body: fonts/Inter.ttf
+code_theme: Solarized (dark)
+"##;
+        let theme = parse_theme(yaml, "ocean", Path::new("/decks/themes")).unwrap();
+        assert_eq!(theme.name, "ocean");
+        assert_eq!(theme.background, Color32::from_rgb(0x0B, 0x1D, 0x2A));
+        assert_eq!(theme.accent, Color32::from_rgb(0x4F, 0xC3, 0xF7));
+        assert_eq!(
+            theme.table_header_background,
+            Some(Color32::from_rgb(0x10, 0x26, 0x35))
+        );
+        // Unset values come from the base theme
+        assert_eq!(theme.foreground, Theme::dark().foreground);
+        assert_eq!(theme.table_stripe, Theme::dark().table_stripe);
+        assert_eq!(theme.h1_size, 120.0);
+        assert_eq!(theme.h2_size, 72.0);
+        assert_eq!(
+            theme.fonts.body,
+            Some(PathBuf::from("/decks/themes/fonts/Inter.ttf"))
+        );
+        assert_eq!(theme.syntect_theme_name(), "Solarized (dark)");
+        assert!(theme.is_dark());
+    }
+
+    #[test]
+    fn rejects_invalid_theme_files() {
+        let dir = Path::new(".");
+        assert!(parse_theme("colors:\n  background: blue\n", "t", dir).is_err());
+        assert!(parse_theme("base: sepia\n", "t", dir).is_err());
+        assert!(parse_theme("colours: {}\n", "t", dir).is_err());
+    }
+
+    #[test]
+    fn loads_themes_from_deck_folder() {
+        let dir = std::env::temp_dir().join(format!("mdeck-themes-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("themes")).unwrap();
+        std::fs::write(
+            dir.join("themes").join("sunset.yaml"),
+            "colors:\n  background: \"#2B1B17\"\n",
+        )
+        .unwrap();
+
+        let themes = read_themes(&[dir.join("themes")]);
+        let sunset = &themes["sunset"];
+        assert_eq!(sunset.theme.background, Color32::from_rgb(0x2B, 0x1B, 0x17));
+        assert_eq!(sunset.path, dir.join("themes").join("sunset.yaml"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often watched files are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Detects changes to a set of files by polling their modification times.
+///
+/// Polling keeps the watcher dependency-free and works the same on every
+/// platform, including network drives and editors that save via rename.
+pub struct FileWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut watcher = Self {
+            files: Vec::new(),
+            last_poll: Instant::now(),
+        };
+        watcher.set_paths(paths);
+        watcher
+    }
+
+    /// Replace the set of watched files, recording their current state.
+    pub fn set_paths(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        self.files = paths
+            .into_iter()
+            .map(|p| {
+                let mtime = modified(&p);
+                (p, mtime)
+            })
+            .collect();
+    }
+
+    /// Returns `true` if any watched file changed since the last check.
+    /// Checks are rate-limited to [`POLL_INTERVAL`].
+    pub fn poll(&mut self) -> bool {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        self.check()
+    }
+
+    fn check(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last) in &mut self.files {
+            let current = modified(path);
+            if current != *last {
+                *last = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_modification() {
+        let path = std::env::temp_dir().join(format!("mdeck-watch-{}.md", std::process::id()));
+        std::fs::write(&path, "# One").unwrap();
+
+        let mut watcher = FileWatcher::new([path.clone()]);
+        assert!(!watcher.check());
+
+        // Force a distinct mtime even on filesystems with coarse timestamps
+        let later = SystemTime::now() + Duration::from_secs(5);
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(later).unwrap();
+
+        assert!(watcher.check());
+        assert!(!watcher.check());
+        std::fs::remove_file(&path).unwrap();
+    }
+}